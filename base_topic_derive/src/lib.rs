@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse::Parser, parse_macro_input, DeriveInput};
+use syn::{parse::Parser, parse_macro_input, DeriveInput, LitStr};
 
 fn impl_base_topic_trait(ast: DeriveInput) -> TokenStream {
     // generate struct identifier
@@ -80,6 +80,64 @@ pub fn base_topic_derive_macro(item: TokenStream) -> TokenStream {
     impl_base_topic_trait(ast)
 }
 
+fn impl_topic_enum_trait(ast: DeriveInput) -> TokenStream {
+    let ident = ast.ident;
+
+    let variants = match ast.data {
+        syn::Data::Enum(data) => data.variants,
+        _ => panic!("TopicEnum can only be derived for enums."),
+    };
+
+    // Variants with a `#[topic("name")]` attribute hold the data of that
+    // topic; other variants, e.g. a default `None`, are left out.
+    let (topic_names, variant_idents): (Vec<String>, Vec<syn::Ident>) = variants
+        .into_iter()
+        .filter_map(|variant| {
+            let topic_name = variant
+                .attrs
+                .iter()
+                .find(|attr| attr.path().is_ident("topic"))?
+                .parse_args::<LitStr>()
+                .expect("Expected #[topic(\"topic_name\")].")
+                .value();
+            match variant.fields {
+                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    Some((topic_name, variant.ident))
+                }
+                _ => panic!("Topic variant {} must hold a single topic.", variant.ident),
+            }
+        })
+        .unzip();
+
+    quote!(
+        impl ::salobj::topics::topic_enum::TopicEnum for #ident {
+            fn get_topic_names() -> Vec<&'static str> {
+                vec![#(#topic_names),*]
+            }
+            fn from_topic_data(
+                topic_name: &str,
+                data: &::salobj::topics::topic_enum::apache_avro::types::Value,
+            ) -> Option<Result<Self, ::salobj::topics::topic_enum::apache_avro::Error>> {
+                match topic_name {
+                    #(#topic_names => Some(
+                        ::salobj::topics::topic_enum::apache_avro::from_value(data)
+                            .map(Self::#variant_idents)
+                    ),)*
+                    _ => None,
+                }
+            }
+        }
+    )
+    .into()
+}
+
+#[proc_macro_derive(TopicEnum, attributes(topic))]
+pub fn topic_enum_derive_macro(item: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(item).unwrap();
+
+    impl_topic_enum_trait(ast)
+}
+
 #[proc_macro_attribute]
 pub fn add_sal_topic_fields(_args: TokenStream, input: TokenStream) -> TokenStream {
     let mut ast = parse_macro_input!(input as DeriveInput);
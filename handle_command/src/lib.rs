@@ -6,6 +6,25 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
 use syn::{parse_macro_input, LitStr, Result, Token};
 
+/// Dispatch a command received by a CSC control loop to its handler.
+///
+/// Takes the names of the commands to handle, e.g. `"start"`, and calls
+/// `self.do_start(&data, ack_channel)` for `command_start`. `exitControl`
+/// is always handled, by `self.do_exit_control`, and ends the loop if the
+/// command succeeds. Other commands are acknowledged as not implemented.
+///
+/// The expansion must be placed inside a loop, in a function returning a
+/// `Result`, with in scope:
+///
+/// * `data`, the received command, with the fields `name: String`,
+///   `data: apache_avro::types::Value` and `cmdtype: i32`, the command type
+///   sent back in the `cmdtype` field of the acks.
+/// * `ack_channel`, a `tokio::sync::mpsc::Sender<CommandAck>`.
+///
+/// Each handler returns the ack and the ack channel back. `self` must also
+/// provide `get_exit_control_grace_period`, as `BaseCSC` does. The crate
+/// using the macro must depend on `salobj`, `apache_avro`, `tokio` and
+/// `log`.
 #[proc_macro]
 pub fn handle_command(input: TokenStream) -> TokenStream {
     let items = parse_macro_input!(input as MyMacroInput);
@@ -47,7 +66,7 @@ fn expand_my_macro(input: MyMacroInput) -> TokenStream2 {
         quote! {
             else if data.name == format!("command_{}", #item_value) {
                 let (command_ack, ack_channel) = self.#varname(&data, ack_channel).await?;
-                let _ = ack_channel.send(command_ack).await;
+                let _ = ack_channel.send(command_ack.with_cmdtype(data.cmdtype)).await;
             }
         }
     });
@@ -56,21 +75,29 @@ fn expand_my_macro(input: MyMacroInput) -> TokenStream2 {
         if data.name == "command_exitControl" {
             let (command_ack, ack_channel) = self.do_exit_control(&data, ack_channel).await?;
             let command_ack_is_good = command_ack.is_good();
-            let _ = ack_channel.send(command_ack).await;
-            sleep(Duration::from_secs(1)).await;
+            let _ = ack_channel.send(command_ack.with_cmdtype(data.cmdtype)).await;
+            ::tokio::time::sleep(self.get_exit_control_grace_period()).await;
             if command_ack_is_good {
                 break;
             }
         }
         #(#code)*
             else {
-            let any = from_value::<ExitControl>(&data.data).unwrap();
-            let command_ack = CommandAck::make_failed(
+            // Only the private fields are needed to build the ack, so decode
+            // into an empty topic and never fail on the command payload.
+            let any = ::apache_avro::from_value::<::salobj::generics::empty_topic::EmptyTopic>(
+                &data.data,
+            )
+            .unwrap_or_else(|error| {
+                ::log::warn!("Failed to decode {} payload: {error}.", data.name);
+                ::salobj::generics::empty_topic::EmptyTopic::default()
+            });
+            let command_ack = ::salobj::utils::command_ack::CommandAck::make_failed(
                 any,
                 1,
                 &format!("Command {} not implemented.", data.name),
             );
-            let _ = ack_channel.send(command_ack).await;
+            let _ = ack_channel.send(command_ack.with_cmdtype(data.cmdtype)).await;
             continue;
         }
 
@@ -14,16 +14,71 @@ use crate::{
     topics::topic_info::{self, TopicInfo},
     utils::xml_utils::convert_sal_name_to_topic_name,
 };
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 extern crate serde;
 extern crate serde_xml_rs;
 
+/// Generic topics that all components must define.
+pub(crate) const MANDATORY_GENERICS: [&str; 3] = [
+    "logevent_heartbeat",
+    "logevent_summaryState",
+    "logevent_softwareVersions",
+];
+
+/// Field types supported in topic definitions.
+const SUPPORTED_TYPES: [&str; 6] = ["boolean", "int", "long", "float", "double", "string"];
+
+/// A problem found in a component interface.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LintWarning {
+    /// A mandatory generic topic is not defined.
+    MissingGeneric(String),
+    /// A topic has more than one field with the same name.
+    DuplicateField { topic: String, field: String },
+    /// A field has a type that is not supported.
+    UnsupportedType {
+        topic: String,
+        field: String,
+        field_type: String,
+    },
+    /// The topic schema could not be parsed.
+    InvalidSchema { topic: String, error: String },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintWarning::MissingGeneric(topic) => {
+                write!(f, "Mandatory generic topic {topic} is missing.")
+            }
+            LintWarning::DuplicateField { topic, field } => {
+                write!(f, "Field {field} is duplicated in {topic}.")
+            }
+            LintWarning::UnsupportedType {
+                topic,
+                field,
+                field_type,
+            } => write!(
+                f,
+                "Field {field} in {topic} has unsupported type {field_type}."
+            ),
+            LintWarning::InvalidSchema { topic, error } => {
+                write!(f, "Invalid schema for {topic}: {error}")
+            }
+        }
+    }
+}
+
 /// Information about one SAL component.
 pub struct ComponentInfo {
     name: String,
     topic_subname: String,
-    /// Command acknowledgment topic definition.
-    ack_cmd: topic_info::TopicInfo,
+    /// Command acknowledgment topic definition. Readonly components, that
+    /// have no commands, may not define one.
+    ack_cmd: Option<topic_info::TopicInfo>,
     /// Map with the commands definition. They key is the
     /// (topic name)[crate::sal_info].
     commands: HashMap<String, topic_info::TopicInfo>,
@@ -39,6 +94,7 @@ pub struct ComponentInfo {
 impl ComponentInfo {
     pub fn new(name: &str, topic_subname: &str) -> SalObjResult<ComponentInfo> {
         let sal_subsystem_info = SALSubsystemInfo::new(name)?;
+        let name = &sal_subsystem_info.get_name().to_owned();
 
         let component_commands: HashMap<String, topic_info::TopicInfo> = sal_subsystem_info
             .get_commands(topic_subname)
@@ -46,6 +102,12 @@ impl ComponentInfo {
             .map(|(sal_name, items)| (convert_sal_name_to_topic_name(name, &sal_name), items))
             .collect();
 
+        let ack_cmd = if component_commands.is_empty() {
+            sal_subsystem_info.get_ackcmd(topic_subname).ok()
+        } else {
+            Some(sal_subsystem_info.get_ackcmd(topic_subname)?)
+        };
+
         let component_events: HashMap<String, topic_info::TopicInfo> = sal_subsystem_info
             .get_events(topic_subname)
             .into_iter()
@@ -61,7 +123,7 @@ impl ComponentInfo {
         Ok(ComponentInfo {
             name: String::from(name),
             topic_subname: String::from(topic_subname),
-            ack_cmd: sal_subsystem_info.get_ackcmd(topic_subname)?,
+            ack_cmd,
             commands: component_commands,
             events: component_events,
             telemetry: component_telemetry,
@@ -70,8 +132,13 @@ impl ComponentInfo {
     }
 
     /// Get ackcmd topic info.
-    pub fn get_ackcmd_topic_info(&self) -> &TopicInfo {
-        &self.ack_cmd
+    pub fn get_ackcmd_topic_info(&self) -> Option<&TopicInfo> {
+        self.ack_cmd.as_ref()
+    }
+
+    /// Is this a readonly component, e.g. one without commands?
+    pub fn is_readonly(&self) -> bool {
+        self.commands.is_empty()
     }
 
     /// Get command topic names.
@@ -130,6 +197,97 @@ impl ComponentInfo {
         self.sal_subsystem_info.get_topic_schemas()
     }
 
+    /// Check the component interface for common problems.
+    ///
+    /// Reports missing mandatory generic topics, duplicated field names and
+    /// fields with unsupported types.
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let topic_schemas = self.get_topic_schemas();
+
+        let mut warnings: Vec<LintWarning> = MANDATORY_GENERICS
+            .iter()
+            .filter(|topic_name| !topic_schemas.contains_key(**topic_name))
+            .map(|topic_name| LintWarning::MissingGeneric(topic_name.to_string()))
+            .collect();
+
+        let mut topic_names: Vec<&String> = topic_schemas.keys().collect();
+        topic_names.sort();
+
+        for topic_name in topic_names {
+            match serde_json::from_str::<serde_json::Value>(&topic_schemas[topic_name]) {
+                Ok(schema) => warnings.append(&mut ComponentInfo::lint_fields(topic_name, &schema)),
+                Err(error) => warnings.push(LintWarning::InvalidSchema {
+                    topic: topic_name.to_owned(),
+                    error: error.to_string(),
+                }),
+            }
+        }
+        warnings
+    }
+
+    /// Get the enumerations defined for the component, see
+    /// [SALSubsystemInfo::get_enum_definitions].
+    pub fn get_enum_definitions(&self) -> HashMap<String, Vec<(String, i64)>> {
+        self.sal_subsystem_info.get_enum_definitions()
+    }
+
+    /// Get the topics that were left out of the component because their
+    /// schema cannot be parsed, together with the parsing error.
+    pub fn parse_errors(&self) -> Vec<(String, String)> {
+        self.sal_subsystem_info.get_parse_errors()
+    }
+
+    /// Check the fields of a topic schema.
+    fn lint_fields(topic_name: &str, schema: &serde_json::Value) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+
+        if let Some(fields) = schema["fields"].as_array() {
+            let mut field_names: HashSet<&str> = HashSet::new();
+
+            for field in fields {
+                let field_name = field["name"].as_str().unwrap_or_default();
+
+                if !field_names.insert(field_name) {
+                    warnings.push(LintWarning::DuplicateField {
+                        topic: topic_name.to_owned(),
+                        field: field_name.to_owned(),
+                    });
+                }
+                if !ComponentInfo::is_supported_type(&field["type"]) {
+                    warnings.push(LintWarning::UnsupportedType {
+                        topic: topic_name.to_owned(),
+                        field: field_name.to_owned(),
+                        field_type: field["type"].to_string(),
+                    });
+                }
+            }
+        } else {
+            warnings.push(LintWarning::InvalidSchema {
+                topic: topic_name.to_owned(),
+                error: "No fields defined.".to_owned(),
+            });
+        }
+        warnings
+    }
+
+    /// Is the field type supported?
+    ///
+    /// Supported types are the primitive types in [SUPPORTED_TYPES] and
+    /// arrays of those.
+    fn is_supported_type(field_type: &serde_json::Value) -> bool {
+        match field_type {
+            serde_json::Value::String(field_type) => SUPPORTED_TYPES.contains(&field_type.as_str()),
+            serde_json::Value::Object(field_type) => {
+                field_type.get("type").and_then(|value| value.as_str()) == Some("array")
+                    && field_type
+                        .get("items")
+                        .map(ComponentInfo::is_supported_type)
+                        .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
     // Make avro schema for all topics in the component.
     //
     // Returns Hashmap with topic name as key and [AvroSchema] as value.
@@ -161,8 +319,60 @@ impl ComponentInfo {
 mod tests {
 
     use super::*;
+    use crate::utils::testing::with_env_vars;
     use apache_avro::{types::Record, Schema};
-    use std::collections::HashSet;
+
+    #[test]
+    fn new_resolves_alias_once() {
+        let component_info = with_env_vars(
+            &[(
+                "LSST_COMPONENT_ALIASES",
+                Some("OldTest:Test,Test:NoSuchComponent"),
+            )],
+            || ComponentInfo::new("OldTest", "unit_test"),
+        )
+        .unwrap();
+
+        assert_eq!(component_info.get_component_name(), "Test");
+        assert!(component_info.commands.contains_key("command_setScalars"));
+    }
+
+    #[test]
+    fn get_enum_definitions() {
+        let component_info = ComponentInfo::new("Test", "unit_test").unwrap();
+
+        let enum_definitions = component_info.get_enum_definitions();
+
+        assert_eq!(
+            enum_definitions["Enum"],
+            vec![
+                ("One".to_owned(), 0),
+                ("Two".to_owned(), 1),
+                ("Three".to_owned(), 2)
+            ]
+        );
+        assert_eq!(
+            enum_definitions["ValueEnum"],
+            vec![
+                ("Zero".to_owned(), 0),
+                ("Two".to_owned(), 2),
+                ("Four".to_owned(), 4),
+                ("Five".to_owned(), 5)
+            ]
+        );
+        assert_eq!(
+            enum_definitions["logevent_scalars.int0"],
+            vec![
+                ("Int0Enum_One".to_owned(), 0),
+                ("Int0Enum_Two".to_owned(), 1),
+                ("Int0Enum_Three".to_owned(), 2)
+            ]
+        );
+        assert_eq!(
+            enum_definitions["logevent_arrays.int0"][3],
+            ("Int0ValueEnum_Five".to_owned(), 5)
+        );
+    }
 
     #[test]
     fn create_test_component_info() {
@@ -173,8 +383,15 @@ mod tests {
         assert_eq!(component_info.name, "Test");
         assert_eq!(component_info.topic_subname, "unit_test");
         assert_eq!(component_info.is_indexed(), true);
-        assert_eq!(component_info.ack_cmd.get_topic_name(), "ackcmd");
-        assert_eq!(component_info.ack_cmd.get_sal_name(), "Test_ackcmd");
+        assert_eq!(
+            component_info.ack_cmd.as_ref().unwrap().get_topic_name(),
+            "ackcmd"
+        );
+        assert_eq!(
+            component_info.ack_cmd.as_ref().unwrap().get_sal_name(),
+            "Test_ackcmd"
+        );
+        assert!(!component_info.is_readonly());
         assert_eq!(component_info.get_topic_subname(), "unit_test");
         assert!(
             component_info.commands.contains_key("command_start"),
@@ -191,6 +408,18 @@ mod tests {
         assert!(component_info.telemetry.contains_key("scalars"));
     }
 
+    #[test]
+    fn create_readonly_component_info() {
+        let component_info = ComponentInfo::new("Sensor", "unit_test").unwrap();
+
+        assert!(component_info.is_readonly());
+        assert!(!component_info.is_indexed());
+        assert!(component_info.get_ackcmd_topic_info().is_none());
+        assert!(component_info.get_topic_name_commands().is_empty());
+        assert!(component_info.events.contains_key("logevent_heartbeat"));
+        assert!(component_info.telemetry.contains_key("temperature"));
+    }
+
     #[test]
     fn make_avro_schema() {
         let component_info = ComponentInfo::new("Test", "unit_test").unwrap();
@@ -224,4 +453,54 @@ mod tests {
 
         assert_eq!(record_fields, expected_fields)
     }
+
+    #[test]
+    fn lint() {
+        let component_info = ComponentInfo::new("Test", "unit_test").unwrap();
+
+        assert_eq!(component_info.lint(), Vec::new())
+    }
+
+    #[test]
+    fn parse_errors() {
+        let component_info = ComponentInfo::new("Test", "unit_test").unwrap();
+
+        assert!(component_info.parse_errors().is_empty());
+    }
+
+    #[test]
+    fn parse_errors_broken_component() {
+        let component_info = ComponentInfo::new("Broken", "unit_test").unwrap();
+
+        let parse_errors = component_info.parse_errors();
+
+        assert!(parse_errors
+            .iter()
+            .any(|(topic_name, _)| topic_name == "logevent_malformed"));
+        assert!(!component_info.events.contains_key("logevent_malformed"));
+    }
+
+    #[test]
+    fn lint_broken_component() {
+        let component_info = ComponentInfo::new("Broken", "unit_test").unwrap();
+
+        let warnings = component_info.lint();
+
+        assert!(warnings.contains(&LintWarning::MissingGeneric(
+            "logevent_heartbeat".to_owned()
+        )));
+        assert!(warnings.contains(&LintWarning::MissingGeneric(
+            "logevent_softwareVersions".to_owned()
+        )));
+        assert!(warnings.contains(&LintWarning::DuplicateField {
+            topic: "badTopic".to_owned(),
+            field: "value".to_owned()
+        }));
+        assert!(warnings.contains(&LintWarning::UnsupportedType {
+            topic: "badTopic".to_owned(),
+            field: "lookup".to_owned(),
+            field_type: r#"{"type":"map","values":"int"}"#.to_owned()
+        }));
+        assert_eq!(warnings.len(), 4);
+    }
 }
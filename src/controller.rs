@@ -3,25 +3,60 @@
 //! The [Controller] is a server-side tool to implement components in the system.
 //! They are basically a mirror of the [crate::remote::Remote], in the sense that they receive commands and outputs events and telemetry.
 
-use std::fmt::Debug;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    domain,
+    domain::{self, ComponentRegistration},
     error::errors::{SalObjError, SalObjResult},
+    generics::configurations_available::ConfigurationsAvailable,
     sal_info,
     topics::{
-        base_sal_topic::BaseSALTopic, base_topic::BaseTopic, controller_command::ControllerCommand,
+        base_sal_topic::BaseSALTopic,
+        base_topic::BaseTopic,
+        controller_command::{ControllerCommand, ReceivedCommand},
         write_topic::WriteTopic,
     },
-    utils::types::{ControllerCommandSet, WriteTopicSet},
+    utils::{
+        command_ack::CommandAck,
+        config::list_configuration_overrides,
+        heartbeat::{start_heartbeat, HeartbeatHandle},
+        types::{ControllerCommandSet, WriteTopicSet},
+    },
 };
-use apache_avro::{to_value, types::Value};
+
+#[cfg(feature = "tracing")]
+use crate::{generics::empty_topic::EmptyTopic, utils::command_span};
+#[cfg(feature = "tracing")]
+use apache_avro::from_value;
+use apache_avro::{to_value, types::Value, Schema};
+use futures::future::join_all;
 use serde::Serialize;
 
+/// Time to wait for data on each command topic in
+/// [Controller::handle_next_command].
+const HANDLE_COMMAND_POLL_TIME: Duration = Duration::from_millis(10);
+
+/// Decide whether a command, given its name and data, may be executed.
+pub type CommandAuthorizer = Arc<dyn Fn(&str, &Value) -> bool + Send + Sync>;
+
 pub struct Controller<'a> {
+    /// Name of the component.
+    name: String,
     pub commands: ControllerCommandSet<'a>,
     pub events: WriteTopicSet<'a>,
     pub telemetry: WriteTopicSet<'a>,
+    /// Fields of the last sample written for each telemetry topic.
+    telemetry_data: HashMap<String, Vec<(String, Value)>>,
+    /// Optional hook consulted before dispatching each command.
+    command_authorizer: Option<CommandAuthorizer>,
+    /// Entry of the component in the domain active components.
+    _registration: ComponentRegistration,
 }
 
 impl<'a> Controller<'a> {
@@ -35,6 +70,7 @@ impl<'a> Controller<'a> {
         if let Err(error) = domain.register_topics(&sal_info.get_topics_name()) {
             log::warn!("Failed to register topics: {error:?}. Continuing...");
         }
+        let registration = domain.register_component(name, index);
 
         let commands: ControllerCommandSet = sal_info
             .get_command_names()
@@ -73,12 +109,45 @@ impl<'a> Controller<'a> {
             .collect();
 
         Ok(Controller {
+            name: sal_info.get_name(),
             commands,
             events,
             telemetry,
+            telemetry_data: HashMap::new(),
+            command_authorizer: None,
+            _registration: registration,
         })
     }
 
+    /// Get the name of the component.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set a hook that decides whether a command may be executed.
+    ///
+    /// The authorizer is called with the command name and data when a
+    /// command is received, before it is dispatched. Commands it rejects are
+    /// acknowledged with
+    /// [SalRetCode::CmdNoperm](crate::sal_enums::SalRetCode::CmdNoperm)
+    /// and never reach the handler.
+    pub fn set_command_authorizer<F>(&mut self, authorizer: F)
+    where
+        F: Fn(&str, &Value) -> bool + Send + Sync + 'static,
+    {
+        let authorizer: CommandAuthorizer = Arc::new(authorizer);
+        for command in self.commands.values_mut() {
+            command.set_authorizer(Some(authorizer.clone()));
+        }
+        self.command_authorizer = Some(authorizer);
+    }
+
+    /// Get the command authorizer, to apply it to command readers created
+    /// outside the controller.
+    pub(crate) fn get_command_authorizer(&self) -> Option<CommandAuthorizer> {
+        self.command_authorizer.clone()
+    }
+
     pub async fn write_telemetry<T>(&mut self, topic_name: &str, data: T) -> SalObjResult<i32>
     where
         T: BaseSALTopic + Serialize,
@@ -87,11 +156,16 @@ impl<'a> Controller<'a> {
             if let Ok(data_value) = to_value(data) {
                 if let Value::Record(data_record) = data_value {
                     let schema = writer.get_schema().clone();
-                    let mut record = WriteTopic::make_data_type(&schema).unwrap();
+                    let mut record = WriteTopic::make_data_type(&schema).ok_or_else(|| {
+                        SalObjError::new(&format!("Failed to create {topic_name} record."))
+                    })?;
                     for (field, value) in data_record.into_iter() {
                         record.put(&field, value);
                     }
-                    writer.write(&mut record).await
+                    let seq_num = writer.write(&mut record).await?;
+                    self.telemetry_data
+                        .insert(topic_name.to_owned(), record.fields.clone());
+                    Ok(seq_num)
                 } else {
                     Err(SalObjError::new("Failed to convert value to record."))
                 }
@@ -105,6 +179,53 @@ impl<'a> Controller<'a> {
         }
     }
 
+    /// Update a single field of a telemetry topic and publish it.
+    ///
+    /// The remaining fields are taken from the last sample written to the
+    /// topic, so at least one sample must have been written with
+    /// [Controller::write_telemetry] before calling this method. `value` is
+    /// resolved against the type of `field`, and an error is returned without
+    /// writing anything if it does not match.
+    pub async fn update_telemetry_field(
+        &mut self,
+        topic_name: &str,
+        field: &str,
+        value: Value,
+    ) -> SalObjResult<i32> {
+        if let Some(writer) = self.telemetry.get_mut(topic_name) {
+            let schema = writer.get_schema().clone();
+            let mut record = WriteTopic::make_data_type(&schema).ok_or_else(|| {
+                SalObjError::new(&format!("Failed to create {topic_name} record."))
+            })?;
+            let value = resolve_fields(
+                topic_name,
+                &schema,
+                Value::Record(vec![(field.to_owned(), value)]),
+            )?
+            .pop()
+            .map(|(_, value)| value)
+            .ok_or_else(|| SalObjError::new(&format!("No value for {field} of {topic_name}.")))?;
+            if let Some(data) = self.telemetry_data.get(topic_name) {
+                for (name, current_value) in data.iter() {
+                    record.put(name, current_value.clone());
+                }
+            } else {
+                return Err(SalObjError::new(&format!(
+                    "No data written to telemetry topic {topic_name} yet."
+                )));
+            }
+            record.put(field, value);
+            let seq_num = writer.write(&mut record).await?;
+            self.telemetry_data
+                .insert(topic_name.to_owned(), record.fields.clone());
+            Ok(seq_num)
+        } else {
+            Err(SalObjError::new(&format!(
+                "No telemetry topic {topic_name}"
+            )))
+        }
+    }
+
     pub fn get_event_to_write<T>(&self, topic_name: &str) -> SalObjResult<T>
     where
         T: BaseSALTopic + Default + Debug,
@@ -137,6 +258,79 @@ impl<'a> Controller<'a> {
         }
     }
 
+    /// Publish the configuration overrides available in `config_dir` with
+    /// the `configurationsAvailable` event.
+    ///
+    /// The overrides, listed by [list_configuration_overrides], are sent as
+    /// a comma-separated list and the directory as a `file://` url.
+    pub async fn publish_available_configurations(
+        &mut self,
+        config_dir: &Path,
+    ) -> SalObjResult<i32> {
+        let overrides = list_configuration_overrides(config_dir)?;
+
+        let configurations_available = self
+            .get_event_to_write::<ConfigurationsAvailable>("logevent_configurationsAvailable")?
+            .with_overrides(&overrides.join(","))
+            .with_url(&format!("file://{}", config_dir.display()));
+
+        self.write_event(
+            "logevent_configurationsAvailable",
+            &configurations_available,
+        )
+        .await
+    }
+
+    /// Write several events at once.
+    ///
+    /// Each event is given as the name of the event topic and a
+    /// `Value::Record` with the event fields. Private fields and `salIndex`
+    /// are filled in automatically. Events for different topics are written
+    /// concurrently, events for the same topic are written in the given
+    /// order. Return the number of events written.
+    ///
+    /// Every event is checked against its topic schema before anything is
+    /// written, so an unknown topic, an unknown field or a value of the wrong
+    /// type fails the call without publishing any event. An error while
+    /// writing, e.g. from the broker, only stops the events of that topic;
+    /// events of other topics may already have been published.
+    ///
+    /// This is useful to publish the current value of many events, e.g.
+    /// when the CSC is enabled.
+    pub async fn write_events(&mut self, events: Vec<(&str, Value)>) -> SalObjResult<usize> {
+        let mut events_per_topic: HashMap<&str, Vec<Vec<(String, Value)>>> = HashMap::new();
+
+        for (topic_name, data) in events {
+            let writer = self
+                .events
+                .get(topic_name)
+                .ok_or_else(|| SalObjError::new(&format!("No event topic {topic_name}")))?;
+            let fields = resolve_fields(topic_name, writer.get_schema(), data)?;
+            events_per_topic.entry(topic_name).or_default().push(fields);
+        }
+
+        let results = join_all(self.events.iter_mut().filter_map(|(topic_name, writer)| {
+            let topic_events = events_per_topic.remove(topic_name.as_str())?;
+            Some(async move {
+                let schema = writer.get_schema().clone();
+                let n_events = topic_events.len();
+                for fields in topic_events {
+                    let mut record = WriteTopic::make_data_type(&schema).ok_or_else(|| {
+                        SalObjError::new(&format!("Failed to create {topic_name} record."))
+                    })?;
+                    for (field, value) in fields {
+                        record.put(&field, value);
+                    }
+                    writer.write(&mut record).await?;
+                }
+                Ok(n_events)
+            })
+        }))
+        .await;
+
+        results.into_iter().sum()
+    }
+
     pub async fn process_command(&mut self, command_name: &str) -> SalObjResult<Value> {
         if let Some(command) = self.commands.get_mut(command_name) {
             command.process_command().await
@@ -144,11 +338,152 @@ impl<'a> Controller<'a> {
             Err(SalObjError::new(&format!("No command {command_name}.")))
         }
     }
+
+    /// Wait for the next command on any command topic, call `handler` with
+    /// the command name and data and publish the acknowledgement it returns.
+    ///
+    /// This is a lightweight alternative to running one task per command,
+    /// suitable for simple controllers. Commands rejected by the command
+    /// authorizer are acknowledged with `CmdNoperm` without calling
+    /// `handler`. Return the acknowledgement that was published, or an error
+    /// if no command arrives in `timeout` or reading a command topic fails.
+    pub async fn handle_next_command<F>(
+        &mut self,
+        handler: F,
+        timeout: Duration,
+    ) -> SalObjResult<CommandAck>
+    where
+        F: FnOnce(&str, Value) -> CommandAck,
+    {
+        let deadline = Instant::now() + timeout;
+
+        while Instant::now() < deadline {
+            let mut received = None;
+            for (command_name, command) in self.commands.iter_mut() {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                match command
+                    .receive_command(HANDLE_COMMAND_POLL_TIME.min(remaining))
+                    .await?
+                {
+                    Some(ReceivedCommand::Authorized(cmd_data)) => {
+                        received = Some((command_name.to_owned(), cmd_data));
+                        break;
+                    }
+                    Some(ReceivedCommand::Rejected(command_ack)) => return Ok(command_ack),
+                    None => continue,
+                }
+            }
+            if let Some((command_name, cmd_data)) = received {
+                #[cfg(feature = "tracing")]
+                let span = self.commands.get(&command_name).and_then(|command| {
+                    let data = from_value::<EmptyTopic>(&cmd_data).unwrap_or_default();
+                    command_span::get_controller_command(&command_span::CommandKey::new(
+                        &self.name,
+                        command.get_command_type() as i32,
+                        data.get_private_seq_num(),
+                        data.get_private_origin(),
+                    ))
+                });
+
+                let dispatch = || handler(&command_name, cmd_data);
+                #[cfg(feature = "tracing")]
+                let command_ack = match &span {
+                    Some(span) => span.in_scope(dispatch),
+                    None => dispatch(),
+                };
+                #[cfg(not(feature = "tracing"))]
+                let command_ack = dispatch();
+
+                if let Some(command) = self.commands.get_mut(&command_name) {
+                    command.ack(command_ack.clone()).await?;
+                }
+
+                return Ok(command_ack);
+            }
+        }
+        Err(SalObjError::new(&format!(
+            "No command received in {timeout:?}."
+        )))
+    }
+}
+
+impl Controller<'static> {
+    /// Publish the heartbeat event every `interval` in a background task.
+    ///
+    /// This lets a bare controller, that is not part of a CSC, announce it
+    /// is alive. The heartbeat writer is moved to the task, so the heartbeat
+    /// can only be started once and cannot be written with
+    /// [Controller::write_event] afterwards. Stop it with
+    /// [HeartbeatHandle::stop].
+    pub fn start_heartbeat(&mut self, interval: Duration) -> SalObjResult<HeartbeatHandle> {
+        let heartbeat_writer = self.events.remove("logevent_heartbeat").ok_or_else(|| {
+            SalObjError::new("No heartbeat event to write; heartbeat already started?")
+        })?;
+
+        Ok(start_heartbeat(heartbeat_writer, interval))
+    }
+}
+
+/// Resolve the fields of `data` against the record `schema` of
+/// `topic_name`.
+///
+/// Return an error if `data` is not a record, if one of its fields is not in
+/// the topic or if a value does not match the type of its field.
+fn resolve_fields(
+    topic_name: &str,
+    schema: &Schema,
+    data: Value,
+) -> SalObjResult<Vec<(String, Value)>> {
+    let fields = if let Schema::Record { fields, .. } = schema {
+        fields
+    } else {
+        return Err(SalObjError::new(&format!(
+            "Schema for {topic_name} is not a record."
+        )));
+    };
+    let data_record = if let Value::Record(data_record) = data {
+        data_record
+    } else {
+        return Err(SalObjError::new(&format!(
+            "Data for {topic_name} is not a record."
+        )));
+    };
+
+    data_record
+        .into_iter()
+        .map(|(name, value)| {
+            let field = fields
+                .iter()
+                .find(|field| field.name == name)
+                .ok_or_else(|| {
+                    SalObjError::new(&format!("No field {name} in topic {topic_name}."))
+                })?;
+            let value = value.resolve(&field.schema).map_err(|error| {
+                SalObjError::new(&format!(
+                    "Field {name} of {topic_name} has wrong type: expected {:?}; {error}.",
+                    field.schema
+                ))
+            })?;
+            Ok((name, value))
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        csc::test_csc::topics::{arrays::Arrays, scalars::Scalars, wait::Wait},
+        remote::Remote,
+        sal_enums::SalRetCode,
+        topics::remote_command::RemoteCommand,
+    };
+    #[cfg(not(feature = "tracing"))]
+    use apache_avro::from_value;
+
     #[test]
     fn test_create() {
         let mut domain = domain::Domain::new();
@@ -158,4 +493,442 @@ mod tests {
 
         assert!(controller.is_ok())
     }
+
+    #[test]
+    fn test_create_assume_schemas_registered() {
+        let mut domain = domain::Domain::new().with_assume_schemas_registered(true);
+
+        assert!(Controller::new(&mut domain, "Test", 1).is_ok());
+        assert!(Remote::from_name_index(&mut domain, "Test", 1).is_ok());
+    }
+
+    #[test]
+    fn test_create_readonly_component() {
+        let mut domain = domain::Domain::new();
+        let controller = Controller::new(&mut domain, "Sensor", 0).unwrap();
+
+        assert!(controller.commands.is_empty());
+        assert!(controller.telemetry.contains_key("temperature"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_next_command() {
+        let mut controller_domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 129).unwrap();
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 129).unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let wait: Wait = remote.get_command_data("command_wait").unwrap();
+
+        let (handled, remote_ack) = tokio::join!(
+            controller.handle_next_command(
+                |command_name, cmd_data| {
+                    assert_eq!(command_name, "command_wait");
+                    CommandAck::make_complete(from_value::<Wait>(&cmd_data).unwrap())
+                },
+                timeout
+            ),
+            remote.run_command_typed("command_wait", &wait, timeout, true)
+        );
+
+        assert_eq!(*handled.unwrap().get_ack_enum(), SalRetCode::CmdComplete);
+        assert_eq!(*remote_ack.unwrap().get_ack_enum(), SalRetCode::CmdComplete);
+    }
+
+    #[tokio::test]
+    async fn test_ack_cmdtype() {
+        let mut controller_domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 147).unwrap();
+
+        let remote_domain = domain::Domain::new();
+        let sal_info = sal_info::SalInfo::new("Test", 147).unwrap();
+        let mut remote_command =
+            RemoteCommand::new("command_setScalars", &remote_domain, &sal_info)
+                .with_verify_cmdtype(true);
+
+        let timeout = Duration::from_secs(10);
+        let scalars = Scalars::default()
+            .with_timestamps()
+            .with_private_seq_num(remote_command.get_seq_num())
+            .with_private_origin(remote_command.get_origin())
+            .with_private_identity(&remote_command.get_identity())
+            .with_sal_index(remote_command.get_index());
+
+        let (_, remote_ack) = tokio::join!(
+            controller.handle_next_command(
+                |_, cmd_data| CommandAck::make_complete(from_value::<Scalars>(&cmd_data).unwrap()),
+                timeout
+            ),
+            remote_command.run_typed(&scalars, timeout, true)
+        );
+
+        assert_eq!(
+            remote_ack.unwrap().get_cmdtype() as usize,
+            sal_info.get_command_type("command_setScalars").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_authorizer_noperm() {
+        let mut controller_domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 142).unwrap();
+        controller.set_command_authorizer(|command_name, _| command_name != "command_enable");
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 142).unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let schema = remote.get_command_schema("command_enable").unwrap();
+        let mut record = WriteTopic::make_data_type(&schema).unwrap();
+
+        let (handled, remote_ack) = tokio::join!(
+            controller.handle_next_command(
+                |command_name, _| panic!("Handler called for rejected {command_name}."),
+                timeout
+            ),
+            remote.run_command("command_enable".to_owned(), &mut record, timeout, true)
+        );
+
+        assert_eq!(*handled.unwrap().get_ack_enum(), SalRetCode::CmdNoperm);
+        assert_eq!(
+            *remote_ack.unwrap_err().get_ack_enum(),
+            SalRetCode::CmdNoperm
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_command_spans() {
+        use crate::{generics::start::Start, utils::command_span::testing::SpanRecorder};
+
+        let recorder = SpanRecorder::default();
+        let _default = tracing::subscriber::set_default(recorder.clone());
+
+        let mut controller_domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 151).unwrap();
+
+        let remote_domain = domain::Domain::new();
+        let sal_info = sal_info::SalInfo::new("Test", 151).unwrap();
+        let mut remote_command = RemoteCommand::new("command_start", &remote_domain, &sal_info);
+
+        let timeout = Duration::from_secs(10);
+        let start = Start::default()
+            .with_private_seq_num(remote_command.get_seq_num())
+            .with_sal_index(remote_command.get_index());
+
+        let (handled, remote_ack) = tokio::join!(
+            controller.handle_next_command(
+                |_, cmd_data| CommandAck::make_complete(from_value::<Start>(&cmd_data).unwrap()),
+                timeout
+            ),
+            remote_command.run_typed(&start, timeout, true)
+        );
+        handled.unwrap();
+        remote_ack.unwrap();
+
+        let spans = recorder.get_spans();
+        let remote_span = spans
+            .iter()
+            .find(|span| span.name == "remote_command")
+            .unwrap();
+        let controller_span = spans
+            .iter()
+            .find(|span| span.name == "controller_command")
+            .unwrap();
+
+        assert_eq!(remote_span.get_field("component"), Some("Test"));
+        assert_eq!(remote_span.get_field("command"), Some("command_start"));
+        assert_eq!(remote_span.get_field("result"), Some("CmdComplete"));
+        assert_eq!(controller_span.get_field("command"), Some("command_start"));
+        assert_eq!(controller_span.get_field("result"), Some("CmdComplete"));
+        assert_eq!(
+            remote_span.get_field("seq_num"),
+            controller_span.get_field("seq_num")
+        );
+        assert_eq!(
+            controller_span.follows_from,
+            vec![spans
+                .iter()
+                .position(|span| span.name == "remote_command")
+                .unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_events() {
+        let mut controller_domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 131).unwrap();
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 131).unwrap();
+
+        let log_level = Value::Record(vec![
+            ("level".to_owned(), Value::Int(20)),
+            (
+                "subsystem".to_owned(),
+                Value::String("unit_test".to_owned()),
+            ),
+        ]);
+
+        controller
+            .write_events(vec![
+                ("logevent_scalars", to_value(Scalars::default()).unwrap()),
+                ("logevent_logLevel", log_level),
+            ])
+            .await
+            .unwrap();
+
+        let timeout = Duration::from_secs(10);
+
+        assert!(remote
+            .pop_event_back("logevent_scalars", false, timeout)
+            .await
+            .unwrap()
+            .is_some());
+
+        if let Some(Value::Record(log_level)) = remote
+            .pop_event_back("logevent_logLevel", false, timeout)
+            .await
+            .unwrap()
+        {
+            assert!(log_level.contains(&("level".to_owned(), Value::Int(20))));
+        } else {
+            panic!("No logLevel event received.");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_event_typed_round_trip() {
+        let mut controller_domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 137).unwrap();
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 137).unwrap();
+
+        let mut scalars: Scalars = controller.get_event_to_write("logevent_scalars").unwrap();
+        scalars.boolean0 = true;
+        scalars.byte0 = 1;
+        scalars.short0 = -2;
+        scalars.int0 = 3;
+        scalars.long0 = -4;
+        scalars.long_long0 = 5;
+        scalars.unsigned_short0 = 6;
+        scalars.unsigned_int0 = 7;
+        scalars.float0 = 8.5;
+        scalars.double0 = -9.25;
+        scalars.string0 = "round trip".to_owned();
+
+        controller
+            .write_event("logevent_scalars", &scalars)
+            .await
+            .unwrap();
+
+        let read_scalars = remote
+            .pop_event_front_typed::<Scalars>("logevent_scalars", false, Duration::from_secs(10))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_scalars.get_sal_index(), 137);
+        assert_eq!(read_scalars.boolean0, scalars.boolean0);
+        assert_eq!(read_scalars.byte0, scalars.byte0);
+        assert_eq!(read_scalars.short0, scalars.short0);
+        assert_eq!(read_scalars.int0, scalars.int0);
+        assert_eq!(read_scalars.long0, scalars.long0);
+        assert_eq!(read_scalars.long_long0, scalars.long_long0);
+        assert_eq!(read_scalars.unsigned_short0, scalars.unsigned_short0);
+        assert_eq!(read_scalars.unsigned_int0, scalars.unsigned_int0);
+        assert_eq!(read_scalars.float0, scalars.float0);
+        assert_eq!(read_scalars.double0, scalars.double0);
+        assert_eq!(read_scalars.string0, scalars.string0);
+    }
+
+    #[tokio::test]
+    async fn test_write_events_bad_topic() {
+        let mut domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut domain, "Test", 1).unwrap();
+
+        let result = controller
+            .write_events(vec![("logevent_badTopic", Value::Null)])
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_events_bad_event() {
+        let mut controller_domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 161).unwrap();
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 161).unwrap();
+
+        let bad_log_level = Value::Record(vec![(
+            "level".to_owned(),
+            Value::String("not a level".to_owned()),
+        )]);
+
+        let result = controller
+            .write_events(vec![
+                ("logevent_scalars", to_value(Scalars::default()).unwrap()),
+                ("logevent_logLevel", bad_log_level),
+                ("logevent_arrays", to_value(Arrays::default()).unwrap()),
+            ])
+            .await;
+
+        assert!(result.is_err());
+
+        let timeout = Duration::from_secs(2);
+        for event_name in ["logevent_scalars", "logevent_logLevel", "logevent_arrays"] {
+            assert!(remote
+                .pop_event_back(event_name, false, timeout)
+                .await
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_telemetry_field() {
+        let mut domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut domain, "Test", 160).unwrap();
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 160).unwrap();
+
+        let mut scalars = Scalars::default();
+        scalars.boolean0 = true;
+        scalars.double0 = 1.5;
+        scalars.string0 = "unchanged".to_owned();
+        controller
+            .write_telemetry("scalars", scalars.clone())
+            .await
+            .unwrap();
+        controller
+            .update_telemetry_field("scalars", "int0", Value::Int(10))
+            .await
+            .unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let mut updated = None;
+        while let Some(sample) = remote
+            .pop_telemetry_front("scalars", false, timeout)
+            .await
+            .unwrap()
+        {
+            let sample = from_value::<Scalars>(&sample).unwrap();
+            if sample.int0 == 10 {
+                updated = Some(sample);
+                break;
+            }
+        }
+        let updated = updated.expect("No updated scalars received.");
+
+        assert_eq!(updated.boolean0, scalars.boolean0);
+        assert_eq!(updated.double0, scalars.double0);
+        assert_eq!(updated.string0, scalars.string0);
+    }
+
+    #[tokio::test]
+    async fn test_update_telemetry_field_bad_field() {
+        let mut domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut domain, "Test", 1).unwrap();
+
+        let result = controller
+            .update_telemetry_field("scalars", "badField", Value::Int(10))
+            .await;
+
+        assert!(result.is_err())
+    }
+
+    #[tokio::test]
+    async fn test_update_telemetry_field_wrong_type() {
+        let mut domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut domain, "Test", 1).unwrap();
+
+        controller
+            .write_telemetry("scalars", Scalars::default())
+            .await
+            .unwrap();
+        let seq_num = controller.telemetry["scalars"].get_seq_num();
+
+        let result = controller
+            .update_telemetry_field("scalars", "int0", Value::String("ten".to_owned()))
+            .await;
+
+        assert!(result
+            .unwrap_err()
+            .get_error_message()
+            .contains("Field int0 of scalars has wrong type"));
+        assert_eq!(controller.telemetry["scalars"].get_seq_num(), seq_num);
+    }
+
+    #[tokio::test]
+    async fn test_start_heartbeat() {
+        let mut domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut domain, "Test", 149).unwrap();
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 149).unwrap();
+
+        let heartbeat = controller
+            .start_heartbeat(Duration::from_millis(100))
+            .unwrap();
+
+        assert!(controller
+            .start_heartbeat(Duration::from_millis(100))
+            .is_err());
+        assert!(remote
+            .pop_event_front("logevent_heartbeat", false, Duration::from_secs(10))
+            .await
+            .unwrap()
+            .is_some());
+
+        heartbeat.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_publish_available_configurations() {
+        let config_dir = std::env::temp_dir().join(format!(
+            "salobj_configurations_available_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&config_dir).unwrap();
+        for file_name in ["_init.yaml", "fast.yaml", "slow.yml", "notes.txt"] {
+            std::fs::write(config_dir.join(file_name), "").unwrap();
+        }
+
+        let mut domain = domain::Domain::new();
+        let mut controller = Controller::new(&mut domain, "Test", 150).unwrap();
+
+        let mut remote_domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut remote_domain, "Test", 150).unwrap();
+
+        controller
+            .publish_available_configurations(&config_dir)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&config_dir).unwrap();
+
+        let configurations_available = remote
+            .pop_event_front_typed::<ConfigurationsAvailable>(
+                "logevent_configurationsAvailable",
+                false,
+                Duration::from_secs(10),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            configurations_available.get_overrides(),
+            "fast.yaml,slow.yml"
+        );
+        assert_eq!(
+            configurations_available.get_url(),
+            format!("file://{}", config_dir.display())
+        );
+    }
 }
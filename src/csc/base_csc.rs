@@ -1,8 +1,51 @@
 //! Trait for CSCs.
 
-use crate::{error::errors::SalObjResult, generics::start::Start, sal_enums::State};
+use serde::de::DeserializeOwned;
+use std::{future::Future, sync::Arc};
+use tokio::sync::watch;
+
+use crate::{
+    error::errors::SalObjResult,
+    generics::{large_file_object_available::LargeFileObjectAvailable, start::Start},
+    sal_enums::State,
+    utils::config,
+};
 
 pub const HEARTBEAT_TIME: std::time::Duration = std::time::Duration::from_secs(1);
+/// Default time to wait after acknowledging exitControl before exiting.
+pub const EXIT_CONTROL_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Handle to request a running CSC to shut down.
+///
+/// Triggering the handle makes the CSC `run` loop return after stopping its
+/// background tasks.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    /// Create a new handle and the receiver the CSC should watch.
+    pub fn channel() -> (ShutdownHandle, watch::Receiver<bool>) {
+        let (sender, receiver) = watch::channel(false);
+        (
+            ShutdownHandle {
+                sender: Arc::new(sender),
+            },
+            receiver,
+        )
+    }
+
+    /// Request the CSC to shut down.
+    pub fn shutdown(&self) {
+        let _ = self.sender.send(true);
+    }
+
+    /// Was shutdown requested?
+    pub fn is_shutdown(&self) -> bool {
+        *self.sender.borrow()
+    }
+}
 
 pub trait BaseCSC {
     fn do_start(&mut self, data: Start) -> SalObjResult<()> {
@@ -10,6 +53,8 @@ pub trait BaseCSC {
 
         self.configure(&data)?;
 
+        self.read_configuration(&data.get_configuration_override())?;
+
         self.set_summary_state(new_state);
 
         Ok(())
@@ -19,5 +64,221 @@ pub trait BaseCSC {
 
     fn set_summary_state(&mut self, new_state: State);
 
+    /// Get a handle that cleanly stops the CSC when triggered, or `None` if
+    /// the CSC does not support being stopped from the outside.
+    ///
+    /// CSCs that support a clean shutdown keep the receiver from
+    /// [ShutdownHandle::channel] and return their own handle.
+    fn shutdown_signal(&self) -> Option<ShutdownHandle> {
+        None
+    }
+
+    /// Time to wait after acknowledging exitControl before exiting, so the
+    /// final summary state and ack are delivered.
+    fn get_exit_control_grace_period(&self) -> std::time::Duration {
+        EXIT_CONTROL_GRACE_PERIOD
+    }
+
+    /// Get the simulation mode; 0 means the CSC is not simulating.
+    ///
+    /// The default is 0, for CSCs that do not support simulation.
+    fn get_simulation_mode(&self) -> isize {
+        0
+    }
+
+    /// Is the CSC running in simulation mode?
+    ///
+    /// Command handlers can use this to replace interactions with hardware
+    /// by simulated behavior.
+    fn is_simulating(&self) -> bool {
+        self.get_simulation_mode() != 0
+    }
+
     fn configure(&mut self, data: &Start) -> SalObjResult<()>;
+
+    /// Read the configuration named by the configuration override.
+    ///
+    /// The default does nothing, so CSCs without a configuration start
+    /// without a configuration directory. CSCs with one implement
+    /// [ConfigurableCSC] and call [ConfigurableCSC::read_typed_configuration]
+    /// from here.
+    fn read_configuration(&mut self, _configuration_override: &str) -> SalObjResult<()> {
+        Ok(())
+    }
+}
+
+/// Trait for CSCs that declare a configuration struct.
+pub trait ConfigurableCSC: BaseCSC {
+    /// Configuration struct of the CSC.
+    type Config: DeserializeOwned;
+
+    /// Configure the CSC from its configuration struct.
+    fn configure_typed(&mut self, config: Self::Config) -> SalObjResult<()>;
+
+    /// Read the configuration file named by the configuration override,
+    /// parse it into [ConfigurableCSC::Config] and pass it to
+    /// [ConfigurableCSC::configure_typed].
+    ///
+    /// See [config::read_configuration] for where the file is read from.
+    fn read_typed_configuration(&mut self, configuration_override: &str) -> SalObjResult<()> {
+        let config = config::read_configuration(configuration_override)?;
+        self.configure_typed(config)
+    }
+
+    /// Fetch the configuration announced as a Large File Object, parse it
+    /// into [ConfigurableCSC::Config] and pass it to
+    /// [ConfigurableCSC::configure_typed].
+    ///
+    /// The object is verified against its checksum first, see
+    /// [config::read_configuration_from_lfa].
+    fn configure_from_lfa(
+        &mut self,
+        large_file_object: &LargeFileObjectAvailable,
+    ) -> impl Future<Output = SalObjResult<()>> {
+        async move {
+            let config = config::read_configuration_from_lfa(large_file_object).await?;
+            self.configure_typed(config)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::utils::{lfa::serve_once, testing::with_env_vars};
+
+    /// Configuration directory used by the tests.
+    const TEST_CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/config/");
+
+    struct NoConfigCSC {
+        summary_state: State,
+    }
+
+    impl BaseCSC for NoConfigCSC {
+        fn get_current_state(&self) -> State {
+            self.summary_state
+        }
+
+        fn set_summary_state(&mut self, new_state: State) {
+            self.summary_state = new_state;
+        }
+
+        fn configure(&mut self, _data: &Start) -> SalObjResult<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestConfig {
+        name: String,
+        wait_time: f64,
+    }
+
+    struct ConfiguredCSC {
+        summary_state: State,
+        config: Option<TestConfig>,
+    }
+
+    impl BaseCSC for ConfiguredCSC {
+        fn get_current_state(&self) -> State {
+            self.summary_state
+        }
+
+        fn set_summary_state(&mut self, new_state: State) {
+            self.summary_state = new_state;
+        }
+
+        fn configure(&mut self, _data: &Start) -> SalObjResult<()> {
+            Ok(())
+        }
+
+        fn read_configuration(&mut self, configuration_override: &str) -> SalObjResult<()> {
+            self.read_typed_configuration(configuration_override)
+        }
+    }
+
+    impl ConfigurableCSC for ConfiguredCSC {
+        type Config = TestConfig;
+
+        fn configure_typed(&mut self, config: Self::Config) -> SalObjResult<()> {
+            self.config = Some(config);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn do_start_without_configuration() {
+        let mut csc = NoConfigCSC {
+            summary_state: State::Standby,
+        };
+
+        csc.do_start(Start::default().with_configuration_override("no_such_file.yaml"))
+            .unwrap();
+
+        assert_eq!(csc.get_current_state(), State::Disabled);
+    }
+
+    #[test]
+    fn default_simulation_mode() {
+        let csc = NoConfigCSC {
+            summary_state: State::Standby,
+        };
+
+        assert_eq!(csc.get_simulation_mode(), 0);
+        assert!(!csc.is_simulating());
+    }
+
+    #[test]
+    fn do_start_with_configuration() {
+        let mut csc = ConfiguredCSC {
+            summary_state: State::Standby,
+            config: None,
+        };
+
+        with_env_vars(&[("LSST_CONFIG_PATH", Some(TEST_CONFIG_PATH))], || {
+            csc.do_start(Start::default().with_configuration_override("test_config.yaml"))
+        })
+        .unwrap();
+
+        let config = csc.config.unwrap();
+        assert_eq!(csc.summary_state, State::Disabled);
+        assert_eq!(config.name, "test");
+        assert_eq!(config.wait_time, 1.5);
+    }
+
+    #[tokio::test]
+    async fn configure_from_lfa() {
+        let body = b"name: lfa\nwait_time: 2.5\n";
+        let url = serve_once("200 OK", body, "/config/_init.yaml").await;
+        let large_file_object = LargeFileObjectAvailable::default()
+            .with_url(&url)
+            .with_check_sum(&format!("{:x}", md5::compute(body)));
+        let mut csc = ConfiguredCSC {
+            summary_state: State::Standby,
+            config: None,
+        };
+
+        csc.configure_from_lfa(&large_file_object).await.unwrap();
+
+        let config = csc.config.unwrap();
+        assert_eq!(config.name, "lfa");
+        assert_eq!(config.wait_time, 2.5);
+    }
+
+    #[test]
+    fn do_start_with_missing_configuration() {
+        let mut csc = ConfiguredCSC {
+            summary_state: State::Standby,
+            config: None,
+        };
+
+        let result = with_env_vars(&[("LSST_CONFIG_PATH", Some(TEST_CONFIG_PATH))], || {
+            csc.do_start(Start::default().with_configuration_override("bad_config.yaml"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(csc.summary_state, State::Standby);
+    }
 }
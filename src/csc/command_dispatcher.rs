@@ -0,0 +1,482 @@
+//! Dispatch commands to handlers registered at runtime.
+//!
+//! This is an alternative to the `handle_command!` macro, which requires
+//! listing every command in the CSC control loop. With a [CommandDispatcher]
+//! each command is bound to a handler once, and the control loop simply
+//! forwards every command it receives to [CommandDispatcher::dispatch_and_ack],
+//! which sends the acks the same way for every command.
+//!
+//! See `TestCSC::run` for a control loop built on a dispatcher.
+
+use std::collections::HashMap;
+
+use apache_avro::{from_value, types::Value};
+use futures::future::BoxFuture;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    error::errors::SalObjResult, generics::empty_topic::EmptyTopic, utils::command_ack::CommandAck,
+};
+
+/// Channel the acknowledgements of a command are sent through.
+pub type AckSender = mpsc::Sender<CommandAck>;
+
+/// Handler of one command.
+///
+/// Receives the CSC, the command data and a channel for acks sent after the
+/// handler returns, and returns the first ack.
+pub type CommandHandler<C> = Box<
+    dyn for<'c> Fn(&'c mut C, Value, AckSender) -> BoxFuture<'c, SalObjResult<CommandAck>>
+        + Send
+        + Sync,
+>;
+
+/// Map of command name to command handler for a CSC of type `C`.
+pub struct CommandDispatcher<C> {
+    handlers: HashMap<String, CommandHandler<C>>,
+}
+
+impl<C> Default for CommandDispatcher<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> CommandDispatcher<C> {
+    pub fn new() -> CommandDispatcher<C> {
+        CommandDispatcher {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Register the handler of a command, e.g. `command_start`.
+    ///
+    /// A previously registered handler for the same command is replaced.
+    pub fn with_handler<F>(mut self, command_name: &str, handler: F) -> Self
+    where
+        F: for<'c> Fn(&'c mut C, Value) -> BoxFuture<'c, SalObjResult<CommandAck>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.add_handler(command_name, handler);
+        self
+    }
+
+    /// Same as [CommandDispatcher::with_handler] for an existing dispatcher.
+    pub fn add_handler<F>(&mut self, command_name: &str, handler: F)
+    where
+        F: for<'c> Fn(&'c mut C, Value) -> BoxFuture<'c, SalObjResult<CommandAck>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.add_in_progress_handler(command_name, move |csc, data, _| handler(csc, data));
+    }
+
+    /// Register the handler of a command that finishes in the background.
+    ///
+    /// The handler returns the in-progress ack, and sends the final ack
+    /// through the given channel when the command is done.
+    pub fn with_in_progress_handler<F>(mut self, command_name: &str, handler: F) -> Self
+    where
+        F: for<'c> Fn(&'c mut C, Value, AckSender) -> BoxFuture<'c, SalObjResult<CommandAck>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.add_in_progress_handler(command_name, handler);
+        self
+    }
+
+    /// Same as [CommandDispatcher::with_in_progress_handler] for an existing
+    /// dispatcher.
+    pub fn add_in_progress_handler<F>(&mut self, command_name: &str, handler: F)
+    where
+        F: for<'c> Fn(&'c mut C, Value, AckSender) -> BoxFuture<'c, SalObjResult<CommandAck>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.handlers
+            .insert(command_name.to_owned(), Box::new(handler));
+    }
+
+    /// Is there a handler for the command?
+    pub fn has_handler(&self, command_name: &str) -> bool {
+        self.handlers.contains_key(command_name)
+    }
+
+    /// Get the names of the commands with a handler, sorted.
+    pub fn get_command_names(&self) -> Vec<String> {
+        let mut command_names: Vec<String> = self.handlers.keys().cloned().collect();
+        command_names.sort();
+        command_names
+    }
+
+    /// Run the handler of the command.
+    ///
+    /// Commands without a handler are acknowledged as failed. Acks sent after
+    /// the handler returns, by in-progress handlers, go through `ack_sender`.
+    pub async fn dispatch(
+        &self,
+        csc: &mut C,
+        command_name: &str,
+        data: Value,
+        ack_sender: AckSender,
+    ) -> SalObjResult<CommandAck> {
+        match self.handlers.get(command_name) {
+            Some(handler) => handler(csc, data, ack_sender).await,
+            None => Ok(CommandAck::make_failed(
+                CommandDispatcher::<C>::decode_private_fields(command_name, &data),
+                1,
+                &format!("Command {command_name} not implemented."),
+            )),
+        }
+    }
+
+    /// Run the handler of the command and send its acks through
+    /// `ack_sender`, all tagged with the command type `cmdtype`.
+    ///
+    /// Handler errors are acknowledged as failed, with the error as result.
+    /// Return the ack sent when the handler returned, e.g. to check whether
+    /// an exitControl command succeeded.
+    pub async fn dispatch_and_ack(
+        &self,
+        csc: &mut C,
+        command_name: &str,
+        data: Value,
+        cmdtype: i32,
+        ack_sender: &AckSender,
+    ) -> CommandAck {
+        let any = CommandDispatcher::<C>::decode_private_fields(command_name, &data);
+        let (first_ack_sent, forward_sender) =
+            CommandDispatcher::<C>::forward_with_cmdtype(ack_sender.clone(), cmdtype);
+        let command_ack = match self.dispatch(csc, command_name, data, forward_sender).await {
+            Ok(command_ack) => command_ack,
+            Err(error) => CommandAck::make_failed(any, 1, error.get_error_message()),
+        }
+        .with_cmdtype(cmdtype);
+
+        let _ = ack_sender.send(command_ack.clone()).await;
+        let _ = first_ack_sent.send(());
+        command_ack
+    }
+
+    /// Make a channel that forwards acks to `ack_sender` tagged with
+    /// `cmdtype`, for the acks in-progress handlers send later.
+    ///
+    /// Acks sent before the returned `oneshot` sender is used (or dropped)
+    /// are held back and forwarded after it, so the acks sent by the handler
+    /// in the background can not overtake the ack the handler returns. They
+    /// are held in a queue, not in the channel, so the handler never blocks
+    /// on them.
+    fn forward_with_cmdtype(
+        ack_sender: AckSender,
+        cmdtype: i32,
+    ) -> (oneshot::Sender<()>, AckSender) {
+        let (first_ack_sent, mut first_ack_received) = oneshot::channel::<()>();
+        let (sender, mut receiver) = mpsc::channel::<CommandAck>(1);
+        tokio::spawn(async move {
+            let mut early_acks = Vec::new();
+            loop {
+                tokio::select! {
+                    _ = &mut first_ack_received => break,
+                    command_ack = receiver.recv() => match command_ack {
+                        Some(command_ack) => early_acks.push(command_ack),
+                        None => {
+                            let _ = first_ack_received.await;
+                            break;
+                        }
+                    },
+                }
+            }
+            for command_ack in early_acks {
+                let _ = ack_sender.send(command_ack.with_cmdtype(cmdtype)).await;
+            }
+            while let Some(command_ack) = receiver.recv().await {
+                let _ = ack_sender.send(command_ack.with_cmdtype(cmdtype)).await;
+            }
+        });
+        (first_ack_sent, sender)
+    }
+
+    /// Decode the private fields of a command, needed to build its acks,
+    /// without failing on the command payload.
+    fn decode_private_fields(command_name: &str, data: &Value) -> EmptyTopic {
+        from_value::<EmptyTopic>(data).unwrap_or_else(|error| {
+            log::warn!("Failed to decode {command_name} payload: {error}.");
+            EmptyTopic::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::errors::SalObjError, sal_enums::SalRetCode};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Counter {
+        count: usize,
+    }
+
+    impl Counter {
+        async fn do_increment(&mut self, data: Value) -> SalObjResult<CommandAck> {
+            self.count += 1;
+            Ok(CommandAck::make_complete(from_value::<EmptyTopic>(&data)?))
+        }
+
+        async fn do_fail(&mut self, _data: Value) -> SalObjResult<CommandAck> {
+            Err(SalObjError::new("Counter is broken."))
+        }
+
+        async fn do_increment_later(
+            &mut self,
+            data: Value,
+            ack_sender: AckSender,
+        ) -> SalObjResult<CommandAck> {
+            let command = from_value::<EmptyTopic>(&data)?;
+            let in_progress = CommandAck::make_in_progress(
+                from_value::<EmptyTopic>(&data)?,
+                std::time::Duration::from_secs(1),
+                "Incrementing.",
+            );
+            self.count += 1;
+            tokio::spawn(async move {
+                let _ = ack_sender.send(CommandAck::make_complete(command)).await;
+            });
+            Ok(in_progress)
+        }
+
+        async fn do_increment_now(
+            &mut self,
+            data: Value,
+            ack_sender: AckSender,
+        ) -> SalObjResult<CommandAck> {
+            self.count += 1;
+            for _ in 0..2 {
+                let in_progress = CommandAck::make_in_progress(
+                    from_value::<EmptyTopic>(&data)?,
+                    Duration::from_secs(1),
+                    "Still incrementing.",
+                );
+                let _ = ack_sender.send(in_progress).await;
+            }
+            let _ = ack_sender
+                .send(CommandAck::make_complete(from_value::<EmptyTopic>(&data)?))
+                .await;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(CommandAck::make_in_progress(
+                from_value::<EmptyTopic>(&data)?,
+                Duration::from_secs(1),
+                "Incrementing.",
+            ))
+        }
+    }
+
+    fn make_dispatcher() -> CommandDispatcher<Counter> {
+        CommandDispatcher::new()
+            .with_handler("command_increment", |counter: &mut Counter, data| {
+                Box::pin(counter.do_increment(data))
+            })
+            .with_handler("command_fail", |counter: &mut Counter, data| {
+                Box::pin(counter.do_fail(data))
+            })
+            .with_in_progress_handler(
+                "command_incrementLater",
+                |counter: &mut Counter, data, ack_sender| {
+                    Box::pin(counter.do_increment_later(data, ack_sender))
+                },
+            )
+            .with_in_progress_handler(
+                "command_incrementNow",
+                |counter: &mut Counter, data, ack_sender| {
+                    Box::pin(counter.do_increment_now(data, ack_sender))
+                },
+            )
+    }
+
+    fn make_command_data(seq_num: i32) -> Value {
+        Value::Record(vec![
+            ("private_sndStamp".to_owned(), Value::Double(0.0)),
+            ("private_rcvStamp".to_owned(), Value::Double(0.0)),
+            ("private_efdStamp".to_owned(), Value::Double(0.0)),
+            ("private_kafkaStamp".to_owned(), Value::Double(0.0)),
+            ("private_seqNum".to_owned(), Value::Int(seq_num)),
+            ("private_revCode".to_owned(), Value::String("".to_owned())),
+            (
+                "private_identity".to_owned(),
+                Value::String("test".to_owned()),
+            ),
+            ("private_origin".to_owned(), Value::Int(1)),
+        ])
+    }
+
+    #[tokio::test]
+    async fn dispatch_registered_handler() {
+        let dispatcher = CommandDispatcher::new()
+            .with_handler("command_increment", |counter: &mut Counter, data| {
+                Box::pin(counter.do_increment(data))
+            });
+        let mut counter = Counter::default();
+
+        let (ack_sender, _) = mpsc::channel(1);
+
+        let command_ack = dispatcher
+            .dispatch(
+                &mut counter,
+                "command_increment",
+                make_command_data(3),
+                ack_sender,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(counter.count, 1);
+        assert_eq!(*command_ack.get_ack_enum(), SalRetCode::CmdComplete);
+        assert_eq!(command_ack.get_seq_num(), 3);
+        assert_eq!(dispatcher.get_command_names(), vec!["command_increment"]);
+    }
+
+    #[tokio::test]
+    async fn dispatch_unknown_command() {
+        let dispatcher = CommandDispatcher::<Counter>::new();
+        let mut counter = Counter::default();
+
+        let (ack_sender, _) = mpsc::channel(1);
+
+        let command_ack = dispatcher
+            .dispatch(&mut counter, "command_unknown", Value::Null, ack_sender)
+            .await
+            .unwrap();
+
+        assert_eq!(counter.count, 0);
+        assert!(!dispatcher.has_handler("command_unknown"));
+        assert_eq!(*command_ack.get_ack_enum(), SalRetCode::CmdFailed);
+        assert_eq!(
+            command_ack.get_result(),
+            "Command command_unknown not implemented."
+        );
+    }
+
+    #[tokio::test]
+    async fn dispatch_and_ack() {
+        let dispatcher = make_dispatcher();
+        let mut counter = Counter::default();
+        let (ack_sender, mut ack_receiver) = mpsc::channel(10);
+
+        for (command_name, seq_num) in [
+            ("command_increment", 1),
+            ("command_fail", 2),
+            ("command_unknown", 3),
+        ] {
+            dispatcher
+                .dispatch_and_ack(
+                    &mut counter,
+                    command_name,
+                    make_command_data(seq_num),
+                    4,
+                    &ack_sender,
+                )
+                .await;
+        }
+
+        let complete = ack_receiver.recv().await.unwrap();
+        assert_eq!(*complete.get_ack_enum(), SalRetCode::CmdComplete);
+        assert_eq!(complete.get_seq_num(), 1);
+        assert_eq!(complete.get_cmdtype(), 4);
+
+        let failed = ack_receiver.recv().await.unwrap();
+        assert_eq!(*failed.get_ack_enum(), SalRetCode::CmdFailed);
+        assert_eq!(failed.get_seq_num(), 2);
+        assert_eq!(failed.get_result(), "Counter is broken.");
+
+        let unknown = ack_receiver.recv().await.unwrap();
+        assert_eq!(*unknown.get_ack_enum(), SalRetCode::CmdFailed);
+        assert_eq!(unknown.get_seq_num(), 3);
+        assert_eq!(unknown.get_cmdtype(), 4);
+        assert_eq!(
+            unknown.get_result(),
+            "Command command_unknown not implemented."
+        );
+
+        assert_eq!(counter.count, 1);
+    }
+
+    #[tokio::test]
+    async fn dispatch_and_ack_in_progress() {
+        let dispatcher = make_dispatcher();
+        let mut counter = Counter::default();
+        let (ack_sender, mut ack_receiver) = mpsc::channel(10);
+
+        let command_ack = dispatcher
+            .dispatch_and_ack(
+                &mut counter,
+                "command_incrementLater",
+                make_command_data(5),
+                2,
+                &ack_sender,
+            )
+            .await;
+        assert!(!command_ack.is_final());
+
+        let in_progress = ack_receiver.recv().await.unwrap();
+        assert_eq!(*in_progress.get_ack_enum(), SalRetCode::CmdInprogress);
+
+        let complete = tokio::time::timeout(Duration::from_secs(1), ack_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*complete.get_ack_enum(), SalRetCode::CmdComplete);
+        assert_eq!(complete.get_seq_num(), 5);
+        assert_eq!(complete.get_cmdtype(), 2);
+        assert_eq!(counter.count, 1);
+    }
+
+    #[tokio::test]
+    async fn dispatch_and_ack_in_progress_order() {
+        let dispatcher = make_dispatcher();
+        let mut counter = Counter::default();
+        let (ack_sender, mut ack_receiver) = mpsc::channel(10);
+
+        // The handler sends several acks before returning, which must neither
+        // block it nor overtake the ack it returns.
+        let command_ack = tokio::time::timeout(
+            Duration::from_secs(1),
+            dispatcher.dispatch_and_ack(
+                &mut counter,
+                "command_incrementNow",
+                make_command_data(6),
+                2,
+                &ack_sender,
+            ),
+        )
+        .await
+        .unwrap();
+        assert!(!command_ack.is_final());
+
+        let in_progress = ack_receiver.recv().await.unwrap();
+        assert_eq!(*in_progress.get_ack_enum(), SalRetCode::CmdInprogress);
+        assert_eq!(in_progress.get_result(), "Incrementing.");
+
+        for _ in 0..2 {
+            let still_in_progress =
+                tokio::time::timeout(Duration::from_secs(1), ack_receiver.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(still_in_progress.get_result(), "Still incrementing.");
+            assert_eq!(still_in_progress.get_cmdtype(), 2);
+        }
+
+        let complete = tokio::time::timeout(Duration::from_secs(1), ack_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*complete.get_ack_enum(), SalRetCode::CmdComplete);
+        assert_eq!(complete.get_cmdtype(), 2);
+        assert_eq!(counter.count, 1);
+    }
+}
@@ -1,5 +1,6 @@
 //! Support for developing CSCs.
 
 pub mod base_csc;
+pub mod command_dispatcher;
 pub mod summary_state;
 pub mod test_csc;
@@ -12,10 +12,13 @@
 //! Once in Enabled the CSC will accept the enabled commands and execute some
 //! operations with the provided data.
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    path::Path,
+};
 
 use apache_avro::{from_value, types::Value};
-use handle_command::handle_command;
 
 use tokio::{
     sync::{mpsc, watch},
@@ -26,14 +29,16 @@ use tokio::{
 use crate::{
     controller::Controller,
     csc::{
-        base_csc::{BaseCSC, HEARTBEAT_TIME},
+        base_csc::{BaseCSC, ShutdownHandle, EXIT_CONTROL_GRACE_PERIOD, HEARTBEAT_TIME},
+        command_dispatcher::{AckSender, CommandDispatcher},
         test_csc::topics::{arrays::Arrays, scalars::Scalars, telemetry::TestTelemetry},
     },
     domain::Domain,
     error::errors::{SalObjError, SalObjResult},
     generics::{
         disable::Disable, empty_topic::EmptyTopic, enable::Enable, exit_control::ExitControl,
-        heartbeat::Heartbeat, standby::Standby, start::Start, summary_state::SummaryState,
+        log_message::LogMessage, software_version::SoftwareVersion, standby::Standby, start::Start,
+        summary_state::SummaryState,
     },
     sal_enums::State,
     sal_info::SalInfo,
@@ -41,18 +46,26 @@ use crate::{
         base_sal_topic::BaseSALTopic, controller_command::ControllerCommand,
         controller_command_ack::ControllerCommandAck, write_topic::WriteTopic,
     },
-    utils::{command_ack::CommandAck, types::WriteTopicSet},
+    utils::{
+        command_ack::CommandAck,
+        heartbeat::{start_heartbeat, HeartbeatHandle},
+        types::WriteTopicSet,
+    },
 };
 
 use super::topics::wait::Wait;
 
+/// Level of informational `logMessage` events, as in Python logging.
+const LOG_LEVEL_INFO: isize = 20;
+
 struct CmdData {
     pub name: String,
     pub data: Value,
+    /// Command type, sent back in the `cmdtype` field of the acks.
+    pub cmdtype: i32,
 }
 
 type CmdPayload = (CmdData, mpsc::Sender<CommandAck>);
-type CommandAckResult = (CommandAck, mpsc::Sender<CommandAck>);
 
 #[derive(Default)]
 struct TelemetryPayload {
@@ -62,16 +75,21 @@ struct TelemetryPayload {
 
 pub struct TestCSC<'a> {
     summary_state: State,
+    simulation_mode: isize,
+    exit_control_grace_period: Duration,
     domain: Domain,
     index: isize,
     controller: Controller<'a>,
     controller_command_ack: Option<ControllerCommandAck>,
-    heartbeat_task: Option<task::JoinHandle<()>>,
+    heartbeat: Option<HeartbeatHandle>,
     telemetry_loop_task: Option<task::JoinHandle<()>>,
     command_sender: mpsc::Sender<CmdPayload>,
     command_receiver: mpsc::Receiver<CmdPayload>,
     telemetry_sender: watch::Sender<TelemetryPayload>,
     telemetry_receiver: watch::Receiver<TelemetryPayload>,
+    command_tasks: Vec<task::JoinHandle<()>>,
+    shutdown_handle: ShutdownHandle,
+    shutdown_receiver: watch::Receiver<bool>,
 }
 
 impl<'a> TestCSC<'a> {
@@ -88,21 +106,58 @@ impl<'a> TestCSC<'a> {
             watch::Receiver<TelemetryPayload>,
         ) = watch::channel(TelemetryPayload::default());
 
+        let (shutdown_handle, shutdown_receiver) = ShutdownHandle::channel();
+
         Ok(TestCSC {
             summary_state: State::Standby,
+            simulation_mode: 0,
+            exit_control_grace_period: EXIT_CONTROL_GRACE_PERIOD,
             domain,
             index,
             controller,
             controller_command_ack: None,
-            heartbeat_task: None,
+            heartbeat: None,
             telemetry_loop_task: None,
             command_sender,
             command_receiver,
             telemetry_sender,
             telemetry_receiver,
+            command_tasks: Vec::new(),
+            shutdown_handle,
+            shutdown_receiver,
         })
     }
 
+    /// Index of the CSC.
+    pub fn get_index(&self) -> isize {
+        self.index
+    }
+
+    /// Set the simulation mode. In simulation mode the `wait` command
+    /// completes immediately.
+    pub fn with_simulation_mode(mut self, simulation_mode: isize) -> Self {
+        self.simulation_mode = simulation_mode;
+        self
+    }
+
+    /// Set the time to wait after acknowledging exitControl before exiting.
+    ///
+    /// Tests can set it to zero to exit right away.
+    pub fn with_exit_control_grace_period(mut self, grace_period: Duration) -> Self {
+        self.exit_control_grace_period = grace_period;
+        self
+    }
+
+    /// Set a hook that decides whether a command may be executed, see
+    /// [Controller::set_command_authorizer].
+    pub fn with_command_authorizer<F>(mut self, authorizer: F) -> Self
+    where
+        F: Fn(&str, &Value) -> bool + Send + Sync + 'static,
+    {
+        self.controller.set_command_authorizer(authorizer);
+        self
+    }
+
     /// Start the CSC.
     ///
     /// This method should run only once after instantiating the CSC and will
@@ -113,10 +168,24 @@ impl<'a> TestCSC<'a> {
             return;
         };
 
+        if let Err(err) = self.write_software_versions().await {
+            log::error!("Failed to write software versions: {err:?}");
+        };
+
+        if let Ok(config_dir) = env::var("LSST_CONFIG_PATH") {
+            if let Err(err) = self
+                .controller
+                .publish_available_configurations(Path::new(&config_dir))
+                .await
+            {
+                log::error!("Failed to write available configurations: {err:?}");
+            }
+        }
+
         let sal_info = SalInfo::new("Test", self.index).unwrap();
 
         log::debug!("Registering schema.");
-        sal_info.register_schema().await;
+        sal_info.register_schema_for_domain(&self.domain).await;
 
         log::debug!("Registering topics: {:?}.", sal_info.get_topics_name());
 
@@ -124,32 +193,10 @@ impl<'a> TestCSC<'a> {
             log::warn!("Failed to register topics: {error:?}. Continuing...");
         }
 
-        let mut heartbeat_writer = WriteTopic::new("logevent_heartbeat", &sal_info, &self.domain);
-
-        let heartbeat_task = task::spawn(async move {
-            let origin = heartbeat_writer.get_origin();
-            let identity = heartbeat_writer.get_identity();
-            let sal_index = heartbeat_writer.get_index();
-            loop {
-                let seq_num = heartbeat_writer.get_seq_num();
-
-                let heartbeat_topic = Heartbeat::default()
-                    .with_timestamps()
-                    .with_sal_index(sal_index)
-                    .with_private_origin(origin)
-                    .with_private_identity(&identity)
-                    .with_private_seq_num(seq_num);
-                let write_res = heartbeat_writer
-                    .write_typed::<Heartbeat>(&heartbeat_topic)
-                    .await;
-                if write_res.is_err() {
-                    log::error!("Failed to write heartbeat data {write_res:?}.");
-                    break;
-                }
-                sleep(HEARTBEAT_TIME).await;
-            }
-        });
-        self.heartbeat_task = Some(heartbeat_task);
+        self.heartbeat = Some(start_heartbeat(
+            WriteTopic::new("logevent_heartbeat", &sal_info, &self.domain),
+            HEARTBEAT_TIME,
+        ));
 
         let controller_command_ack = ControllerCommandAck::start(&self.domain, &sal_info).await;
 
@@ -159,8 +206,10 @@ impl<'a> TestCSC<'a> {
             let command_sender = self.command_sender.clone();
             let mut controller_command =
                 ControllerCommand::new(&command, &self.domain, &sal_info).unwrap();
+            controller_command.set_authorizer(self.controller.get_command_authorizer());
+            let cmdtype = controller_command.get_command_type() as i32;
 
-            task::spawn(async move {
+            self.command_tasks.push(task::spawn(async move {
                 loop {
                     if let Ok(command_data) = controller_command.process_command().await {
                         let ack_sender = controller_command_ack_sender.clone();
@@ -169,13 +218,14 @@ impl<'a> TestCSC<'a> {
                                 CmdData {
                                     name: command.to_owned(),
                                     data: command_data,
+                                    cmdtype,
                                 },
                                 ack_sender,
                             ))
                             .await;
                     }
                 }
-            });
+            }));
         }
 
         self.controller_command_ack = Some(controller_command_ack);
@@ -183,70 +233,174 @@ impl<'a> TestCSC<'a> {
 
     /// This method runs the control loop of the CSC.
     ///
-    /// Once awaited the CSC will start to respond to commands.
+    /// Once awaited the CSC will start to respond to commands. The loop ends
+    /// when the CSC exits control or when shutdown is requested through the
+    /// [ShutdownHandle] returned by [BaseCSC::shutdown_signal]; either way
+    /// the background tasks are stopped before returning. Once shutdown is
+    /// requested no more commands are executed; commands still queued are
+    /// acknowledged as aborted.
     pub async fn run(&mut self) -> SalObjResult<()> {
-        while let Some((data, ack_channel)) = self.command_receiver.recv().await {
-            handle_command!(
-                "start",
-                "standby",
-                "enable",
-                "disable",
-                "setScalars",
-                "setArrays",
-                "fault",
-                "wait"
-            );
+        let dispatcher = TestCSC::make_command_dispatcher();
+        loop {
+            let (data, ack_channel) = tokio::select! {
+                biased;
+                _ = self.shutdown_receiver.changed() => {
+                    log::info!("Shutdown requested.");
+                    break;
+                }
+                payload = self.command_receiver.recv() => match payload {
+                    Some(payload) => payload,
+                    None => break,
+                },
+            };
+            let command_ack = dispatcher
+                .dispatch_and_ack(self, &data.name, data.data, data.cmdtype, &ack_channel)
+                .await;
+            if data.name == "command_exitControl" {
+                sleep(self.get_exit_control_grace_period()).await;
+                if command_ack.is_good() {
+                    break;
+                }
+            }
         }
+        self.stop_background_tasks().await;
         Ok(())
     }
 
+    /// Bind each command of the CSC to its handler.
+    ///
+    /// Commands without a handler are acknowledged as not implemented.
+    fn make_command_dispatcher() -> CommandDispatcher<TestCSC<'a>> {
+        CommandDispatcher::new()
+            .with_handler("command_exitControl", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_exit_control(data))
+            })
+            .with_handler("command_start", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_start(data))
+            })
+            .with_handler("command_standby", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_standby(data))
+            })
+            .with_handler("command_enable", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_enable(data))
+            })
+            .with_handler("command_disable", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_disable(data))
+            })
+            .with_handler("command_setScalars", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_set_scalars(data))
+            })
+            .with_handler("command_setArrays", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_set_arrays(data))
+            })
+            .with_handler("command_fault", |csc: &mut TestCSC<'a>, data| {
+                Box::pin(csc.do_fault(data))
+            })
+            .with_in_progress_handler("command_wait", |csc: &mut TestCSC<'a>, data, ack_sender| {
+                Box::pin(csc.do_wait(data, ack_sender))
+            })
+    }
+
+    /// Stop the heartbeat task and wait for it to finish.
+    ///
+    /// The heartbeat loop is asked to stop rather than aborted, so it exits
+    /// cleanly between writes. Does nothing if the heartbeat is not running.
+    pub async fn stop_heartbeat(&mut self) -> SalObjResult<()> {
+        match self.heartbeat.take() {
+            Some(heartbeat) => heartbeat.stop().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Stop the heartbeat, telemetry and command tasks, abort the queued
+    /// commands and flush the pending command acknowledgements.
+    async fn stop_background_tasks(&mut self) {
+        if let Err(error) = self.stop_heartbeat().await {
+            log::error!("Failed to stop heartbeat: {error:?}");
+        }
+        if let Some(telemetry_loop_task) = self.telemetry_loop_task.take() {
+            telemetry_loop_task.abort();
+        }
+        for command_task in self.command_tasks.drain(..) {
+            command_task.abort();
+            let _ = command_task.await;
+        }
+        self.reject_pending_commands().await;
+        if let Some(controller_command_ack) = self.controller_command_ack.take() {
+            controller_command_ack.close(HEARTBEAT_TIME).await;
+        }
+    }
+
+    /// Acknowledge the commands still queued as aborted, so their senders
+    /// do not wait for them to time out.
+    ///
+    /// Must be called after the command tasks are stopped, so no new command
+    /// is queued.
+    async fn reject_pending_commands(&mut self) {
+        while let Ok((data, ack_channel)) = self.command_receiver.try_recv() {
+            log::info!("Rejecting {} on shutdown.", data.name);
+            let any = from_value::<EmptyTopic>(&data.data).unwrap_or_default();
+            let command_ack = CommandAck::make_aborted(
+                any,
+                &format!("CSC shutting down; {} not executed.", data.name),
+            );
+            let _ = ack_channel
+                .send(command_ack.with_cmdtype(data.cmdtype))
+                .await;
+        }
+    }
+
     /// Respond to the exitControl command.
     ///
-    /// If the CSC is in Standby, this will terminate the CSC execution.
-    async fn do_exit_control(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        let exit_control = from_value::<ExitControl>(&data.data).unwrap();
+    /// If the CSC is in Standby, this will terminate the CSC execution. The
+    /// heartbeat is stopped and a final Offline summary state published
+    /// before the command is acknowledged.
+    async fn do_exit_control(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        let exit_control = from_value::<ExitControl>(&data).unwrap();
         let current_state = self.get_current_state();
         if current_state != State::Standby {
-            return Ok((
-                CommandAck::make_failed(
-                    exit_control,
-                    1,
-                    &format!("Invalid state transition {current_state:?} -> Offline."),
-                ),
-                ack_channel,
+            return Ok(CommandAck::make_failed(
+                exit_control,
+                1,
+                &format!("Invalid state transition {current_state:?} -> Offline."),
             ));
         }
+        // Stop the heartbeat first, so the Offline summary state is the last
+        // sign of life of the CSC.
+        self.stop_heartbeat().await?;
         self.set_summary_state(State::Offline);
         self.update_summary_state().await?;
-        Ok((CommandAck::make_complete(exit_control), ack_channel))
+        Ok(CommandAck::make_complete(exit_control))
     }
 
     /// Respond to the start command.
     ///
     /// This will transition the CSC from Standby to Disabled.
-    async fn do_start(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        log::info!("do_start received {:?}", data.name);
-        let start = from_value::<Start>(&data.data).unwrap();
+    async fn do_start(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        log::info!("do_start received.");
+        let start = from_value::<Start>(&data).unwrap();
         let current_state = self.get_current_state();
         if current_state != State::Standby {
-            return Ok((
-                CommandAck::make_failed(
-                    start,
-                    1,
-                    &format!("Invalid state transition {current_state:?} -> Disable."),
-                ),
-                ack_channel,
+            return Ok(CommandAck::make_failed(
+                start,
+                1,
+                &format!("Invalid state transition {current_state:?} -> Disable."),
             ));
         }
         let _ = self.configure(&start);
+        if let Err(err) = self.read_configuration(&start.get_configuration_override()) {
+            return Ok(CommandAck::make_failed(
+                start,
+                1,
+                &format!("Failed to read configuration: {}", err.get_error_message()),
+            ));
+        }
+        if let Err(err) = self
+            .write_log_message(LOG_LEVEL_INFO, &TestCSC::configure_message(&start))
+            .await
+        {
+            log::error!("Failed to write log message: {err:?}");
+        }
 
         let sal_info = SalInfo::new("Test", self.index).unwrap();
 
@@ -298,10 +452,14 @@ impl<'a> TestCSC<'a> {
                     if let Some(telemetry_data_to_write) = telemetry_data.get_mut(name) {
                         match telemetry_data_to_write {
                             TestTelemetry::Scalars(scalar) => {
-                                let _ = telemetry_writer.write_typed::<Scalars>(scalar).await;
+                                let scalar =
+                                    TestCSC::prepare_telemetry(scalar.clone(), telemetry_writer);
+                                let _ = telemetry_writer.write_typed::<Scalars>(&scalar).await;
                             }
                             TestTelemetry::Arrays(array) => {
-                                let _ = telemetry_writer.write_typed::<Arrays>(array).await;
+                                let array =
+                                    TestCSC::prepare_telemetry(array.clone(), telemetry_writer);
+                                let _ = telemetry_writer.write_typed::<Arrays>(&array).await;
                             }
                             TestTelemetry::None => {}
                         }
@@ -314,82 +472,61 @@ impl<'a> TestCSC<'a> {
 
         self.set_summary_state(State::Disabled);
         self.update_summary_state().await?;
-        Ok((CommandAck::make_complete(start), ack_channel))
+        Ok(CommandAck::make_complete(start))
     }
 
     /// Respond to the standby command.
     ///
     /// This command will transition the CSC from Fault or Disabled into
     /// Standby.
-    async fn do_standby(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        log::info!("do_standby received {:?}", data.name);
-        let standby = from_value::<Standby>(&data.data).unwrap();
+    async fn do_standby(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        log::info!("do_standby received.");
+        let standby = from_value::<Standby>(&data).unwrap();
         let current_state = self.get_current_state();
         if !HashSet::from([State::Fault, State::Disabled]).contains(&current_state) {
-            return Ok((
-                CommandAck::make_failed(
-                    standby,
-                    1,
-                    &format!("Invalid state transition {current_state:?} -> Standby."),
-                ),
-                ack_channel,
+            return Ok(CommandAck::make_failed(
+                standby,
+                1,
+                &format!("Invalid state transition {current_state:?} -> Standby."),
             ));
         }
         self.set_summary_state(State::Standby);
         self.update_summary_state().await?;
-        Ok((CommandAck::make_complete(standby), ack_channel))
+        Ok(CommandAck::make_complete(standby))
     }
 
     /// Respond to the enable command.
     ///
     /// This command will transition the CSC from Disabled to Enabled.
-    async fn do_enable(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        log::info!("do_enable received {:?}", data.name);
-        let enable = from_value::<Enable>(&data.data).unwrap();
+    async fn do_enable(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        log::info!("do_enable received.");
+        let enable = from_value::<Enable>(&data).unwrap();
         let current_state = self.get_current_state();
         if current_state != State::Disabled {
-            return Ok((
-                CommandAck::make_failed(
-                    enable,
-                    1,
-                    &format!("Invalid state transition {current_state:?} -> Enabled."),
-                ),
-                ack_channel,
+            return Ok(CommandAck::make_failed(
+                enable,
+                1,
+                &format!("Invalid state transition {current_state:?} -> Enabled."),
             ));
         }
         self.set_summary_state(State::Enabled);
         self.update_summary_state().await?;
 
-        Ok((CommandAck::make_complete(enable), ack_channel))
+        Ok(CommandAck::make_complete(enable))
     }
 
     /// Respond to the disable command.
     ///
     /// This command will transition the CSC from Enabled to Disabled.
-    async fn do_disable(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        log::info!("do_disabled received {:?}", data.name);
-        let disable = from_value::<Disable>(&data.data).unwrap();
+    async fn do_disable(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        log::info!("do_disabled received.");
+        let disable = from_value::<Disable>(&data).unwrap();
         let current_state = self.get_current_state();
         if current_state != State::Enabled {
-            return Ok((
-                CommandAck::make_failed(
-                    disable,
-                    1,
-                    &format!("Invalid state transition {current_state:?} -> Disable."),
-                ),
-                ack_channel,
+            return Ok(CommandAck::make_failed(
+                disable,
+                1,
+                &format!("Invalid state transition {current_state:?} -> Disable."),
             ));
         }
         self.set_summary_state(State::Disabled);
@@ -398,7 +535,7 @@ impl<'a> TestCSC<'a> {
             log::debug!("Stopping telemetry task.");
             telemetry_loop_task.abort();
         }
-        Ok((CommandAck::make_complete(disable), ack_channel))
+        Ok(CommandAck::make_complete(disable))
     }
 
     /// Respond to the setScalars command.
@@ -406,23 +543,16 @@ impl<'a> TestCSC<'a> {
     /// This command is only valid when the CSC is in Enabled state. It will
     /// publish an event with the values of scalars topics and update the
     /// scalars telemetry.
-    async fn do_set_scalars(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        if let Ok(mut scalars) = from_value::<Scalars>(&data.data) {
+    async fn do_set_scalars(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        if let Ok(mut scalars) = from_value::<Scalars>(&data) {
             log::debug!("setScalars received: {scalars:?}");
             let current_state = self.get_current_state();
             if current_state != State::Enabled {
                 log::debug!("Invalid, current state {current_state}.");
-                return Ok((
-                    CommandAck::make_failed(
-                        scalars,
-                        1,
-                        &format!("Command setScalars not allowed in {current_state:?}."),
-                    ),
-                    ack_channel,
+                return Ok(CommandAck::make_failed(
+                    scalars,
+                    1,
+                    &format!("Command setScalars not allowed in {current_state:?}."),
                 ));
             }
             let original_scalars = scalars.clone();
@@ -439,12 +569,13 @@ impl<'a> TestCSC<'a> {
                 });
                 log::debug!("setScalars telemetry sent, command completed.");
 
-                Ok((CommandAck::make_complete(original_scalars), ack_channel))
+                Ok(CommandAck::make_complete(original_scalars))
             } else {
                 log::debug!("setScalars command failed.");
-                Ok((
-                    CommandAck::make_failed(original_scalars, 1, "Failed to parse event data"),
-                    ack_channel,
+                Ok(CommandAck::make_failed(
+                    original_scalars,
+                    1,
+                    "Failed to parse event data",
                 ))
             }
         } else {
@@ -456,22 +587,15 @@ impl<'a> TestCSC<'a> {
     /// Respond to a setArrays command.
     ///
     /// This is similar to the setScalars command but for arrays instead.
-    async fn do_set_arrays(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        match from_value::<Arrays>(&data.data) {
+    async fn do_set_arrays(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        match from_value::<Arrays>(&data) {
             Ok(mut arrays) => {
                 let current_state = self.get_current_state();
                 if current_state != State::Enabled {
-                    return Ok((
-                        CommandAck::make_failed(
-                            arrays,
-                            1,
-                            &format!("Command setArrays not allowed in {current_state:?}."),
-                        ),
-                        ack_channel,
+                    return Ok(CommandAck::make_failed(
+                        arrays,
+                        1,
+                        &format!("Command setArrays not allowed in {current_state:?}."),
                     ));
                 }
                 let original_arrays = arrays.clone();
@@ -485,11 +609,12 @@ impl<'a> TestCSC<'a> {
                         name: "arrays".to_owned(),
                         data: TestTelemetry::Arrays(arrays.clone()),
                     });
-                    Ok((CommandAck::make_complete(original_arrays), ack_channel))
+                    Ok(CommandAck::make_complete(original_arrays))
                 } else {
-                    Ok((
-                        CommandAck::make_failed(original_arrays, 1, "Failed to parse event data"),
-                        ack_channel,
+                    Ok(CommandAck::make_failed(
+                        original_arrays,
+                        1,
+                        "Failed to parse event data",
                     ))
                 }
             }
@@ -504,16 +629,12 @@ impl<'a> TestCSC<'a> {
     /// Respond to the fault command.
     ///
     /// This will send the CSC to Fault.
-    async fn do_fault(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        match from_value::<EmptyTopic>(&data.data) {
+    async fn do_fault(&mut self, data: Value) -> SalObjResult<CommandAck> {
+        match from_value::<EmptyTopic>(&data) {
             Ok(fault) => {
                 self.set_summary_state(State::Fault);
                 self.update_summary_state().await?;
-                Ok((CommandAck::make_complete(fault), ack_channel))
+                Ok(CommandAck::make_complete(fault))
             }
             Err(error) => {
                 let error_message = format!("Cannot parse data: {error}");
@@ -525,39 +646,50 @@ impl<'a> TestCSC<'a> {
 
     /// Respond to the wait command.
     ///
-    /// This command will wait for the specified duration before completing.
-    /// If many commands are sent at the same time they are all executed in
-    /// parallel.
-    async fn do_wait(
-        &mut self,
-        data: &CmdData,
-        ack_channel: mpsc::Sender<CommandAck>,
-    ) -> SalObjResult<CommandAckResult> {
-        match from_value::<Wait>(&data.data) {
+    /// This command will wait for the specified duration, in seconds, before
+    /// completing; the duration must be positive and finite. If many
+    /// commands are sent at the same time they are all executed in parallel.
+    async fn do_wait(&mut self, data: Value, ack_channel: AckSender) -> SalObjResult<CommandAck> {
+        match from_value::<Wait>(&data) {
             Ok(wait) => {
                 let current_state = self.get_current_state();
                 if current_state != State::Enabled {
-                    return Ok((
-                        CommandAck::make_failed(
-                            wait,
-                            1,
-                            &format!("Command wait not allowed in {current_state:?}."),
-                        ),
-                        ack_channel,
+                    return Ok(CommandAck::make_failed(
+                        wait,
+                        1,
+                        &format!("Command wait not allowed in {current_state:?}."),
                     ));
                 }
 
-                let timeout = Duration::from_secs((wait.duration * 2.0) as u64);
+                // try_from_secs_f64 rejects negative, non-finite and
+                // overflowing values but accepts zero.
+                let timeout = match Duration::try_from_secs_f64(wait.duration * 2.0) {
+                    Ok(timeout) if wait.duration > 0.0 => timeout,
+                    _ => {
+                        let duration = wait.duration;
+                        return Ok(CommandAck::make_failed(
+                            wait,
+                            1,
+                            &format!(
+                                "Invalid wait duration {duration}; must be positive and finite."
+                            ),
+                        ));
+                    }
+                };
+
+                if self.is_simulating() {
+                    return Ok(CommandAck::make_complete(wait));
+                }
 
                 let wait_data = wait.clone();
-                let ack_channel_process = ack_channel.clone();
                 let _ = task::spawn(async move {
-                    TestCSC::wait_and_ack(wait_data, ack_channel_process).await;
+                    TestCSC::wait_and_ack(wait_data, ack_channel).await;
                 });
 
-                Ok((
-                    CommandAck::make_in_progress(wait, timeout, "Wait command in progress."),
-                    ack_channel,
+                Ok(CommandAck::make_in_progress(
+                    wait,
+                    timeout,
+                    "Wait command in progress.",
                 ))
             }
             Err(error) => {
@@ -589,12 +721,57 @@ impl<'a> TestCSC<'a> {
         Ok(())
     }
 
+    /// Publish the softwareVersions event.
+    async fn write_software_versions(&mut self) -> SalObjResult<()> {
+        let software_versions = self
+            .controller
+            .get_event_to_write::<SoftwareVersion>("logevent_softwareVersions")?
+            .with_csc_version(env!("CARGO_PKG_VERSION"));
+
+        self.controller
+            .write_event("logevent_softwareVersions", &software_versions)
+            .await?;
+        Ok(())
+    }
+
+    /// Publish a logMessage event.
+    async fn write_log_message(&mut self, level: isize, message: &str) -> SalObjResult<()> {
+        let log_message = self
+            .controller
+            .get_event_to_write::<LogMessage>("logevent_logMessage")?
+            .with_name("Test")
+            .with_level(level)
+            .with_message(message);
+
+        self.controller
+            .write_event("logevent_logMessage", &log_message)
+            .await?;
+        Ok(())
+    }
+
+    /// Message logged when configuring the CSC from the start command.
+    fn configure_message(data: &Start) -> String {
+        format!(
+            "Received {} configuration override.",
+            data.get_configuration_override()
+        )
+    }
+
+    /// Fill in the private fields of a telemetry sample before writing it.
+    fn prepare_telemetry<T: BaseSALTopic>(data: T, telemetry_writer: &WriteTopic) -> T {
+        data.with_timestamps()
+            .with_private_seq_num(telemetry_writer.get_seq_num())
+            .with_private_origin(telemetry_writer.get_origin())
+            .with_private_identity(&telemetry_writer.get_identity())
+            .with_sal_index(telemetry_writer.get_index())
+    }
+
     /// A task that will wait for a specified duration and then acknowledge
     /// a command.
     ///
     /// This is used by the TestCSC::do_wait method to implement the command response.
     async fn wait_and_ack(wait: Wait, ack_channel: mpsc::Sender<CommandAck>) {
-        let wait_time = Duration::from_secs(wait.duration as u64);
+        let wait_time = Duration::from_secs_f64(wait.duration);
         sleep(wait_time).await;
         let _ = ack_channel.send(CommandAck::make_complete(wait)).await;
     }
@@ -609,11 +786,20 @@ impl<'a> BaseCSC for TestCSC<'a> {
         self.summary_state = new_state;
     }
 
+    fn shutdown_signal(&self) -> Option<ShutdownHandle> {
+        Some(self.shutdown_handle.clone())
+    }
+
+    fn get_exit_control_grace_period(&self) -> Duration {
+        self.exit_control_grace_period
+    }
+
+    fn get_simulation_mode(&self) -> isize {
+        self.simulation_mode
+    }
+
     fn configure(&mut self, data: &Start) -> SalObjResult<()> {
-        log::info!(
-            "Received {} configuration override.",
-            data.get_configuration_override()
-        );
+        log::info!("{}", TestCSC::configure_message(data));
         Ok(())
     }
 }
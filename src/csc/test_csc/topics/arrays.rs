@@ -2,6 +2,9 @@ use crate::{topics::base_sal_topic::BaseSALTopic, utils::xml_utils::get_default_
 use base_topic_derive::{add_sal_topic_fields, BaseSALTopic};
 use chrono::Utc;
 
+/// Number of elements in each array field, as defined in the interface.
+pub const ARRAY_LEN: usize = 5;
+
 #[add_sal_topic_fields]
 #[derive(Debug, Deserialize, Serialize, BaseSALTopic, Clone)]
 pub struct Arrays {
@@ -47,3 +50,116 @@ impl Default for Arrays {
         }
     }
 }
+
+/// Typed setters for the array fields.
+///
+/// Taking fixed size arrays guarantees the fields have the number of
+/// elements the interface expects.
+impl Arrays {
+    pub fn with_boolean0(mut self, boolean0: [bool; ARRAY_LEN]) -> Self {
+        self.boolean0 = boolean0.to_vec();
+        self
+    }
+
+    pub fn with_byte0(mut self, byte0: [u8; ARRAY_LEN]) -> Self {
+        self.byte0 = byte0.to_vec();
+        self
+    }
+
+    pub fn with_short0(mut self, short0: [i16; ARRAY_LEN]) -> Self {
+        self.short0 = short0.to_vec();
+        self
+    }
+
+    pub fn with_int0(mut self, int0: [i32; ARRAY_LEN]) -> Self {
+        self.int0 = int0.to_vec();
+        self
+    }
+
+    pub fn with_long0(mut self, long0: [i32; ARRAY_LEN]) -> Self {
+        self.long0 = long0.to_vec();
+        self
+    }
+
+    pub fn with_long_long0(mut self, long_long0: [i64; ARRAY_LEN]) -> Self {
+        self.long_long0 = long_long0.to_vec();
+        self
+    }
+
+    pub fn with_unsigned_short0(mut self, unsigned_short0: [u16; ARRAY_LEN]) -> Self {
+        self.unsigned_short0 = unsigned_short0.to_vec();
+        self
+    }
+
+    pub fn with_unsigned_int0(mut self, unsigned_int0: [u64; ARRAY_LEN]) -> Self {
+        self.unsigned_int0 = unsigned_int0.to_vec();
+        self
+    }
+
+    pub fn with_float0(mut self, float0: [f32; ARRAY_LEN]) -> Self {
+        self.float0 = float0.to_vec();
+        self
+    }
+
+    pub fn with_double0(mut self, double0: [f64; ARRAY_LEN]) -> Self {
+        self.double0 = double0.to_vec();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::sal_info::SalInfo;
+    use apache_avro::{to_value, types::Value};
+
+    #[test]
+    fn with_arrays_serialize() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let schema = sal_info
+            .get_topic_info("command_setArrays")
+            .and_then(|topic_info| topic_info.get_schema_ref())
+            .unwrap();
+
+        let arrays = Arrays::default()
+            .with_boolean0([true, false, true, false, true])
+            .with_byte0([1, 2, 3, 4, 255])
+            .with_int0([-1, 0, 1, 2, i32::MAX])
+            .with_unsigned_int0([0, 1, 2, 3, u32::MAX as u64])
+            .with_double0([0.5, -1.5, 2.5, -3.5, 4.5]);
+
+        let value = to_value(&arrays).unwrap();
+
+        assert!(value.validate(schema));
+        if let Value::Record(fields) = value {
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|(field_name, _)| field_name == name)
+                    .map(|(_, value)| value.clone())
+                    .unwrap()
+            };
+            assert_eq!(
+                field("int0"),
+                Value::Array(
+                    [-1, 0, 1, 2, i32::MAX]
+                        .into_iter()
+                        .map(Value::Int)
+                        .collect()
+                )
+            );
+            assert_eq!(
+                field("unsignedInt0"),
+                Value::Array(
+                    [0, 1, 2, 3, u32::MAX as i64]
+                        .into_iter()
+                        .map(Value::Long)
+                        .collect()
+                )
+            );
+        } else {
+            panic!("Arrays did not serialize to a record.");
+        }
+    }
+}
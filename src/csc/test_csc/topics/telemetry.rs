@@ -1,11 +1,43 @@
+use base_topic_derive::TopicEnum;
+
 use super::{arrays::Arrays, scalars::Scalars};
 
 /// Define all the telemetry types for the Test CSC.
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, TopicEnum)]
 pub enum TestTelemetry {
     #[default]
     None,
+    #[topic("scalars")]
     Scalars(Scalars),
+    #[topic("arrays")]
     Arrays(Arrays),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topics::topic_enum::TopicEnum;
+    use apache_avro::to_value;
+
+    #[test]
+    fn get_topic_names() {
+        assert_eq!(TestTelemetry::get_topic_names(), vec!["scalars", "arrays"]);
+    }
+
+    #[test]
+    fn from_topic_data() {
+        let mut scalars = Scalars::default();
+        scalars.int0 = 5;
+        let data = to_value(&scalars).unwrap();
+
+        match TestTelemetry::from_topic_data("scalars", &data) {
+            Some(Ok(TestTelemetry::Scalars(decoded))) => assert_eq!(decoded.int0, 5),
+            _ => panic!("Expected scalars."),
+        }
+        assert!(TestTelemetry::from_topic_data("arrays", &data)
+            .unwrap()
+            .is_err());
+        assert!(TestTelemetry::from_topic_data("logevent_scalars", &data).is_none());
+    }
+}
@@ -6,9 +6,16 @@
 //! When developing applications with salobj you will want to reduce the number
 //! of [Domain] instances, ideally having only one per application.
 
+use crate::kafka_config::KafkaExtraConfig;
 use kafka::client::KafkaClient;
 use kafka::error::Error as KafkaError;
+use schema_registry_converter::async_impl::{
+    avro::{AvroDecoder, AvroEncoder},
+    schema_registry::SrSettings,
+};
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::sync::{Arc, Mutex};
 use std::{process, thread, time::Duration};
 use whoami;
 
@@ -16,11 +23,83 @@ const MAX_ITER_LOAD_METADATA: u8 = 5;
 const POOL_CLIENT_WAIT_TIME: Duration = Duration::from_millis(500);
 const DEFAULT_LSST_KAFKA_CLIENT_ADDR: &str = "localhost:9092";
 const DEFAULT_LSST_SCHEMA_REGISTRY_URL: &str = "http://127.0.0.1:8081";
+/// Known sites as `(site, broker address, schema registry url)`.
+///
+/// Other sites, or different endpoints for these ones, can be given with the
+/// LSST_SITE_ENDPOINTS environment variable.
+const SITE_ENDPOINTS: &[(&str, &str, &str)] = &[(
+    "local",
+    DEFAULT_LSST_KAFKA_CLIENT_ADDR,
+    DEFAULT_LSST_SCHEMA_REGISTRY_URL,
+)];
+
+/// Kafka broker and schema registry endpoints of a site.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct SiteEndpoints {
+    broker_addr: String,
+    schema_registry_url: String,
+}
+
+/// Entry of a controller or remote in [Domain::active_components].
+///
+/// The entry is removed when this is dropped.
+pub(crate) struct ComponentRegistration {
+    active_components: Arc<Mutex<Vec<(String, isize)>>>,
+    name: String,
+    index: isize,
+}
+
+impl ComponentRegistration {
+    /// Move the entry to another index of the component.
+    pub(crate) fn set_index(&mut self, index: isize) {
+        if let Ok(mut active_components) = self.active_components.lock() {
+            if let Some(component) = active_components
+                .iter_mut()
+                .find(|(name, component_index)| {
+                    *name == self.name && *component_index == self.index
+                })
+            {
+                component.1 = index;
+            }
+        }
+        self.index = index;
+    }
+}
+
+impl Drop for ComponentRegistration {
+    fn drop(&mut self) {
+        if let Ok(mut active_components) = self.active_components.lock() {
+            if let Some(position) = active_components
+                .iter()
+                .position(|(name, index)| *name == self.name && *index == self.index)
+            {
+                active_components.remove(position);
+            }
+        }
+    }
+}
 
 pub struct Domain {
     origin: u32,
     identity: Option<String>,
     kafka_client: KafkaClient,
+    /// Extra properties applied to the kafka clients created for this domain.
+    kafka_extra_config: KafkaExtraConfig,
+    /// Topics already registered with this domain.
+    registered_topics: HashSet<String>,
+    /// Components, as `(name, index)`, with a live controller or remote
+    /// created with this domain, one entry per controller or remote, in
+    /// creation order.
+    active_components: Arc<Mutex<Vec<(String, isize)>>>,
+    /// Number of times topic metadata was loaded from the broker.
+    registration_passes: usize,
+    /// Assume topic schemas are already in the schema registry and never
+    /// try to register them.
+    assume_schemas_registered: bool,
+    /// Avro encoder shared by all the topics written with this domain.
+    encoder: Arc<AvroEncoder<'static>>,
+    /// Avro decoder shared by all the topics read with this domain.
+    decoder: Arc<AvroDecoder<'static>>,
 }
 
 impl Default for Domain {
@@ -32,13 +111,97 @@ impl Default for Domain {
 impl Domain {
     /// Create a new instance of Domain,
     pub fn new() -> Domain {
+        let kafka_extra_config = KafkaExtraConfig::from_env();
+        let mut kafka_client = KafkaClient::new(Domain::get_client_hosts());
+        kafka_extra_config.apply_to_client(&mut kafka_client);
+
         Domain {
             origin: process::id(),
             identity: None,
-            kafka_client: KafkaClient::new(Domain::get_client_hosts()),
+            kafka_client,
+            kafka_extra_config,
+            registered_topics: HashSet::new(),
+            active_components: Arc::new(Mutex::new(Vec::new())),
+            registration_passes: 0,
+            assume_schemas_registered: Domain::parse_assume_schemas_registered(
+                env::var("LSST_ASSUME_SCHEMAS_REGISTERED").ok(),
+            ),
+            encoder: Arc::new(AvroEncoder::new(SrSettings::new(
+                Domain::get_schema_registry_url(),
+            ))),
+            decoder: Arc::new(AvroDecoder::new(SrSettings::new(
+                Domain::get_schema_registry_url(),
+            ))),
         }
     }
 
+    /// Get the avro encoder shared by the topics of this domain.
+    ///
+    /// The encoder caches the schemas retrieved from the schema registry, so
+    /// sharing it reduces the number of requests to the registry.
+    pub fn encoder(&self) -> Arc<AvroEncoder<'static>> {
+        self.encoder.clone()
+    }
+
+    /// Get the avro decoder shared by the topics of this domain.
+    pub fn decoder(&self) -> Arc<AvroDecoder<'static>> {
+        self.decoder.clone()
+    }
+
+    /// Override the origin identifier.
+    ///
+    /// By default the origin is the process id, which changes on every run.
+    /// A fixed origin is useful to make tests reproducible.
+    pub fn with_origin(mut self, origin: u32) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Assume the topic schemas are already registered.
+    ///
+    /// This is needed when the schema registry is locked down and clients
+    /// are not allowed to register schemas. Encoders always use the latest
+    /// schema registered for the topic subject, so nothing else changes.
+    ///
+    /// The default is read from the LSST_ASSUME_SCHEMAS_REGISTERED
+    /// environment variable (`true` or `1`).
+    pub fn with_assume_schemas_registered(mut self, assume_schemas_registered: bool) -> Self {
+        self.assume_schemas_registered = assume_schemas_registered;
+        self
+    }
+
+    /// Are topic schemas assumed to be registered?
+    pub fn assume_schemas_registered(&self) -> bool {
+        self.assume_schemas_registered
+    }
+
+    /// Set extra kafka client properties.
+    ///
+    /// The properties are applied to the admin client right away and to the
+    /// producers and consumers created afterwards. The default is read from
+    /// the LSST_KAFKA_EXTRA_CONFIG environment variable, see
+    /// [KafkaExtraConfig].
+    pub fn with_kafka_extra_config(mut self, kafka_extra_config: KafkaExtraConfig) -> Self {
+        kafka_extra_config.apply_to_client(&mut self.kafka_client);
+        self.kafka_extra_config = kafka_extra_config;
+        self
+    }
+
+    /// Get the extra kafka client properties.
+    pub fn get_kafka_extra_config(&self) -> &KafkaExtraConfig {
+        &self.kafka_extra_config
+    }
+
+    fn parse_assume_schemas_registered(value: Option<String>) -> bool {
+        matches!(
+            value
+                .as_deref()
+                .map(|value| value.trim().to_lowercase())
+                .as_deref(),
+            Some("true") | Some("1")
+        )
+    }
+
     /// Return the default identify.
     pub fn get_default_identity(&self) -> String {
         let username = whoami::username();
@@ -58,74 +221,183 @@ impl Domain {
     }
 
     /// Register topics.
+    ///
+    /// Topics already registered with this domain are skipped, so creating
+    /// many remotes or controllers for the same component only loads the
+    /// topic metadata once.
     pub fn register_topics<T: AsRef<str>>(&mut self, topics: &[T]) -> Result<(), KafkaError> {
+        let topics: Vec<&str> = topics
+            .iter()
+            .map(|topic| topic.as_ref())
+            .filter(|topic| !self.registered_topics.contains(*topic))
+            .collect();
+
+        if topics.is_empty() {
+            return Ok(());
+        }
+
+        self.registration_passes += 1;
+        log::debug!(
+            "Registration pass {} for {} topics.",
+            self.registration_passes,
+            topics.len()
+        );
+
         for _ in 0..MAX_ITER_LOAD_METADATA {
-            let result = self.kafka_client.load_metadata(topics);
-            match result {
-                Ok(_) => {
-                    if topics
-                        .iter()
-                        .filter_map(|topic| {
-                            if self
-                                .kafka_client
-                                .topics()
-                                .partitions(topic.as_ref())
-                                .map(|p| p.len())
-                                .unwrap_or(0)
-                                > 0
-                            {
-                                None
-                            } else {
-                                Some(true)
-                            }
-                        })
-                        .next()
-                        .is_none()
-                    {
-                        return Ok(());
-                    }
-                }
-                Err(err) => return Err(err),
+            self.kafka_client.load_metadata(&topics)?;
+
+            let missing_topics: Vec<&str> = topics
+                .iter()
+                .copied()
+                .filter(|topic| {
+                    self.kafka_client
+                        .topics()
+                        .partitions(topic)
+                        .map(|p| p.len())
+                        .unwrap_or(0)
+                        == 0
+                })
+                .collect();
+
+            if missing_topics.is_empty() {
+                self.registered_topics
+                    .extend(topics.iter().map(|topic| topic.to_string()));
+                return Ok(());
             }
             thread::sleep(POOL_CLIENT_WAIT_TIME);
         }
         Ok(())
     }
 
+    /// Was the topic registered with this domain?
+    pub fn is_topic_registered(&self, topic: &str) -> bool {
+        self.registered_topics.contains(topic)
+    }
+
+    /// Record a component with a controller or remote created with this
+    /// domain.
+    ///
+    /// The component is listed by [Domain::active_components] until the
+    /// returned registration is dropped.
+    pub(crate) fn register_component(&mut self, name: &str, index: isize) -> ComponentRegistration {
+        if let Ok(mut active_components) = self.active_components.lock() {
+            active_components.push((name.to_owned(), index));
+        }
+        ComponentRegistration {
+            active_components: self.active_components.clone(),
+            name: name.to_owned(),
+            index,
+        }
+    }
+
+    /// Get the components, as `(name, index)`, with a live controller or
+    /// remote created with this domain, in creation order.
+    ///
+    /// Each component is listed once, even if it has several controllers or
+    /// remotes.
+    pub fn active_components(&self) -> Vec<(String, isize)> {
+        let mut components: Vec<(String, isize)> = Vec::new();
+        if let Ok(active_components) = self.active_components.lock() {
+            for component in active_components.iter() {
+                if !components.contains(component) {
+                    components.push(component.clone());
+                }
+            }
+        }
+        components
+    }
+
     /// Get client host address.
     ///
-    /// This method will look for the LSST_KAFKA_CLIENT_ADDR environment
-    /// variable and return a default value if it is not set. Usually the
-    /// default is only good enough for local testing. For production this
-    /// environment variable should be set.
+    /// This method will look for the LSST_KAFKA_BROKER_ADDR environment
+    /// variable, falling back to the legacy LSST_KAFKA_CLIENT_ADDR, then to
+    /// the broker of the site in LSST_SITE, and return a default value if
+    /// none is set. Usually the default is only good enough for local
+    /// testing.
     pub fn get_client_hosts() -> Vec<String> {
-        match env::var("LSST_KAFKA_CLIENT_ADDR") {
-            Ok(kafka_client_addr) => kafka_client_addr
-                .split(',')
-                .map(|addr| addr.to_owned())
-                .collect(),
-            Err(_) => vec![DEFAULT_LSST_KAFKA_CLIENT_ADDR.to_owned()],
+        Domain::parse_client_hosts(
+            env::var("LSST_KAFKA_BROKER_ADDR").ok(),
+            env::var("LSST_KAFKA_CLIENT_ADDR")
+                .ok()
+                .or_else(|| Domain::get_site_endpoints().map(|site| site.broker_addr)),
+        )
+    }
+
+    /// Parse the comma separated list of kafka hosts, giving precedence to
+    /// the broker address over the client address.
+    fn parse_client_hosts(
+        kafka_broker_addr: Option<String>,
+        kafka_client_addr: Option<String>,
+    ) -> Vec<String> {
+        match kafka_broker_addr.or(kafka_client_addr) {
+            Some(kafka_addr) => kafka_addr.split(',').map(|addr| addr.to_owned()).collect(),
+            None => vec![DEFAULT_LSST_KAFKA_CLIENT_ADDR.to_owned()],
         }
     }
 
     /// Get schema registry url.
     ///
     /// This method will look for the LSST_SCHEMA_REGISTRY_URL environment
-    /// variable and return a default value if it is not set. Usually the
-    /// default is only good enough for local testing. For production this
-    /// environment variable should be set.
+    /// variable, falling back to the schema registry of the site in
+    /// LSST_SITE, and return a default value if neither is set. Usually the
+    /// default is only good enough for local testing.
     pub fn get_schema_registry_url() -> String {
-        match env::var("LSST_SCHEMA_REGISTRY_URL") {
-            Ok(schema_registry_url) => schema_registry_url,
-            Err(_) => DEFAULT_LSST_SCHEMA_REGISTRY_URL.to_owned(),
+        env::var("LSST_SCHEMA_REGISTRY_URL")
+            .ok()
+            .or_else(|| Domain::get_site_endpoints().map(|site| site.schema_registry_url))
+            .unwrap_or_else(|| DEFAULT_LSST_SCHEMA_REGISTRY_URL.to_owned())
+    }
+
+    /// Get the endpoints of the site in the LSST_SITE environment variable.
+    ///
+    /// Warn if the site is unknown, in which case the defaults are used.
+    fn get_site_endpoints() -> Option<SiteEndpoints> {
+        let site = env::var("LSST_SITE").ok()?;
+        let site_endpoints =
+            Domain::find_site_endpoints(&site, env::var("LSST_SITE_ENDPOINTS").ok());
+        if site_endpoints.is_none() {
+            log::warn!(
+                "Unknown LSST_SITE {site}; using the default endpoints. \
+                Add it to LSST_SITE_ENDPOINTS."
+            );
         }
+        site_endpoints
+    }
+
+    /// Find the endpoints of a site.
+    ///
+    /// `site_endpoints` is a json object mapping site names to their
+    /// `broker_addr` and `schema_registry_url`. Sites there take precedence
+    /// over the known sites.
+    fn find_site_endpoints(site: &str, site_endpoints: Option<String>) -> Option<SiteEndpoints> {
+        let overrides: HashMap<String, SiteEndpoints> = match site_endpoints {
+            Some(site_endpoints) => serde_json::from_str(&site_endpoints).unwrap_or_else(|error| {
+                log::warn!("Ignoring invalid LSST_SITE_ENDPOINTS: {error}.");
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+
+        overrides.get(site).cloned().or_else(|| {
+            SITE_ENDPOINTS
+                .iter()
+                .find(|(name, _, _)| *name == site)
+                .map(|(_, broker_addr, schema_registry_url)| SiteEndpoints {
+                    broker_addr: broker_addr.to_string(),
+                    schema_registry_url: schema_registry_url.to_string(),
+                })
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Domain, DEFAULT_LSST_KAFKA_CLIENT_ADDR, DEFAULT_LSST_SCHEMA_REGISTRY_URL};
-    use std::env;
+    use super::{
+        Domain, KafkaExtraConfig, DEFAULT_LSST_KAFKA_CLIENT_ADDR, DEFAULT_LSST_SCHEMA_REGISTRY_URL,
+    };
+    use crate::{
+        controller::Controller, remote::Remote, sal_info::SalInfo, utils::testing::with_env_vars,
+    };
 
     #[test]
     fn get_default_identity() {
@@ -138,31 +410,171 @@ mod tests {
 
     #[test]
     fn get_client_hosts_env_not_set() {
-        let current_lsst_kafka_client_addr = env::var("LSST_KAFKA_CLIENT_ADDR");
+        let client_hosts = with_env_vars(
+            &[
+                ("LSST_KAFKA_CLIENT_ADDR", None),
+                ("LSST_KAFKA_BROKER_ADDR", None),
+                ("LSST_SITE", None),
+                ("LSST_SITE_ENDPOINTS", None),
+            ],
+            Domain::get_client_hosts,
+        );
 
-        if current_lsst_kafka_client_addr.is_ok() {
-            env::remove_var("LSST_KAFKA_CLIENT_ADDR");
-        }
+        assert_eq!(
+            client_hosts,
+            vec![DEFAULT_LSST_KAFKA_CLIENT_ADDR.to_owned()]
+        )
+    }
 
-        let default_value = DEFAULT_LSST_KAFKA_CLIENT_ADDR.to_owned();
-        let value = Domain::get_client_hosts()[0].to_owned();
-        if current_lsst_kafka_client_addr.is_ok() {
-            env::set_var(
-                "LSST_KAFKA_CLIENT_ADDR",
-                current_lsst_kafka_client_addr.unwrap(),
-            );
-        }
-        assert_eq!(value, default_value)
+    #[test]
+    fn parse_assume_schemas_registered() {
+        assert!(Domain::parse_assume_schemas_registered(Some(
+            "true".to_owned()
+        )));
+        assert!(Domain::parse_assume_schemas_registered(Some(
+            "1".to_owned()
+        )));
+        assert!(Domain::parse_assume_schemas_registered(Some(
+            "True".to_owned()
+        )));
+        assert!(!Domain::parse_assume_schemas_registered(Some(
+            "false".to_owned()
+        )));
+        assert!(!Domain::parse_assume_schemas_registered(None));
     }
 
     #[test]
-    fn get_client_hosts_env_set() {
-        env::set_var(
-            "LSST_KAFKA_CLIENT_ADDR",
-            "kafka_client_1:9092,kafka_client_2:9092",
+    fn with_origin() {
+        let domain = Domain::new().with_origin(1234);
+
+        assert_eq!(domain.get_origin(), 1234);
+    }
+
+    #[test]
+    fn register_topics_once() {
+        let mut domain = Domain::new();
+
+        Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+        Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+
+        assert_eq!(domain.registration_passes, 1);
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        assert!(sal_info
+            .get_topics_name()
+            .iter()
+            .all(|topic| domain.is_topic_registered(topic)));
+    }
+
+    #[test]
+    fn active_components() {
+        let mut domain = Domain::new();
+
+        let controller_1 = Controller::new(&mut domain, "Test", 1).unwrap();
+        let controller_2 = Controller::new(&mut domain, "Test", 2).unwrap();
+        let remote = Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+
+        assert_eq!(
+            domain.active_components(),
+            vec![("Test".to_owned(), 1), ("Test".to_owned(), 2)]
         );
 
-        let client_hosts = Domain::get_client_hosts();
+        drop(controller_2);
+        drop(controller_1);
+
+        assert_eq!(domain.active_components(), vec![("Test".to_owned(), 1)]);
+
+        drop(remote);
+
+        assert!(domain.active_components().is_empty());
+    }
+
+    #[test]
+    fn active_components_with_index() {
+        let mut domain = Domain::new();
+
+        let mut remote = Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+        remote.with_index(3).unwrap();
+
+        assert_eq!(domain.active_components(), vec![("Test".to_owned(), 3)]);
+    }
+
+    #[test]
+    fn with_kafka_extra_config() {
+        let domain =
+            Domain::new().with_kafka_extra_config(KafkaExtraConfig::parse("retries=9;linger.ms=5"));
+
+        assert_eq!(domain.get_kafka_extra_config().get("linger.ms"), Some("5"));
+        assert_eq!(domain.kafka_client.retry_max_attempts(), 9);
+    }
+
+    #[test]
+    fn with_assume_schemas_registered() {
+        let domain = Domain::new().with_assume_schemas_registered(true);
+
+        assert!(domain.assume_schemas_registered());
+    }
+
+    #[test]
+    fn parse_client_hosts() {
+        let broker_addr = Some("kafka_broker:9092".to_owned());
+        let client_addr = Some("kafka_client_1:9092,kafka_client_2:9092".to_owned());
+
+        assert_eq!(
+            Domain::parse_client_hosts(broker_addr.clone(), None),
+            vec!["kafka_broker:9092".to_owned()]
+        );
+        assert_eq!(
+            Domain::parse_client_hosts(None, client_addr.clone()),
+            vec![
+                "kafka_client_1:9092".to_owned(),
+                "kafka_client_2:9092".to_owned()
+            ]
+        );
+        assert_eq!(
+            Domain::parse_client_hosts(broker_addr, client_addr),
+            vec!["kafka_broker:9092".to_owned()]
+        );
+        assert_eq!(
+            Domain::parse_client_hosts(None, None),
+            vec![DEFAULT_LSST_KAFKA_CLIENT_ADDR.to_owned()]
+        );
+    }
+
+    #[test]
+    fn find_site_endpoints() {
+        let site_endpoints = Some(
+            r#"{"summit": {"broker_addr": "kafka.summit:9092", "schema_registry_url": "http://registry.summit:8081"}}"#
+                .to_owned(),
+        );
+
+        let summit = Domain::find_site_endpoints("summit", site_endpoints.clone()).unwrap();
+
+        assert_eq!(
+            Domain::parse_client_hosts(None, Some(summit.broker_addr)),
+            vec!["kafka.summit:9092".to_owned()]
+        );
+        assert_eq!(summit.schema_registry_url, "http://registry.summit:8081");
+
+        let local = Domain::find_site_endpoints("local", site_endpoints).unwrap();
+
+        assert_eq!(local.broker_addr, DEFAULT_LSST_KAFKA_CLIENT_ADDR);
+        assert_eq!(local.schema_registry_url, DEFAULT_LSST_SCHEMA_REGISTRY_URL);
+        assert!(Domain::find_site_endpoints("unknown", None).is_none());
+        assert!(Domain::find_site_endpoints("summit", Some("not json".to_owned())).is_none());
+    }
+
+    #[test]
+    fn get_client_hosts_env_set() {
+        let client_hosts = with_env_vars(
+            &[
+                (
+                    "LSST_KAFKA_CLIENT_ADDR",
+                    Some("kafka_client_1:9092,kafka_client_2:9092"),
+                ),
+                ("LSST_KAFKA_BROKER_ADDR", None),
+            ],
+            Domain::get_client_hosts,
+        );
 
         assert_eq!(client_hosts.len(), 2);
         assert!(client_hosts.contains(&"kafka_client_1:9092".to_owned()));
@@ -170,28 +582,90 @@ mod tests {
     }
 
     #[test]
-    fn get_schema_registry_url_env_not_set() {
-        if env::var("LSST_SCHEMA_REGISTRY_URL").is_ok() {
-            env::remove_var("LSST_SCHEMA_REGISTRY_URL");
-        }
+    fn get_client_hosts_broker_addr_env_set() {
+        let client_hosts = with_env_vars(
+            &[
+                ("LSST_KAFKA_CLIENT_ADDR", None),
+                ("LSST_KAFKA_BROKER_ADDR", Some("kafka_broker:9092")),
+            ],
+            Domain::get_client_hosts,
+        );
 
-        let default_value = DEFAULT_LSST_SCHEMA_REGISTRY_URL.to_owned();
+        assert_eq!(client_hosts, vec!["kafka_broker:9092".to_owned()]);
+    }
+
+    #[test]
+    fn get_endpoints_site_env_set() {
+        let site_endpoints = r#"{"summit": {"broker_addr": "kafka.summit:9092", "schema_registry_url": "http://registry.summit:8081"}}"#;
 
-        env::remove_var("LSST_KAFKA_CLIENT_ADDR");
+        let (client_hosts, schema_registry_url) = with_env_vars(
+            &[
+                ("LSST_KAFKA_CLIENT_ADDR", None),
+                ("LSST_KAFKA_BROKER_ADDR", None),
+                ("LSST_SCHEMA_REGISTRY_URL", None),
+                ("LSST_SITE", Some("summit")),
+                ("LSST_SITE_ENDPOINTS", Some(site_endpoints)),
+            ],
+            || {
+                (
+                    Domain::get_client_hosts(),
+                    Domain::get_schema_registry_url(),
+                )
+            },
+        );
 
-        assert_eq!(Domain::get_schema_registry_url(), default_value)
+        assert_eq!(client_hosts, vec!["kafka.summit:9092".to_owned()]);
+        assert_eq!(schema_registry_url, "http://registry.summit:8081");
     }
 
     #[test]
-    fn get_schema_registry_url_env_set() {
-        env::set_var(
-            "LSST_SCHEMA_REGISTRY_URL",
-            "http://lsst-schema-registry.lsst.codes:8081",
+    fn get_endpoints_unknown_site() {
+        let (client_hosts, schema_registry_url) = with_env_vars(
+            &[
+                ("LSST_KAFKA_CLIENT_ADDR", None),
+                ("LSST_KAFKA_BROKER_ADDR", None),
+                ("LSST_SCHEMA_REGISTRY_URL", None),
+                ("LSST_SITE", Some("unknown")),
+                ("LSST_SITE_ENDPOINTS", None),
+            ],
+            || {
+                (
+                    Domain::get_client_hosts(),
+                    Domain::get_schema_registry_url(),
+                )
+            },
         );
 
-        let schema_registry_url = Domain::get_schema_registry_url();
+        assert_eq!(
+            client_hosts,
+            vec![DEFAULT_LSST_KAFKA_CLIENT_ADDR.to_owned()]
+        );
+        assert_eq!(schema_registry_url, DEFAULT_LSST_SCHEMA_REGISTRY_URL);
+    }
 
-        env::remove_var("LSST_SCHEMA_REGISTRY_URL");
+    #[test]
+    fn get_schema_registry_url_env_not_set() {
+        let schema_registry_url = with_env_vars(
+            &[
+                ("LSST_SCHEMA_REGISTRY_URL", None),
+                ("LSST_SITE", None),
+                ("LSST_SITE_ENDPOINTS", None),
+            ],
+            Domain::get_schema_registry_url,
+        );
+
+        assert_eq!(schema_registry_url, DEFAULT_LSST_SCHEMA_REGISTRY_URL)
+    }
+
+    #[test]
+    fn get_schema_registry_url_env_set() {
+        let schema_registry_url = with_env_vars(
+            &[(
+                "LSST_SCHEMA_REGISTRY_URL",
+                Some("http://lsst-schema-registry.lsst.codes:8081"),
+            )],
+            Domain::get_schema_registry_url,
+        );
 
         assert_eq!(
             schema_registry_url,
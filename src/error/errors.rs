@@ -45,3 +45,34 @@ impl From<apache_avro::Error> for SalObjError {
         SalObjError::new(&item.to_string())
     }
 }
+
+impl From<kafka::error::Error> for SalObjError {
+    fn from(item: kafka::error::Error) -> SalObjError {
+        SalObjError::new(&item.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_kafka_error() {
+        let kafka_error = kafka::error::Error::NoHostReachable;
+        let err_msg = kafka_error.to_string();
+
+        let error: SalObjError = kafka_error.into();
+
+        assert_eq!(error.get_error_message(), err_msg);
+    }
+
+    #[test]
+    fn from_boxed_error() {
+        let boxed_error: Box<dyn Error> = Box::new(kafka::error::Error::NoHostReachable);
+        let err_msg = boxed_error.to_string();
+
+        let error: SalObjError = boxed_error.into();
+
+        assert_eq!(error.get_error_message(), err_msg);
+    }
+}
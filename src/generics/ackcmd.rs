@@ -1,7 +1,32 @@
-use crate::{topics::base_sal_topic::BaseSALTopic, utils::xml_utils::get_default_sal_index};
+use crate::{
+    error::errors::{SalObjError, SalObjResult},
+    topics::{base_sal_topic::BaseSALTopic, read_topic::ReadTopic},
+    utils::xml_utils::get_default_sal_index,
+};
+use apache_avro::{
+    types::{Record, Value},
+    Schema,
+};
 use base_topic_derive::{add_sal_topic_fields, BaseSALTopic};
 use chrono::Utc;
 
+/// Name of the acknowledgement code field.
+pub const ACK: &str = "ack";
+/// Name of the error number field.
+pub const ERROR: &str = "error";
+/// Name of the result field.
+pub const RESULT: &str = "result";
+/// Name of the field with the identity of the commander.
+pub const IDENTITY: &str = "identity";
+/// Name of the field with the origin of the commander.
+pub const ORIGIN: &str = "origin";
+/// Name of the command type field.
+pub const CMDTYPE: &str = "cmdtype";
+/// Name of the timeout field.
+pub const TIMEOUT: &str = "timeout";
+/// Name of the field with the sequence number of the acknowledged command.
+pub const SEQ_NUM: &str = "private_seqNum";
+
 #[add_sal_topic_fields]
 #[derive(Debug, Default, Deserialize, Serialize, BaseSALTopic)]
 pub struct AckCmd {
@@ -47,3 +72,230 @@ impl AckCmd {
         self.cmdtype
     }
 }
+
+/// The fields of an ackcmd sample needed to match and interpret an
+/// acknowledgement, without the remaining private fields.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AckCmdRecord {
+    pub ack: i32,
+    pub error: i32,
+    pub result: String,
+    pub identity: String,
+    pub origin: i32,
+    pub cmdtype: i32,
+    pub timeout: f64,
+    pub seq_num: i32,
+}
+
+impl AckCmdRecord {
+    /// Read the ackcmd fields from a decoded ackcmd sample.
+    pub fn from_value(value: &Value) -> SalObjResult<AckCmdRecord> {
+        let fields = if let Value::Record(fields) = value {
+            fields
+        } else {
+            return Err(SalObjError::new("Ackcmd data is not a record."));
+        };
+
+        let get_field = |name: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| ReadTopic::unwrap_union(value))
+                .ok_or_else(|| SalObjError::new(&format!("Ackcmd has no {name} field.")))
+        };
+        let get_int = |name: &str| match get_field(name)? {
+            Value::Int(value) => Ok(*value),
+            Value::Long(value) => Ok(*value as i32),
+            value => Err(SalObjError::new(&format!(
+                "Ackcmd {name} has wrong type: {value:?}."
+            ))),
+        };
+        let get_string = |name: &str| match get_field(name)? {
+            Value::String(value) => Ok(value.to_owned()),
+            value => Err(SalObjError::new(&format!(
+                "Ackcmd {name} has wrong type: {value:?}."
+            ))),
+        };
+        let get_double = |name: &str| match get_field(name)? {
+            Value::Double(value) => Ok(*value),
+            Value::Float(value) => Ok(float_to_double(*value)),
+            value => Err(SalObjError::new(&format!(
+                "Ackcmd {name} has wrong type: {value:?}."
+            ))),
+        };
+
+        Ok(AckCmdRecord {
+            ack: get_int(ACK)?,
+            error: get_int(ERROR)?,
+            result: get_string(RESULT)?,
+            identity: get_string(IDENTITY)?,
+            origin: get_int(ORIGIN)?,
+            cmdtype: get_int(CMDTYPE)?,
+            timeout: get_double(TIMEOUT)?,
+            seq_num: get_int(SEQ_NUM)?,
+        })
+    }
+
+    /// Make an ackcmd record with these fields from the ackcmd schema.
+    ///
+    /// The remaining private fields are left for the writer to fill.
+    pub fn to_record<'s>(&self, schema: &'s Schema) -> SalObjResult<Record<'s>> {
+        let mut record = Record::new(schema)
+            .ok_or_else(|| SalObjError::new("Ackcmd schema is not a record."))?;
+
+        record.put(ACK, Value::Int(self.ack));
+        record.put(ERROR, Value::Int(self.error));
+        record.put(RESULT, Value::String(self.result.to_owned()));
+        record.put(IDENTITY, Value::String(self.identity.to_owned()));
+        record.put(ORIGIN, Value::Int(self.origin));
+        record.put(CMDTYPE, Value::Int(self.cmdtype));
+        record.put(TIMEOUT, Value::Double(self.timeout));
+        record.put(SEQ_NUM, Value::Int(self.seq_num));
+
+        Ok(record)
+    }
+
+    /// Is this the acknowledgement of the command with the given origin,
+    /// identity and sequence number?
+    pub fn is_reply_to(&self, origin: i32, identity: &str, seq_num: i32) -> bool {
+        self.origin == origin && self.identity == identity && self.seq_num == seq_num
+    }
+}
+
+/// Widen an ackcmd timeout written as a float.
+///
+/// The value goes through its shortest decimal representation, so e.g. a
+/// timeout of 0.1 is read as 0.1 seconds and not 0.10000000149011612.
+fn float_to_double(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(value as f64)
+}
+
+impl From<&AckCmd> for AckCmdRecord {
+    fn from(ackcmd: &AckCmd) -> AckCmdRecord {
+        AckCmdRecord {
+            ack: ackcmd.ack,
+            error: ackcmd.error,
+            result: ackcmd.result.to_owned(),
+            identity: ackcmd.identity.to_owned(),
+            origin: ackcmd.origin,
+            cmdtype: ackcmd.cmdtype,
+            timeout: ackcmd.timeout,
+            seq_num: ackcmd.get_private_seq_num(),
+        }
+    }
+}
+
+impl From<&AckCmdRecord> for AckCmd {
+    fn from(ackcmd_record: &AckCmdRecord) -> AckCmd {
+        AckCmd::new(
+            ackcmd_record.ack,
+            ackcmd_record.error,
+            &ackcmd_record.result,
+            &ackcmd_record.identity,
+            ackcmd_record.origin,
+            ackcmd_record.cmdtype,
+            ackcmd_record.timeout,
+        )
+        .with_private_seq_num(ackcmd_record.seq_num)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component_info::ComponentInfo;
+
+    #[test]
+    fn ackcmd_record_round_trip() {
+        let component_info = ComponentInfo::new("Test", "unit_test").unwrap();
+        let schema = component_info
+            .get_ackcmd_topic_info()
+            .unwrap()
+            .make_schema()
+            .unwrap();
+
+        let ackcmd_record = AckCmdRecord {
+            ack: 303,
+            error: 1,
+            result: "Done.".to_owned(),
+            identity: "unit@test".to_owned(),
+            origin: 123,
+            cmdtype: 4,
+            timeout: 1.5,
+            seq_num: 321,
+        };
+
+        let record = ackcmd_record.to_record(&schema).unwrap();
+        let value: Value = record.into();
+
+        assert_eq!(AckCmdRecord::from_value(&value).unwrap(), ackcmd_record);
+    }
+
+    #[test]
+    fn ackcmd_record_is_reply_to() {
+        let ackcmd_record = AckCmdRecord {
+            identity: "unit@test".to_owned(),
+            origin: 123,
+            seq_num: 321,
+            ..Default::default()
+        };
+
+        assert!(ackcmd_record.is_reply_to(123, "unit@test", 321));
+        assert!(!ackcmd_record.is_reply_to(123, "unit@test", 322));
+        assert!(!ackcmd_record.is_reply_to(12, "unit@test", 321));
+    }
+
+    #[test]
+    fn ackcmd_record_from_ackcmd() {
+        let ackcmd_record = AckCmdRecord {
+            ack: 301,
+            error: 0,
+            result: "In progress.".to_owned(),
+            identity: "unit@test".to_owned(),
+            origin: 123,
+            cmdtype: 4,
+            timeout: 60.0,
+            seq_num: 321,
+        };
+
+        let ackcmd = AckCmd::from(&ackcmd_record);
+
+        assert_eq!(AckCmdRecord::from(&ackcmd), ackcmd_record);
+    }
+
+    fn make_ackcmd_value(timeout: Value) -> Value {
+        Value::Record(vec![
+            (ACK.to_owned(), Value::Int(301)),
+            (ERROR.to_owned(), Value::Int(0)),
+            (RESULT.to_owned(), Value::String("".to_owned())),
+            (IDENTITY.to_owned(), Value::String("unit@test".to_owned())),
+            (ORIGIN.to_owned(), Value::Int(123)),
+            (CMDTYPE.to_owned(), Value::Int(4)),
+            (TIMEOUT.to_owned(), timeout),
+            (SEQ_NUM.to_owned(), Value::Int(321)),
+        ])
+    }
+
+    #[test]
+    fn ackcmd_record_from_value_float_timeout() {
+        let value = make_ackcmd_value(Value::Float(0.1));
+
+        assert_eq!(AckCmdRecord::from_value(&value).unwrap().timeout, 0.1);
+    }
+
+    #[test]
+    fn ackcmd_record_from_value_wrong_timeout_type() {
+        let value = make_ackcmd_value(Value::String("1.5".to_owned()));
+
+        let error = AckCmdRecord::from_value(&value).unwrap_err();
+
+        assert!(error
+            .get_error_message()
+            .starts_with("Ackcmd timeout has wrong type"));
+    }
+
+    #[test]
+    fn ackcmd_record_from_value_not_record() {
+        assert!(AckCmdRecord::from_value(&Value::Null).is_err());
+    }
+}
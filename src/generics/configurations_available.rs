@@ -3,7 +3,7 @@ use base_topic_derive::{add_sal_topic_fields, BaseSALTopic};
 use chrono::Utc;
 
 #[add_sal_topic_fields]
-#[derive(Debug, Deserialize, BaseSALTopic)]
+#[derive(Debug, Default, Deserialize, Serialize, BaseSALTopic)]
 pub struct ConfigurationsAvailable {
     overrides: String,
     version: String,
@@ -25,6 +25,22 @@ impl ConfigurationsAvailable {
     pub fn get_schema_version(&self) -> String {
         self.schema_version.to_owned()
     }
+    pub fn with_overrides(mut self, value: &str) -> Self {
+        self.overrides = value.to_owned();
+        self
+    }
+    pub fn with_version(mut self, value: &str) -> Self {
+        self.version = value.to_owned();
+        self
+    }
+    pub fn with_url(mut self, value: &str) -> Self {
+        self.url = value.to_owned();
+        self
+    }
+    pub fn with_schema_version(mut self, value: &str) -> Self {
+        self.schema_version = value.to_owned();
+        self
+    }
 }
 
 #[cfg(test)]
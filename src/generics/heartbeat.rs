@@ -12,6 +12,11 @@ impl Heartbeat {
     pub fn get_heartbeat(&self) -> bool {
         self.heartbeat
     }
+
+    pub fn with_heartbeat(mut self, heartbeat: bool) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,7 @@ use base_topic_derive::{add_sal_topic_fields, BaseSALTopic};
 use chrono::Utc;
 
 #[add_sal_topic_fields]
-#[derive(Debug, Deserialize, BaseSALTopic)]
+#[derive(Debug, Default, Deserialize, BaseSALTopic)]
 pub struct LargeFileObjectAvailable {
     url: String,
     generator: String,
@@ -39,6 +39,14 @@ impl LargeFileObjectAvailable {
     pub fn get_id(&self) -> String {
         self.id.to_owned()
     }
+    pub fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_owned();
+        self
+    }
+    pub fn with_check_sum(mut self, check_sum: &str) -> Self {
+        self.check_sum = check_sum.to_owned();
+        self
+    }
 }
 
 #[cfg(test)]
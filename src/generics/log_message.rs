@@ -3,7 +3,7 @@ use base_topic_derive::{add_sal_topic_fields, BaseSALTopic};
 use chrono::Utc;
 
 #[add_sal_topic_fields]
-#[derive(Debug, Deserialize, BaseSALTopic)]
+#[derive(Debug, Default, Deserialize, Serialize, BaseSALTopic)]
 pub struct LogMessage {
     name: String,
     level: isize,
@@ -47,6 +47,18 @@ impl LogMessage {
     pub fn get_timestamp(&self) -> f64 {
         self.timestamp
     }
+    pub fn with_name(mut self, value: &str) -> Self {
+        self.name = value.to_owned();
+        self
+    }
+    pub fn with_level(mut self, value: isize) -> Self {
+        self.level = value;
+        self
+    }
+    pub fn with_message(mut self, value: &str) -> Self {
+        self.message = value.to_owned();
+        self
+    }
 }
 
 #[cfg(test)]
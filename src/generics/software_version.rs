@@ -3,7 +3,7 @@ use base_topic_derive::{add_sal_topic_fields, BaseSALTopic};
 use chrono::Utc;
 
 #[add_sal_topic_fields]
-#[derive(Debug, Deserialize, BaseSALTopic)]
+#[derive(Debug, Default, Deserialize, Serialize, BaseSALTopic)]
 pub struct SoftwareVersion {
     #[serde(rename = "salVersion")]
     sal_version: String,
@@ -33,6 +33,10 @@ impl SoftwareVersion {
     pub fn get_subsystem_versions(&self) -> String {
         self.subsystem_versions.to_owned()
     }
+    pub fn with_csc_version(mut self, value: &str) -> Self {
+        self.csc_version = value.to_owned();
+        self
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,7 @@ use base_topic_derive::{add_sal_topic_fields, BaseSALTopic};
 use chrono::Utc;
 
 #[add_sal_topic_fields]
-#[derive(Debug, Deserialize, BaseSALTopic)]
+#[derive(Debug, Default, Deserialize, Serialize, BaseSALTopic)]
 pub struct Start {
     #[serde(rename = "configurationOverride")]
     configuration_override: String,
@@ -13,6 +13,10 @@ impl Start {
     pub fn get_configuration_override(&self) -> String {
         self.configuration_override.to_owned()
     }
+    pub fn with_configuration_override(mut self, value: &str) -> Self {
+        self.configuration_override = value.to_owned();
+        self
+    }
 }
 
 #[cfg(test)]
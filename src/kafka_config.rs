@@ -0,0 +1,312 @@
+//! Extra Kafka client properties.
+//!
+//! Operators can tune the Kafka clients without code changes by setting the
+//! LSST_KAFKA_EXTRA_CONFIG environment variable to a list of `key=value`
+//! pairs separated by `;`, using the standard Kafka property names, e.g.
+//! `retries=5;fetch.min.bytes=1024`.
+//!
+//! Properties are applied to the admin client held by the
+//! [Domain](crate::domain::Domain) and to the producers and consumers
+//! created for each topic. Properties not supported by the kafka client,
+//! like `linger.ms` or `batch.size`, are ignored with a warning.
+//!
+//! The consumer group timeouts, `session.timeout.ms` and
+//! `max.poll.interval.ms`, are available with
+//! [KafkaExtraConfig::get_consumer_configuration].
+
+use std::{env, str::FromStr, time::Duration};
+
+use kafka::{
+    client::{Compression, KafkaClient, RequiredAcks},
+    consumer, producer,
+};
+
+/// Consumer group timeouts.
+///
+/// The kafka client reads topics without joining the consumer group, so it
+/// is never rebalanced and these are not applied to it; they are kept for
+/// clients that do join the group.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ConsumerConfiguration {
+    /// Time without heartbeats after which a consumer is removed from the
+    /// group (`session.timeout.ms`).
+    pub session_timeout: Option<Duration>,
+    /// Maximum time between polls before a consumer is removed from the
+    /// group (`max.poll.interval.ms`).
+    pub max_poll_interval: Option<Duration>,
+}
+
+/// Extra Kafka client properties, in the order they were given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KafkaExtraConfig {
+    properties: Vec<(String, String)>,
+}
+
+impl KafkaExtraConfig {
+    /// Read the extra properties from the LSST_KAFKA_EXTRA_CONFIG
+    /// environment variable. Return an empty configuration if it is not set.
+    pub fn from_env() -> KafkaExtraConfig {
+        env::var("LSST_KAFKA_EXTRA_CONFIG")
+            .map(|config| KafkaExtraConfig::parse(&config))
+            .unwrap_or_default()
+    }
+
+    /// Parse a list of `key=value` pairs separated by `;`.
+    ///
+    /// Empty entries are skipped and entries without `=` are ignored with a
+    /// warning.
+    pub fn parse(config: &str) -> KafkaExtraConfig {
+        let properties = config
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| match entry.split_once('=') {
+                Some((key, value)) => Some((key.trim().to_owned(), value.trim().to_owned())),
+                None => {
+                    log::warn!("Ignoring invalid kafka property {entry:?}.");
+                    None
+                }
+            })
+            .collect();
+
+        KafkaExtraConfig { properties }
+    }
+
+    /// Get all the properties as `(key, value)` pairs.
+    pub fn get_properties(&self) -> &[(String, String)] {
+        &self.properties
+    }
+
+    /// Get the value of a property. If given more than once, the last value
+    /// is returned.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .rev()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Is the configuration empty?
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    /// Get the consumer group timeouts, see [ConsumerConfiguration].
+    ///
+    /// Timeouts that are not set or are invalid are `None`.
+    pub fn get_consumer_configuration(&self) -> ConsumerConfiguration {
+        let get_millis = |key: &str| self.get(key).and_then(|value| parse_millis(key, value));
+        ConsumerConfiguration {
+            session_timeout: get_millis("session.timeout.ms"),
+            max_poll_interval: get_millis("max.poll.interval.ms"),
+        }
+    }
+
+    /// Apply the properties to the admin client.
+    pub fn apply_to_client(&self, client: &mut KafkaClient) {
+        for (key, value) in &self.properties {
+            match key.as_str() {
+                "connections.max.idle.ms" => {
+                    if let Some(timeout) = parse_millis(key, value) {
+                        client.set_connection_idle_timeout(timeout);
+                    }
+                }
+                "retry.backoff.ms" => {
+                    if let Some(backoff) = parse_millis(key, value) {
+                        client.set_retry_backoff_time(backoff);
+                    }
+                }
+                "retries" => {
+                    if let Some(retries) = parse_value(key, value) {
+                        client.set_retry_max_attempts(retries);
+                    }
+                }
+                _ => log_unsupported("client", key),
+            }
+        }
+    }
+
+    /// Apply the properties to a producer builder.
+    pub fn apply_to_producer(&self, mut builder: producer::Builder) -> producer::Builder {
+        for (key, value) in &self.properties {
+            builder = match key.as_str() {
+                "connections.max.idle.ms" => match parse_millis(key, value) {
+                    Some(timeout) => builder.with_connection_idle_timeout(timeout),
+                    None => builder,
+                },
+                "request.timeout.ms" => match parse_millis(key, value) {
+                    Some(timeout) => builder.with_ack_timeout(timeout),
+                    None => builder,
+                },
+                "acks" => match parse_required_acks(value) {
+                    Some(required_acks) => builder.with_required_acks(required_acks),
+                    None => builder,
+                },
+                "compression.type" => match parse_compression(value) {
+                    Some(compression) => builder.with_compression(compression),
+                    None => builder,
+                },
+                _ => {
+                    log_unsupported("producer", key);
+                    builder
+                }
+            };
+        }
+        builder
+    }
+
+    /// Apply the properties to a consumer builder.
+    pub fn apply_to_consumer(&self, mut builder: consumer::Builder) -> consumer::Builder {
+        for (key, value) in &self.properties {
+            builder = match key.as_str() {
+                "connections.max.idle.ms" => match parse_millis(key, value) {
+                    Some(timeout) => builder.with_connection_idle_timeout(timeout),
+                    None => builder,
+                },
+                "fetch.max.wait.ms" => match parse_millis(key, value) {
+                    Some(wait_time) => builder.with_fetch_max_wait_time(wait_time),
+                    None => builder,
+                },
+                "fetch.min.bytes" => match parse_value(key, value) {
+                    Some(min_bytes) => builder.with_fetch_min_bytes(min_bytes),
+                    None => builder,
+                },
+                "max.partition.fetch.bytes" => match parse_value(key, value) {
+                    Some(max_bytes) => builder.with_fetch_max_bytes_per_partition(max_bytes),
+                    None => builder,
+                },
+                "check.crcs" => match parse_value(key, value) {
+                    Some(validate_crc) => builder.with_fetch_crc_validation(validate_crc),
+                    None => builder,
+                },
+                "session.timeout.ms" | "max.poll.interval.ms" => {
+                    log::debug!(
+                        "Kafka property {key} only applies to consumer group members; \
+                         see get_consumer_configuration."
+                    );
+                    builder
+                }
+                _ => {
+                    log_unsupported("consumer", key);
+                    builder
+                }
+            };
+        }
+        builder
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Option<T> {
+    let parsed = value.parse().ok();
+    if parsed.is_none() {
+        log::warn!("Ignoring invalid value {value:?} for kafka property {key}.");
+    }
+    parsed
+}
+
+fn parse_millis(key: &str, value: &str) -> Option<Duration> {
+    parse_value(key, value).map(Duration::from_millis)
+}
+
+fn parse_required_acks(value: &str) -> Option<RequiredAcks> {
+    match value {
+        "0" => Some(RequiredAcks::None),
+        "1" => Some(RequiredAcks::One),
+        "-1" | "all" => Some(RequiredAcks::All),
+        _ => {
+            log::warn!("Ignoring invalid value {value:?} for kafka property acks.");
+            None
+        }
+    }
+}
+
+fn parse_compression(value: &str) -> Option<Compression> {
+    match value {
+        "none" => Some(Compression::NONE),
+        "gzip" => Some(Compression::GZIP),
+        "snappy" => Some(Compression::SNAPPY),
+        _ => {
+            log::warn!("Ignoring invalid value {value:?} for kafka property compression.type.");
+            None
+        }
+    }
+}
+
+fn log_unsupported(client_type: &str, key: &str) {
+    log::warn!("Kafka property {key} not supported by the {client_type}; ignoring.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let extra_config =
+            KafkaExtraConfig::parse("linger.ms=5; batch.size = 16384;;invalid;retries=3");
+
+        assert_eq!(
+            extra_config.get_properties(),
+            &[
+                ("linger.ms".to_owned(), "5".to_owned()),
+                ("batch.size".to_owned(), "16384".to_owned()),
+                ("retries".to_owned(), "3".to_owned()),
+            ]
+        );
+        assert_eq!(extra_config.get("batch.size"), Some("16384"));
+        assert_eq!(extra_config.get("acks"), None);
+    }
+
+    #[test]
+    fn parse_empty() {
+        assert!(KafkaExtraConfig::parse("").is_empty());
+        assert!(KafkaExtraConfig::parse(" ; ").is_empty());
+    }
+
+    #[test]
+    fn get_last_value() {
+        let extra_config = KafkaExtraConfig::parse("retries=3;retries=5");
+
+        assert_eq!(extra_config.get("retries"), Some("5"));
+    }
+
+    #[test]
+    fn get_consumer_configuration() {
+        let extra_config =
+            KafkaExtraConfig::parse("session.timeout.ms=45000;max.poll.interval.ms=600000");
+
+        assert_eq!(
+            extra_config.get_consumer_configuration(),
+            ConsumerConfiguration {
+                session_timeout: Some(Duration::from_secs(45)),
+                max_poll_interval: Some(Duration::from_secs(600)),
+            }
+        );
+    }
+
+    #[test]
+    fn get_consumer_configuration_unset() {
+        let extra_config = KafkaExtraConfig::parse("session.timeout.ms=abc;retries=3");
+
+        assert_eq!(
+            extra_config.get_consumer_configuration(),
+            ConsumerConfiguration::default()
+        );
+    }
+
+    #[test]
+    fn apply_to_client() {
+        let extra_config = KafkaExtraConfig::parse(
+            "retries=7;retry.backoff.ms=250;connections.max.idle.ms=abc;linger.ms=5",
+        );
+        let mut client = KafkaClient::new(vec!["localhost:9092".to_owned()]);
+        let connection_idle_timeout = client.connection_idle_timeout();
+
+        extra_config.apply_to_client(&mut client);
+
+        assert_eq!(client.retry_max_attempts(), 7);
+        assert_eq!(client.retry_backoff_time(), Duration::from_millis(250));
+        assert_eq!(client.connection_idle_timeout(), connection_idle_timeout);
+    }
+}
@@ -24,16 +24,22 @@
 #[macro_use]
 extern crate serde_derive;
 
+// Lets code generated by the derive macros refer to this crate as `salobj`,
+// both here and in crates that depend on it.
+extern crate self as salobj;
+
 mod component_info;
 pub mod controller;
 pub mod csc;
 pub mod domain;
 mod error;
 pub mod generics;
+pub mod kafka_config;
 pub mod remote;
 pub mod sal_enums;
 pub mod sal_info;
 mod sal_subsystem;
+pub mod single_topic_reader;
 pub mod topics;
 pub mod utils;
 
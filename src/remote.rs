@@ -4,23 +4,43 @@
 //! It allows users to send commands and receive events and telemetry to
 //! components.
 
-use crate::domain;
+use crate::component_info::MANDATORY_GENERICS;
+use crate::domain::{self, ComponentRegistration};
 use crate::error::errors::{SalObjError, SalObjResult};
+use crate::generics::{start::Start, summary_state::SummaryState};
+use crate::sal_enums::{get_enum_field, SalEnum, State};
 use crate::sal_info;
 
 use crate::topics::remote_command;
 
+use crate::generics::ackcmd::AckCmdRecord;
 use crate::topics::{
     base_sal_topic::BaseSALTopic, read_topic::ReadTopic, remote_command::RemoteCommand,
+    topic_enum::TopicEnum, topic_info::TopicInfo,
 };
 use crate::utils::command_ack::CommandAck;
 use crate::utils::types::{ReadTopicSet, RemoteCommandSet};
 use apache_avro::types::Record;
 use apache_avro::types::Value;
-use apache_avro::Schema;
-use serde::Serialize;
-use std::collections::HashMap;
-use std::{fmt::Debug, time::Duration};
+use apache_avro::{from_value, Schema};
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+/// Time to wait for data on each topic of [Remote::event_stream] before
+/// moving to the next.
+const STREAM_POLL_TIME: Duration = Duration::from_millis(50);
+
+/// Time to wait for new samples when draining the summary state queue in
+/// [Remote::wait_for_any_summary_state].
+const SUMMARY_STATE_POLL_TIME: Duration = Duration::from_millis(100);
+
+/// Error reported when a command is issued through a readonly [Remote].
+const READONLY_ERROR: &str = "Remote is read-only; cannot issue commands.";
 
 /// Handle operations on a remote SAL object.
 /// This object can execute commands to and receive telemetry and events from
@@ -33,9 +53,22 @@ pub struct Remote<'b> {
     commands: RemoteCommandSet<'b>,
     events: ReadTopicSet<'b>,
     telemetry: ReadTopicSet<'b>,
+    /// Was the remote created without the ability to issue commands?
+    readonly: bool,
+    /// Entry of the component in the domain active components.
+    registration: ComponentRegistration,
 }
 
 impl<'b> Remote<'b> {
+    /// Create a remote for component `name` with the given `index`.
+    ///
+    /// Readers are created for every event and telemetry topic of the
+    /// component unless `include` lists the only topics to read, or
+    /// `exclude` the topics to leave out, by name, e.g. `logevent_heartbeat`
+    /// or `scalars`. Topics listed more than once in `include` get a single
+    /// reader, and a warning is logged, since two consumers of the same topic
+    /// would compete for its data. Giving both `include` and `exclude` is an
+    /// error.
     pub fn new(
         domain: &mut domain::Domain,
         name: &str,
@@ -46,21 +79,34 @@ impl<'b> Remote<'b> {
         evt_max_history: usize,
     ) -> SalObjResult<Remote<'b>> {
         if !include.is_empty() && !exclude.is_empty() {
-            panic!("include_only and exclude can not both have elements.");
-        } else if !include.is_empty() || !exclude.is_empty() {
-            unimplemented!(
-                "Including only a subset of topics or \
-                excluding a subset of topics is not implemented yet."
-            );
+            return Err(SalObjError::new(
+                "include and exclude can not both have elements.",
+            ));
         }
 
         let sal_info = sal_info::SalInfo::new(name, index)?;
 
+        let include = Remote::dedupe_topic_names(include);
+        let exclude: HashSet<String> = exclude.into_iter().collect();
+        let is_wanted = |topic_name: &str| {
+            (include.is_empty() || include.contains(topic_name)) && !exclude.contains(topic_name)
+        };
+
+        let event_names = sal_info.get_event_names();
+        let telemetry_names = sal_info.get_telemetry_names();
+
+        for topic_name in include.iter().filter(|topic_name| {
+            !event_names.contains(topic_name) && !telemetry_names.contains(topic_name)
+        }) {
+            log::warn!("Included topic {topic_name} not in {name}; ignoring.");
+        }
+
         if let Err(error) = domain.register_topics(&sal_info.get_topics_name()) {
             log::warn!("Failed to register topics: {error:?}. Continuing...");
         }
+        let registration = domain.register_component(name, index);
 
-        let commands: RemoteCommandSet = if readonly {
+        let commands: RemoteCommandSet = if readonly || sal_info.is_readonly() {
             HashMap::new()
         } else {
             sal_info
@@ -75,9 +121,9 @@ impl<'b> Remote<'b> {
                 .collect()
         };
 
-        let events: ReadTopicSet = sal_info
-            .get_event_names()
+        let events: ReadTopicSet = event_names
             .into_iter()
+            .filter(|event_name| is_wanted(event_name))
             .map(|event_name| {
                 (
                     event_name.to_owned(),
@@ -86,9 +132,9 @@ impl<'b> Remote<'b> {
             })
             .collect();
 
-        let telemetry: ReadTopicSet = sal_info
-            .get_telemetry_names()
+        let telemetry: ReadTopicSet = telemetry_names
             .into_iter()
+            .filter(|telemetry_name| is_wanted(telemetry_name))
             .map(|telemetry_name| {
                 (
                     telemetry_name.to_owned(),
@@ -102,9 +148,24 @@ impl<'b> Remote<'b> {
             commands,
             events,
             telemetry,
+            readonly,
+            registration,
         })
     }
 
+    /// Remove duplicated topic names, logging a warning for each one.
+    fn dedupe_topic_names(topic_names: Vec<String>) -> HashSet<String> {
+        let mut unique_topic_names = HashSet::with_capacity(topic_names.len());
+        for topic_name in topic_names {
+            if unique_topic_names.contains(&topic_name) {
+                log::warn!("Topic {topic_name} subscribed more than once; using a single reader.");
+            } else {
+                unique_topic_names.insert(topic_name);
+            }
+        }
+        unique_topic_names
+    }
+
     pub fn from_name_index(
         domain: &mut domain::Domain,
         name: &str,
@@ -113,6 +174,37 @@ impl<'b> Remote<'b> {
         Remote::new(domain, name, index, false, Vec::new(), Vec::new(), 1)
     }
 
+    /// Query the current summary state of a component.
+    ///
+    /// This is a lightweight alternative to creating a [Remote] that only
+    /// reads the most recent `logevent_summaryState` sample.
+    pub async fn query_state(
+        domain: &mut domain::Domain,
+        name: &str,
+        index: isize,
+        timeout: Duration,
+    ) -> SalObjResult<State> {
+        let sal_info = sal_info::SalInfo::new(name, index)?;
+        let topic_name = "logevent_summaryState";
+
+        if let Err(error) =
+            domain.register_topics(&[sal_info.make_schema_registry_topic_name(topic_name)])
+        {
+            log::warn!("Failed to register topics: {error:?}. Continuing...");
+        }
+
+        let mut summary_state_reader = ReadTopic::new(topic_name, &sal_info, domain, 1);
+
+        if let Some(summary_state) = summary_state_reader.pop_back(false, timeout).await {
+            Ok(from_value::<SummaryState>(&summary_state)?.get_summary_state())
+        } else {
+            Err(SalObjError::new(&format!(
+                "No summary state received from {} in {timeout:?}.",
+                sal_info.get_name_index()
+            )))
+        }
+    }
+
     /// Get component name.
     pub fn get_name(&self) -> String {
         self.sal_info.get_name()
@@ -122,11 +214,128 @@ impl<'b> Remote<'b> {
         Some(self.commands.get(command_name)?.get_schema().clone())
     }
 
+    /// Validate a command record against the command schema.
+    ///
+    /// Checks that all fields are set and have the type expected by the
+    /// schema. Private fields and `salIndex` are ignored, since they are
+    /// filled in when the command is written.
+    pub fn validate_command(&self, command_name: &str, parameters: &Record) -> SalObjResult<()> {
+        let schema = self.get_command_schema(command_name).ok_or_else(|| {
+            SalObjError::new(&format!(
+                "Command {command_name} not in the list of commands."
+            ))
+        })?;
+
+        if let Schema::Record { fields, .. } = &schema {
+            for field in fields
+                .iter()
+                .filter(|field| !field.name.starts_with("private_") && field.name != "salIndex")
+            {
+                match parameters
+                    .fields
+                    .iter()
+                    .find(|(name, _)| *name == field.name)
+                {
+                    Some((_, Value::Null)) | None => {
+                        return Err(SalObjError::new(&format!(
+                            "Field {} of {command_name} is not set.",
+                            field.name
+                        )))
+                    }
+                    Some((_, value)) => {
+                        if !value.validate(&field.schema) {
+                            return Err(SalObjError::new(&format!(
+                                "Field {} of {command_name} has wrong type: expected {:?}, got {value:?}.",
+                                field.name, field.schema
+                            )));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        } else {
+            Err(SalObjError::new(&format!(
+                "Schema for {command_name} is not a record."
+            )))
+        }
+    }
+
+    /// Is the remote unable to issue commands?
+    ///
+    /// This is the case if the remote was created readonly or if the
+    /// component has no commands, e.g. Sensor.
+    pub fn is_readonly(&self) -> bool {
+        self.readonly || self.sal_info.is_readonly()
+    }
+
+    /// Does the remote read all indices of an indexed component?
+    ///
+    /// Such a remote (index 0) can read events and telemetry from every
+    /// index, but cannot issue commands, which need a specific index.
+    pub fn is_all_indices(&self) -> bool {
+        self.sal_info.is_indexed() && self.sal_info.get_index() == 0
+    }
+
+    /// Error message explaining why commands cannot be issued, if they
+    /// cannot.
+    fn get_command_error(&self) -> Option<String> {
+        if self.is_readonly() {
+            Some(READONLY_ERROR.to_owned())
+        } else if self.is_all_indices() {
+            Some(format!(
+                "{} is indexed and this remote reads all indices (index=0); \
+                 use a specific index to issue commands.",
+                self.sal_info.get_name()
+            ))
+        } else {
+            None
+        }
+    }
+
     /// Get component index.
     pub fn get_index(&self) -> isize {
         self.sal_info.get_index()
     }
 
+    /// Switch the remote to another index of the same component.
+    ///
+    /// Topic names do not depend on the index, so the existing readers and
+    /// command writers are kept and only their `salIndex` filtering changes,
+    /// which is much cheaper than creating a new remote. Data queued for the
+    /// previous index is discarded.
+    pub fn with_index(&mut self, index: isize) -> SalObjResult<()> {
+        if !self.sal_info.is_indexed() {
+            return if index == 0 {
+                Ok(())
+            } else {
+                Err(SalObjError::new(&format!(
+                    "{} is not indexed; index must be 0, got {index}.",
+                    self.sal_info.get_name()
+                )))
+            };
+        }
+
+        // Check every reader before changing any, so a failure leaves the
+        // remote on the previous index.
+        let sal_index = Some(index as i32);
+        for reader in self.events.values().chain(self.telemetry.values()) {
+            reader.check_sal_index(sal_index)?;
+        }
+        for command in self.commands.values() {
+            command.check_index(index as i32)?;
+        }
+
+        self.sal_info.set_index(index);
+        self.registration.set_index(index);
+        for reader in self.events.values_mut().chain(self.telemetry.values_mut()) {
+            reader.set_sal_index(sal_index)?;
+        }
+        for command in self.commands.values_mut() {
+            command.set_index(index as i32)?;
+        }
+        Ok(())
+    }
+
     pub async fn run_command<'c>(
         &mut self,
         command_name: String,
@@ -134,6 +343,10 @@ impl<'b> Remote<'b> {
         timeout: Duration,
         wait_done: bool,
     ) -> remote_command::AckCmdResult {
+        if let Some(error) = self.get_command_error() {
+            return Err(CommandAck::invalid_command(&error));
+        }
+
         if !self.sal_info.is_command(&command_name) {
             return Err(CommandAck::invalid_command(&format!(
                 "Invalid command name {command_name}."
@@ -149,16 +362,26 @@ impl<'b> Remote<'b> {
         }
     }
 
+    /// Get the data to send a command.
+    ///
+    /// Fields are initialized with the default values declared in the
+    /// command schema, falling back to `T::default()` for fields without
+    /// one. Private fields and `salIndex` are filled in for the command.
     pub fn get_command_data<T>(&self, cmd_name: &str) -> SalObjResult<T>
     where
-        T: BaseSALTopic + Default + Debug,
+        T: BaseSALTopic + Default + Debug + Serialize + DeserializeOwned,
     {
+        if let Some(error) = self.get_command_error() {
+            return Err(SalObjError::new(&error));
+        }
+
         if let Some(command) = self.commands.get(cmd_name) {
             let seq_num = command.get_seq_num();
             let origin = command.get_origin();
             let identity = command.get_identity();
             let sal_index = command.get_index();
-            let data = T::default()
+            let data = self
+                .with_schema_defaults(cmd_name, T::default())?
                 .with_timestamps()
                 .with_private_seq_num(seq_num)
                 .with_private_origin(origin)
@@ -170,6 +393,41 @@ impl<'b> Remote<'b> {
         }
     }
 
+    /// Replace the values of `data` with the defaults declared in the schema
+    /// of the topic.
+    fn with_schema_defaults<T>(&self, topic_name: &str, data: T) -> SalObjResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        match self.sal_info.get_topic_info(topic_name) {
+            Some(topic_info) => Remote::apply_schema_defaults(topic_info, data),
+            None => Ok(data),
+        }
+    }
+
+    /// Replace the values of `data` with the defaults declared in the fields
+    /// of `topic_info`.
+    fn apply_schema_defaults<T>(topic_info: &TopicInfo, data: T) -> SalObjResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let to_error = |error: serde_json::Error| SalObjError::new(&error.to_string());
+
+        let mut values = serde_json::to_value(data).map_err(to_error)?;
+
+        if let Some(values) = values.as_object_mut() {
+            for field in topic_info.get_fields() {
+                if let (Some(value), Some(default)) =
+                    (values.get_mut(field.get_name()), field.get_default())
+                {
+                    *value = default.to_owned();
+                }
+            }
+        }
+
+        serde_json::from_value(values).map_err(to_error)
+    }
+
     pub async fn run_command_typed<T>(
         &mut self,
         command_name: &str,
@@ -180,6 +438,10 @@ impl<'b> Remote<'b> {
     where
         T: BaseSALTopic + Serialize + Debug,
     {
+        if let Some(error) = self.get_command_error() {
+            return Err(CommandAck::invalid_command(&error));
+        }
+
         if let Some(command) = self.commands.get_mut(command_name) {
             command.run_typed(data, timeout, wait_done).await
         } else {
@@ -189,6 +451,72 @@ impl<'b> Remote<'b> {
         }
     }
 
+    /// Send the CSC to Disabled with the `start` command, configuring it
+    /// with the `configuration_override` file, and wait for the command to
+    /// complete.
+    pub async fn start_with_config(
+        &mut self,
+        configuration_override: &str,
+        timeout: Duration,
+    ) -> remote_command::AckCmdResult {
+        let start = self
+            .get_command_data::<Start>("command_start")
+            .map_err(|error| CommandAck::invalid_command(error.get_error_message()))?
+            .with_configuration_override(configuration_override);
+
+        self.run_command_typed("command_start", &start, timeout, true)
+            .await
+    }
+
+    /// Run a state transition command, e.g. `command_enable`, and return the
+    /// summary state the component reports after it completes.
+    ///
+    /// `command_start` is sent without a configuration override, use
+    /// [Remote::start_with_config] to give one. Summary states seen before
+    /// the command was issued are discarded.
+    pub async fn run_state_command(
+        &mut self,
+        command_name: &str,
+        timeout: Duration,
+    ) -> SalObjResult<State> {
+        let schema = self
+            .get_command_schema(command_name)
+            .ok_or_else(|| SalObjError::new(&format!("No command {command_name}.")))?;
+        let mut parameters = Record::new(&schema)
+            .ok_or_else(|| SalObjError::new(&format!("Invalid schema for {command_name}.")))?;
+        if command_name == "command_start" && self.sal_info.is_configurable() {
+            parameters.put("configurationOverride", Value::String(String::new()));
+        }
+
+        self.events
+            .get_mut("logevent_summaryState")
+            .ok_or_else(|| SalObjError::new("No logevent_summaryState event."))?
+            .flush();
+
+        self.run_command(command_name.to_owned(), &mut parameters, timeout, true)
+            .await
+            .map_err(|command_ack| {
+                SalObjError::new(&format!(
+                    "Command {command_name} failed: {}",
+                    command_ack.get_result()
+                ))
+            })?;
+
+        let summary_state = self
+            .events
+            .get_mut("logevent_summaryState")
+            .ok_or_else(|| SalObjError::new("No logevent_summaryState event."))?
+            .pop_back(false, timeout)
+            .await
+            .ok_or_else(|| {
+                SalObjError::new(&format!(
+                    "No summary state after {command_name} in {timeout:?}."
+                ))
+            })?;
+
+        Ok(from_value::<SummaryState>(&summary_state)?.get_summary_state())
+    }
+
     pub async fn pop_event_front(
         &mut self,
         event_name: &str,
@@ -202,6 +530,23 @@ impl<'b> Remote<'b> {
         }
     }
 
+    /// Same as [Remote::pop_event_front] but deserialize the event into `T`.
+    pub async fn pop_event_front_typed<T>(
+        &mut self,
+        event_name: &str,
+        flush: bool,
+        timeout: Duration,
+    ) -> SalObjResult<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(event_reader) = self.events.get_mut(event_name) {
+            event_reader.pop_front_typed(flush, timeout).await
+        } else {
+            Err(SalObjError::new(&format!("No event topic {event_name}")))
+        }
+    }
+
     pub async fn pop_event_back(
         &mut self,
         event_name: &str,
@@ -228,6 +573,303 @@ impl<'b> Remote<'b> {
         }
     }
 
+    /// Get the consumer lag of a telemetry topic, see [ReadTopic::lag].
+    pub fn telemetry_lag(&mut self, telemetry_name: &str) -> SalObjResult<i64> {
+        self.telemetry
+            .get_mut(telemetry_name)
+            .ok_or_else(|| SalObjError::new(&format!("No telemetry topic {telemetry_name}")))?
+            .lag()
+    }
+
+    /// Read the `n_acks` most recent command acknowledgements of the
+    /// component, from any commander, oldest first.
+    ///
+    /// This is meant for auditing: a new ackcmd reader is created, reading
+    /// the history of the topic up to the end it had when the reader
+    /// started. Reading also stops when `timeout` expires, so the call
+    /// returns even on a busy topic. The remote does not keep the domain it
+    /// was created with, so, as for [Remote::query_state], `domain` provides
+    /// the Kafka connection of the reader.
+    ///
+    /// # Remotes of all indices
+    ///
+    /// For a remote of all indices (index 0 of an indexed component) **at
+    /// most one historical ack per index is read**, whatever `n_acks` is,
+    /// since readers of all indices only keep the most recent sample of each
+    /// index from the history.
+    pub async fn read_recent_acks(
+        &self,
+        domain: &domain::Domain,
+        n_acks: usize,
+        timeout: Duration,
+    ) -> SalObjResult<Vec<CommandAck>> {
+        if self.sal_info.is_readonly() {
+            return Err(SalObjError::new(&format!(
+                "{} has no commands, so no ackcmd topic.",
+                self.sal_info.get_name()
+            )));
+        }
+
+        let max_history = if self.is_all_indices() {
+            if n_acks > 1 {
+                log::warn!(
+                    "Reading at most one historical ack per index of {}, not {n_acks}.",
+                    self.sal_info.get_name()
+                );
+            }
+            1
+        } else {
+            n_acks.max(1)
+        };
+        let mut ack_reader = ReadTopic::new("ackcmd", &self.sal_info, domain, max_history);
+
+        let deadline = Instant::now() + timeout;
+        let mut acks = Vec::new();
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if ack_reader.is_caught_up() {
+                break;
+            }
+            match ack_reader.pop_front(false, remaining).await {
+                Some(ackcmd) => acks.push(CommandAck::from_ackcmd_record(
+                    &AckCmdRecord::from_value(&ackcmd)?,
+                )),
+                None => break,
+            }
+        }
+
+        if acks.len() > n_acks {
+            acks.drain(..acks.len() - n_acks);
+        }
+        Ok(acks)
+    }
+
+    /// Read the samples of all the topics of `T` as a single stream.
+    ///
+    /// Each sample is decoded into the variant of its topic. Topics are read
+    /// in turn, so a busy topic does not starve the others, and the stream
+    /// ends when no sample arrives for `timeout`. Topics of `T` without a
+    /// reader in the remote are skipped with a warning.
+    pub fn event_stream<T>(&mut self, timeout: Duration) -> LocalBoxStream<'_, SalObjResult<T>>
+    where
+        T: TopicEnum + 'static,
+    {
+        let topic_names: Vec<String> = T::get_topic_names()
+            .into_iter()
+            .filter(|topic_name| {
+                let has_reader = self.events.contains_key(*topic_name)
+                    || self.telemetry.contains_key(*topic_name);
+                if !has_reader {
+                    log::warn!("No reader for {topic_name}; skipping it in the stream.");
+                }
+                has_reader
+            })
+            .map(str::to_owned)
+            .collect();
+
+        stream::unfold(
+            (self, topic_names, 0, Instant::now()),
+            move |(remote, topic_names, mut next_topic, last_sample_time)| async move {
+                if topic_names.is_empty() {
+                    return None;
+                }
+                loop {
+                    let topic_name = &topic_names[next_topic % topic_names.len()];
+                    next_topic += 1;
+
+                    let reader = match remote.events.get_mut(topic_name) {
+                        Some(reader) => reader,
+                        None => remote.telemetry.get_mut(topic_name)?,
+                    };
+                    if let Some(data) = reader.pop_front(false, STREAM_POLL_TIME).await {
+                        let sample =
+                            T::from_topic_data(topic_name, &data)?.map_err(SalObjError::from_error);
+                        return Some((sample, (remote, topic_names, next_topic, Instant::now())));
+                    }
+                    if last_sample_time.elapsed() >= timeout {
+                        return None;
+                    }
+                }
+            },
+        )
+        .boxed_local()
+    }
+
+    /// Wait for the next sample of a telemetry topic.
+    ///
+    /// Data already in the queue is flushed, so only samples that arrive
+    /// after calling this method are returned.
+    pub async fn wait_for_telemetry(
+        &mut self,
+        telemetry_name: &str,
+        timeout: Duration,
+    ) -> SalObjResult<Value> {
+        if let Some(telemetry_reader) = self.telemetry.get_mut(telemetry_name) {
+            telemetry_reader
+                .pop_front(true, timeout)
+                .await
+                .ok_or_else(|| {
+                    SalObjError::new(&format!(
+                        "No {telemetry_name} telemetry received in {timeout:?}."
+                    ))
+                })
+        } else {
+            Err(SalObjError::new(&format!(
+                "No telemetry topic {telemetry_name}"
+            )))
+        }
+    }
+
+    /// Wait for the component to be in the given summary state.
+    ///
+    /// See [Remote::wait_for_any_summary_state].
+    pub async fn wait_for_summary_state(
+        &mut self,
+        target: State,
+        timeout: Duration,
+    ) -> SalObjResult<State> {
+        self.wait_for_any_summary_state(&[target], timeout).await
+    }
+
+    /// Wait for the component to be in any of the given summary states.
+    ///
+    /// The queue of summary states is first drained to its newest sample,
+    /// so older samples cannot be mistaken for a new transition. Return
+    /// immediately if that sample is one of the targets, otherwise wait for
+    /// new samples until one matches or the timeout expires. Return the
+    /// matching state.
+    pub async fn wait_for_any_summary_state(
+        &mut self,
+        targets: &[State],
+        timeout: Duration,
+    ) -> SalObjResult<State> {
+        let summary_state_reader = self
+            .events
+            .get_mut("logevent_summaryState")
+            .ok_or_else(|| SalObjError::new("No logevent_summaryState event."))?;
+
+        let newest = summary_state_reader
+            .pop_back(false, SUMMARY_STATE_POLL_TIME.min(timeout))
+            .await;
+        summary_state_reader.flush();
+
+        if let Some(summary_state) = newest.or_else(|| summary_state_reader.get()) {
+            let summary_state = from_value::<SummaryState>(&summary_state)?.get_summary_state();
+            if targets.contains(&summary_state) {
+                return Ok(summary_state);
+            }
+        }
+
+        let deadline = Instant::now() + timeout;
+
+        while let Some(summary_state) = summary_state_reader
+            .pop_front(false, deadline.saturating_duration_since(Instant::now()))
+            .await
+        {
+            let summary_state = from_value::<SummaryState>(&summary_state)?.get_summary_state();
+            if targets.contains(&summary_state) {
+                return Ok(summary_state);
+            }
+        }
+
+        Err(SalObjError::new(&format!(
+            "Summary state not in {targets:?} after {timeout:?}."
+        )))
+    }
+
+    /// Get the enumeration published by an event.
+    ///
+    /// The enumeration is read from the field named after the event, e.g.
+    /// `detailedState` for `logevent_detailedState`. Use the last sample seen
+    /// or wait up to `timeout` for a new one.
+    pub async fn get_enum_event<E: SalEnum>(
+        &mut self,
+        event_name: &str,
+        timeout: Duration,
+    ) -> SalObjResult<E> {
+        let event_reader = self
+            .events
+            .get_mut(event_name)
+            .ok_or_else(|| SalObjError::new(&format!("No {event_name} event.")))?;
+
+        let event = match event_reader.get() {
+            Some(event) => event,
+            None => event_reader
+                .pop_back(false, timeout)
+                .await
+                .ok_or_else(|| SalObjError::new(&format!("No {event_name} after {timeout:?}.")))?,
+        };
+
+        get_enum_field(
+            &event,
+            event_name.strip_prefix("logevent_").unwrap_or(event_name),
+        )
+    }
+
+    /// Verify that the component publishes all the mandatory generic events;
+    /// heartbeat, summaryState and softwareVersions.
+    ///
+    /// Each event must have been seen or arrive within `timeout`. Return an
+    /// error listing the missing events otherwise.
+    pub async fn verify_mandatory_events(&mut self, timeout: Duration) -> SalObjResult<()> {
+        let deadline = Instant::now() + timeout;
+        let mut missing_events = Vec::new();
+
+        for event_name in MANDATORY_GENERICS {
+            let received = match self.events.get_mut(event_name) {
+                Some(event_reader) => {
+                    event_reader.get().is_some()
+                        || event_reader
+                            .pop_back(false, deadline.saturating_duration_since(Instant::now()))
+                            .await
+                            .is_some()
+                }
+                None => false,
+            };
+            if !received {
+                missing_events.push(event_name);
+            }
+        }
+
+        if missing_events.is_empty() {
+            Ok(())
+        } else {
+            Err(SalObjError::new(&format!(
+                "Mandatory events not received after {timeout:?}: {missing_events:?}."
+            )))
+        }
+    }
+
+    /// Get the indices of the component instances that are alive.
+    ///
+    /// Read heartbeats for `observe` time and return the distinct, sorted,
+    /// `salIndex` values seen. This is mostly useful for a remote of an
+    /// indexed component with index 0, which reads data from all indices.
+    pub async fn active_indices(&mut self, observe: Duration) -> SalObjResult<Vec<i32>> {
+        let heartbeat_reader = self
+            .events
+            .get_mut("logevent_heartbeat")
+            .ok_or_else(|| SalObjError::new("No logevent_heartbeat event."))?;
+
+        let deadline = Instant::now() + observe;
+        let mut active_indices: Vec<i32> = Vec::new();
+
+        while let Some(heartbeat) = heartbeat_reader
+            .pop_front(false, deadline.saturating_duration_since(Instant::now()))
+            .await
+        {
+            let sal_index = ReadTopic::get_sal_index(&heartbeat).unwrap_or_default();
+            if !active_indices.contains(&sal_index) {
+                active_indices.push(sal_index);
+            }
+            if Instant::now() >= deadline {
+                break;
+            }
+        }
+        active_indices.sort();
+
+        Ok(active_indices)
+    }
+
     pub async fn pop_telemetry_back(
         &mut self,
         telemetry_name: &str,
@@ -245,6 +887,12 @@ impl<'b> Remote<'b> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        csc::test_csc::topics::{
+            arrays::Arrays, scalars::Scalars, telemetry::TestTelemetry, wait::Wait,
+        },
+        topics::{topic_info::FieldMetadata, write_topic::WriteTopic},
+    };
     #[test]
     fn test_get_name() {
         let mut domain = domain::Domain::new();
@@ -255,6 +903,120 @@ mod tests {
         assert_eq!("Test", remote.get_name())
     }
 
+    #[test]
+    fn get_command_data_schema_defaults() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+
+        let start: Start = remote.get_command_data("command_start").unwrap();
+        let set_arrays: Arrays = remote.get_command_data("command_setArrays").unwrap();
+
+        assert_eq!(start.get_configuration_override(), "");
+        assert_eq!(start.get_sal_index(), 1);
+        assert_eq!(set_arrays.int0, vec![0; 5]);
+    }
+
+    #[test]
+    fn apply_schema_defaults_override() {
+        let schema = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/schema_overrides/command_defaults/Test_command_wait.json"
+        ))
+        .unwrap();
+        let topic_info = TopicInfo::new().with_fields(FieldMetadata::from_schema_str(&schema));
+
+        let wait = Remote::apply_schema_defaults(&topic_info, Wait::default()).unwrap();
+
+        assert_eq!(wait.duration, 2.5);
+    }
+
+    #[tokio::test]
+    async fn readonly_run_command() {
+        let mut domain = domain::Domain::new();
+        let mut remote =
+            Remote::new(&mut domain, "Test", 1, true, Vec::new(), Vec::new(), 1).unwrap();
+
+        assert!(remote.is_readonly());
+
+        let timeout = Duration::from_secs(1);
+        let start = Start::default();
+
+        let ack = remote
+            .run_command_typed("command_start", &start, timeout, true)
+            .await
+            .unwrap_err();
+        assert_eq!(ack.get_result(), READONLY_ERROR);
+
+        let schema = remote
+            .sal_info
+            .get_topic_info("command_start")
+            .and_then(|topic_info| topic_info.get_schema_ref())
+            .cloned()
+            .unwrap();
+        let mut record = Record::new(&schema).unwrap();
+        let ack = remote
+            .run_command("command_start".to_owned(), &mut record, timeout, true)
+            .await
+            .unwrap_err();
+        assert_eq!(ack.get_result(), READONLY_ERROR);
+
+        let error = remote
+            .get_command_data::<Start>("command_start")
+            .unwrap_err();
+        assert_eq!(error.get_error_message(), READONLY_ERROR);
+    }
+
+    #[tokio::test]
+    async fn readonly_component_run_command() {
+        let mut domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut domain, "Sensor", 0).unwrap();
+
+        assert!(remote.is_readonly());
+
+        let ack = remote
+            .run_command_typed(
+                "command_start",
+                &Start::default(),
+                Duration::from_secs(1),
+                true,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(ack.get_result(), READONLY_ERROR);
+    }
+
+    #[tokio::test]
+    async fn all_indices_run_command() {
+        let mut domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut domain, "Test", 0).unwrap();
+
+        assert!(remote.is_all_indices());
+
+        let expected_error = "Test is indexed and this remote reads all indices (index=0); \
+                              use a specific index to issue commands.";
+        let timeout = Duration::from_secs(1);
+
+        let ack = remote
+            .run_command_typed("command_start", &Start::default(), timeout, true)
+            .await
+            .unwrap_err();
+        assert_eq!(ack.get_result(), expected_error);
+
+        let schema = remote.get_command_schema("command_start").unwrap();
+        let mut record = Record::new(&schema).unwrap();
+        let ack = remote
+            .run_command("command_start".to_owned(), &mut record, timeout, true)
+            .await
+            .unwrap_err();
+        assert_eq!(ack.get_result(), expected_error);
+
+        // Reading across indices is still allowed.
+        assert!(remote
+            .pop_telemetry_back("scalars", false, Duration::from_millis(10))
+            .await
+            .is_ok());
+    }
+
     #[test]
     fn test_get_index() {
         let mut domain = domain::Domain::new();
@@ -264,4 +1026,230 @@ mod tests {
 
         assert_eq!(index, remote.get_index());
     }
+
+    #[tokio::test]
+    async fn event_stream() {
+        let mut domain = domain::Domain::new();
+        let mut remote = Remote::new(
+            &mut domain,
+            "Test",
+            1,
+            true,
+            vec!["scalars".to_owned(), "arrays".to_owned()],
+            Vec::new(),
+            0,
+        )
+        .unwrap();
+        remote.sal_info.register_schema().await;
+
+        let sal_info = sal_info::SalInfo::new("Test", 1).unwrap();
+        let mut scalars_writer = WriteTopic::new("scalars", &sal_info, &domain);
+        let scalars = Scalars::default()
+            .with_sal_index(1)
+            .with_private_seq_num(scalars_writer.get_seq_num());
+        scalars_writer.write_typed(&scalars).await.unwrap();
+
+        let mut arrays_writer = WriteTopic::new("arrays", &sal_info, &domain);
+        let mut arrays = Arrays::default()
+            .with_sal_index(1)
+            .with_private_seq_num(arrays_writer.get_seq_num());
+        arrays.int0 = vec![1, 2, 3, 4, 5];
+        arrays_writer.write_typed(&arrays).await.unwrap();
+
+        let samples: Vec<TestTelemetry> = remote
+            .event_stream::<TestTelemetry>(Duration::from_secs(5))
+            .take(2)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(samples.len(), 2);
+        assert!(samples
+            .iter()
+            .any(|sample| matches!(sample, TestTelemetry::Scalars(_))));
+        assert!(samples.iter().any(
+            |sample| matches!(sample, TestTelemetry::Arrays(arrays) if arrays.int0 == [1, 2, 3, 4, 5])
+        ));
+    }
+
+    #[tokio::test]
+    async fn with_index() {
+        let mut domain = domain::Domain::new();
+        let mut remote = Remote::new(
+            &mut domain,
+            "Test",
+            1,
+            false,
+            vec!["scalars".to_owned()],
+            Vec::new(),
+            0,
+        )
+        .unwrap();
+        remote.sal_info.register_schema().await;
+
+        remote.with_index(2).unwrap();
+
+        assert_eq!(remote.get_index(), 2);
+        assert_eq!(
+            remote
+                .get_command_data::<Start>("command_start")
+                .unwrap()
+                .get_sal_index(),
+            2
+        );
+
+        for index in [1, 2] {
+            let sal_info = sal_info::SalInfo::new("Test", index).unwrap();
+            let mut write_topic = WriteTopic::new("scalars", &sal_info, &domain);
+            let scalars = Scalars::default()
+                .with_sal_index(index as i32)
+                .with_private_seq_num(write_topic.get_seq_num());
+            write_topic.write_typed(&scalars).await.unwrap();
+        }
+
+        let scalars = remote
+            .wait_for_telemetry("scalars", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(ReadTopic::get_sal_index(&scalars), Some(2));
+    }
+
+    #[test]
+    fn with_index_invalid() {
+        let mut domain = domain::Domain::new();
+        let mut remote =
+            Remote::new(&mut domain, "Test", 1, false, Vec::new(), Vec::new(), 2).unwrap();
+
+        assert!(remote.with_index(0).is_err());
+        assert_eq!(remote.get_index(), 1);
+        assert!(remote
+            .commands
+            .values()
+            .all(|command| command.get_index() == 1));
+    }
+
+    #[test]
+    fn with_index_not_indexed() {
+        let mut domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut domain, "Sensor", 0).unwrap();
+
+        assert!(remote.with_index(0).is_ok());
+        assert!(remote.with_index(1).is_err());
+    }
+
+    #[test]
+    fn test_new_readonly_component() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::from_name_index(&mut domain, "Sensor", 0).unwrap();
+
+        assert!(remote.commands.is_empty());
+        assert!(remote.telemetry.contains_key("temperature"));
+        assert!(remote.events.contains_key("logevent_heartbeat"));
+    }
+
+    #[test]
+    fn new_duplicated_include() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::new(
+            &mut domain,
+            "Test",
+            1,
+            false,
+            vec!["scalars".to_owned(), "scalars".to_owned()],
+            Vec::new(),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(remote.telemetry.len(), 1);
+        assert!(remote.telemetry.contains_key("scalars"));
+        assert!(remote.events.is_empty());
+    }
+
+    #[test]
+    fn new_include_and_exclude() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::new(
+            &mut domain,
+            "Test",
+            1,
+            false,
+            vec!["scalars".to_owned()],
+            vec!["arrays".to_owned()],
+            1,
+        );
+
+        assert!(remote.is_err());
+    }
+
+    #[test]
+    fn new_exclude() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::new(
+            &mut domain,
+            "Test",
+            1,
+            false,
+            Vec::new(),
+            vec!["logevent_heartbeat".to_owned()],
+            1,
+        )
+        .unwrap();
+
+        assert!(!remote.events.contains_key("logevent_heartbeat"));
+        assert!(remote.events.contains_key("logevent_summaryState"));
+    }
+
+    #[test]
+    fn telemetry_lag_unknown_topic() {
+        let mut domain = domain::Domain::new();
+        let mut remote = Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+
+        assert!(remote.telemetry_lag("no_such_telemetry").is_err());
+    }
+
+    #[test]
+    fn test_validate_command() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+
+        let schema = remote.get_command_schema("command_start").unwrap();
+        let mut record = Record::new(&schema).unwrap();
+        record.put(
+            "configurationOverride",
+            Value::String("some_config.yaml".to_owned()),
+        );
+
+        assert!(remote.validate_command("command_start", &record).is_ok())
+    }
+
+    #[test]
+    fn test_validate_command_missing_field() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+
+        let schema = remote.get_command_schema("command_start").unwrap();
+        let record = Record::new(&schema).unwrap();
+
+        let error = remote
+            .validate_command("command_start", &record)
+            .unwrap_err();
+
+        assert_eq!(
+            error.get_error_message(),
+            "Field configurationOverride of command_start is not set."
+        )
+    }
+
+    #[test]
+    fn test_validate_command_wrong_type() {
+        let mut domain = domain::Domain::new();
+        let remote = Remote::from_name_index(&mut domain, "Test", 1).unwrap();
+
+        let schema = remote.get_command_schema("command_start").unwrap();
+        let mut record = Record::new(&schema).unwrap();
+        record.put("configurationOverride", Value::Int(1));
+
+        assert!(remote.validate_command("command_start", &record).is_err())
+    }
 }
@@ -1,6 +1,8 @@
 //! Standard enumerations and utilities used by the middleware.
 
+use crate::error::errors::{SalObjError, SalObjResult};
 use crate::generics::summary_state::SummaryState;
+use crate::topics::read_topic::ReadTopic;
 use apache_avro::types::Value;
 use num_traits::{cast::cast, PrimInt};
 use std::{fmt, str::FromStr, string::ParseError};
@@ -146,6 +148,51 @@ impl State {
     }
 }
 
+/// A component enumeration published as an integer field of an event.
+///
+/// Implement this trait for component specific enumerations (e.g. a
+/// `detailedState`) to decode them from events with
+/// [Remote::get_enum_event](crate::remote::Remote::get_enum_event).
+pub trait SalEnum: Sized {
+    /// Convert the published value into the enumeration, return `None` if
+    /// the value is not valid.
+    fn from_sal_value(value: i64) -> Option<Self>;
+}
+
+impl SalEnum for State {
+    fn from_sal_value(value: i64) -> Option<Self> {
+        match State::from(value) {
+            State::Invalid => None,
+            state => Some(state),
+        }
+    }
+}
+
+/// Decode the enumeration stored in `field_name` of an event record.
+pub fn get_enum_field<E: SalEnum>(record: &Value, field_name: &str) -> SalObjResult<E> {
+    let value = match record {
+        Value::Record(fields) => fields
+            .iter()
+            .find(|(name, _)| name == field_name)
+            .map(|(_, value)| ReadTopic::unwrap_union(value)),
+        _ => None,
+    };
+
+    let value = match value {
+        Some(Value::Int(value)) => *value as i64,
+        Some(Value::Long(value)) => *value,
+        Some(value) => {
+            return Err(SalObjError::new(&format!(
+                "Field {field_name} has non integer value {value:?}."
+            )))
+        }
+        None => return Err(SalObjError::new(&format!("No field {field_name}."))),
+    };
+
+    E::from_sal_value(value)
+        .ok_or_else(|| SalObjError::new(&format!("Invalid value {value} for {field_name}.")))
+}
+
 impl FromStr for State {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -295,4 +342,54 @@ mod tests {
 
         assert_eq!(state, 5)
     }
+
+    #[derive(Debug, PartialEq)]
+    enum DetailedState {
+        Idle = 1,
+        Tracking = 2,
+    }
+
+    impl SalEnum for DetailedState {
+        fn from_sal_value(value: i64) -> Option<Self> {
+            match value {
+                1 => Some(DetailedState::Idle),
+                2 => Some(DetailedState::Tracking),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn get_enum_field_detailed_state() {
+        let record = Value::Record(vec![
+            ("salIndex".to_owned(), Value::Int(1)),
+            ("detailedState".to_owned(), Value::Int(2)),
+        ]);
+
+        assert_eq!(
+            get_enum_field::<DetailedState>(&record, "detailedState").unwrap(),
+            DetailedState::Tracking
+        );
+    }
+
+    #[test]
+    fn get_enum_field_invalid() {
+        let record = Value::Record(vec![("detailedState".to_owned(), Value::Int(7))]);
+
+        assert!(get_enum_field::<DetailedState>(&record, "detailedState").is_err());
+        assert!(get_enum_field::<DetailedState>(&record, "badField").is_err());
+    }
+
+    #[test]
+    fn get_enum_field_state() {
+        let record = Value::Record(vec![(
+            "summaryState".to_owned(),
+            Value::Union(1, Box::new(Value::Int(5))),
+        )]);
+
+        assert_eq!(
+            get_enum_field::<State>(&record, "summaryState").unwrap(),
+            State::Standby
+        );
+    }
 }
@@ -65,26 +65,65 @@
 //!   * `lsst.ts.kafka-Test`.
 //!
 
+pub use crate::component_info::LintWarning;
+
 use crate::{
     component_info::ComponentInfo,
     domain::Domain,
     error::errors::{SalObjError, SalObjResult},
-    topics::topic_info::TopicInfo,
+    topics::{topic_info::TopicInfo, topic_names::TopicNames},
+    utils::avro_utils::{avro_to_json, json_to_avro},
 };
 
-use futures::future::join_all;
+use apache_avro::{
+    types::{Record, Value},
+    Schema,
+};
+use futures::{stream, Future, StreamExt};
 
 use schema_registry_converter::{
     async_impl::{
         avro::{AvroDecoder, AvroEncoder},
-        schema_registry::{post_schema, SrSettings},
+        schema_registry::{get_schema_by_subject, post_schema, SrSettings},
     },
     error::SRCError,
-    schema_registry_common::{RegisteredSchema, SchemaType, SuppliedSchema},
+    schema_registry_common::{RegisteredSchema, SchemaType, SubjectNameStrategy, SuppliedSchema},
 };
 use std::collections::HashMap;
 use std::env;
 
+/// Commands known to report in progress acknowledgements before completing,
+/// given as `(component name, topic name)`.
+///
+/// The interface does not mark long running commands, so they are listed
+/// here. Commands of other components are added at runtime with the
+/// LSST_LONG_RUNNING_COMMANDS environment variable, see
+/// [SalInfo::command_has_progress].
+const LONG_RUNNING_COMMANDS: &[(&str, &str)] = &[("Test", "command_wait")];
+
+/// Parse long running commands in the form
+/// `Component:command_name,Other:command_name`.
+///
+/// Malformed entries are ignored.
+fn parse_long_running_commands(commands: Option<String>) -> Vec<(String, String)> {
+    commands
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|command| {
+            let (component, command_name) = command.split_once(':')?;
+            let (component, command_name) = (component.trim(), command_name.trim());
+            if component.is_empty() || command_name.is_empty() {
+                None
+            } else {
+                Some((component.to_owned(), command_name.to_owned()))
+            }
+        })
+        .collect()
+}
+
+/// Default maximum number of concurrent requests to the schema registry.
+pub const DEFAULT_MAX_CONCURRENT_REGISTRATIONS: usize = 8;
+
 ///Information for one SAL component and index.
 pub struct SalInfo {
     index: isize,
@@ -95,7 +134,12 @@ unsafe impl Send for SalInfo {}
 
 impl SalInfo {
     /// Create a new instance of `SalInfo`.
+    ///
+    /// Renamed components can be loaded by their old name by setting the
+    /// LSST_COMPONENT_ALIASES environment variable, e.g. `OldName:NewName`.
     pub fn new(name: &str, index: isize) -> SalObjResult<SalInfo> {
+        SalInfo::validate_component_name(name)?;
+
         let topic_subname = match env::var("LSST_TOPIC_SUBNAME") {
             Ok(val) => val,
             Err(_) => {
@@ -118,6 +162,30 @@ impl SalInfo {
         })
     }
 
+    /// Check that `name` is a valid component name.
+    ///
+    /// Component names must start with a letter and contain only ASCII
+    /// letters and digits, e.g. `Test` or `ATMCS`.
+    pub fn validate_component_name(name: &str) -> SalObjResult<()> {
+        let mut chars = name.chars();
+
+        let is_valid = match chars.next() {
+            Some(first) => {
+                first.is_ascii_alphabetic() && chars.all(|char| char.is_ascii_alphanumeric())
+            }
+            None => false,
+        };
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(SalObjError::new(&format!(
+                "Invalid component name '{name}'. Must start with a letter and \
+                contain only letters and digits."
+            )))
+        }
+    }
+
     /// Make an AckCmd `Record` from keyword arguments.
     ///
     /// A `Record` is an object that is built from the avro schema and,
@@ -163,6 +231,11 @@ impl SalInfo {
         self.index
     }
 
+    /// Change the component index.
+    pub(crate) fn set_index(&mut self, index: isize) {
+        self.index = index;
+    }
+
     pub fn get_optional_index(&self) -> Option<i32> {
         if self.is_indexed() {
             Some(self.index as i32)
@@ -196,19 +269,49 @@ impl SalInfo {
         self.component_info.get_component_name()
     }
 
+    /// Check the component interface for common problems.
+    ///
+    /// See [LintWarning] for the problems reported.
+    pub fn lint(&self) -> Vec<LintWarning> {
+        self.component_info.lint()
+    }
+
+    /// Get the topics left out of the component because their schema cannot
+    /// be parsed, together with the parsing error.
+    pub fn get_parse_errors(&self) -> Vec<(String, String)> {
+        self.component_info.parse_errors()
+    }
+
+    /// Get the enumerations defined for the component, by name, as
+    /// `(item, value)` pairs.
+    pub fn get_enum_definitions(&self) -> HashMap<String, Vec<(String, i64)>> {
+        self.component_info.get_enum_definitions()
+    }
+
+    /// Make the client id used by the kafka consumers and producers.
+    ///
+    /// The client id has the form `{name}-{index}-{origin}` and allows
+    /// attributing connections to a component on the broker side.
+    pub fn make_client_id(&self, origin: u32) -> String {
+        format!("{}-{}-{origin}", self.get_name(), self.get_index())
+    }
+
+    /// Get the converter between the different names of the topics of the
+    /// component.
+    pub fn get_topic_names(&self) -> TopicNames {
+        TopicNames::new(
+            &self.component_info.get_component_name(),
+            &self.component_info.get_topic_subname(),
+        )
+    }
+
     /// Make schema registry topic name
     pub fn make_schema_registry_topic_name(&self, topic_name: &str) -> String {
-        format!(
-            "lsst.{}.{}.{}",
-            self.component_info.get_topic_subname(),
-            self.component_info.get_component_name(),
-            topic_name
-        )
-        // "lsst.test.Test.logevent_heartbeat".to_owned()
+        self.get_topic_names().make_schema_registry_name(topic_name)
     }
 
     pub fn get_sal_name(&self, topic_name: &str) -> String {
-        format!("{}_{}", self.get_name(), topic_name)
+        self.get_topic_names().make_sal_name(topic_name)
     }
 
     /// Make topic subject name.
@@ -243,30 +346,56 @@ impl SalInfo {
 
     /// Get names of all the topics.
     pub fn get_topics_name(&self) -> Vec<String> {
-        self.get_telemetry_names()
+        self.get_all_topic_names()
             .into_iter()
             .map(|topic_name| self.make_schema_registry_topic_name(&topic_name))
+            .collect()
+    }
+
+    /// Get subject names of all the topics.
+    pub fn get_all_subject_names(&self) -> Vec<String> {
+        self.get_all_topic_names()
+            .into_iter()
+            .map(|topic_name| self.make_subject_name(&topic_name))
+            .collect()
+    }
+
+    /// Get topic names of telemetry, events, commands and ackcmd.
+    fn get_all_topic_names(&self) -> Vec<String> {
+        self.get_telemetry_names()
+            .into_iter()
+            .chain(self.get_event_names())
+            .chain(self.get_command_names())
             .chain(
-                self.get_event_names()
-                    .into_iter()
-                    .map(|topic_name| self.make_schema_registry_topic_name(&topic_name)),
-            )
-            .chain(
-                self.get_command_names()
-                    .into_iter()
-                    .map(|topic_name| self.make_schema_registry_topic_name(&topic_name)),
+                self.component_info
+                    .get_ackcmd_topic_info()
+                    .map(|_| "ackcmd".to_owned()),
             )
-            .chain(vec![self.make_schema_registry_topic_name("ackcmd")])
             .collect()
     }
 
+    /// Is this a readonly component, e.g. one without commands?
+    pub fn is_readonly(&self) -> bool {
+        self.component_info.is_readonly()
+    }
+
+    /// Does the component take configuration?
+    ///
+    /// Configurable components have a `start` command with a
+    /// `configurationOverride` field, naming the configuration to load.
+    pub fn is_configurable(&self) -> bool {
+        self.get_topic_info("command_start")
+            .and_then(|topic_info| topic_info.get_field("configurationOverride"))
+            .is_some()
+    }
+
     /// Get topic info for a particular topic.
     ///
     /// This high-level method will identify if a topic is a command, event,
     /// telemetry or ackcmd and return the appropriate TopicInfo.
     pub fn get_topic_info(&self, topic_name: &str) -> Option<&TopicInfo> {
         if self.is_ackcmd(topic_name) {
-            Some(self.component_info.get_ackcmd_topic_info())
+            self.component_info.get_ackcmd_topic_info()
         } else if self.is_command(topic_name) {
             self.get_command_topic_info(topic_name)
         } else if self.is_event(topic_name) {
@@ -276,6 +405,27 @@ impl SalInfo {
         }
     }
 
+    /// Does the command report progress before completing?
+    ///
+    /// Long running commands acknowledge in progress first and only complete
+    /// later, while other commands complete right away. A command has
+    /// progress if it is in the built-in list of long running commands or in
+    /// the LSST_LONG_RUNNING_COMMANDS environment variable, given as
+    /// `Component:command_name` pairs separated by commas, e.g.
+    /// `MTMount:command_homeBothAxes,ATDome:command_moveAzimuth`. Return false
+    /// for unknown commands.
+    pub fn command_has_progress(&self, command_name: &str) -> bool {
+        let name = self.get_name();
+
+        self.get_command_topic_info(command_name).is_some()
+            && (LONG_RUNNING_COMMANDS
+                .iter()
+                .any(|(component, command)| *component == name && *command == command_name)
+                || parse_long_running_commands(env::var("LSST_LONG_RUNNING_COMMANDS").ok())
+                    .iter()
+                    .any(|(component, command)| *component == name && command == command_name))
+    }
+
     /// Check if topic name matches command acknowledgement.
     fn is_ackcmd(&self, topic_name: &str) -> bool {
         topic_name == "ackcmd"
@@ -312,29 +462,198 @@ impl SalInfo {
         self.component_info.get_topic_info_telemetry(topic_name)
     }
 
+    /// Register the schemas of all topics, unless the domain assumes they
+    /// are already registered, in which case nothing is sent to the schema
+    /// registry and an empty map is returned.
+    pub async fn register_schema_for_domain(
+        &self,
+        domain: &Domain,
+    ) -> HashMap<String, Result<RegisteredSchema, SRCError>> {
+        if domain.assume_schemas_registered() {
+            log::debug!("Assuming schemas for {} are registered.", self.get_name());
+            HashMap::new()
+        } else {
+            self.register_schema().await
+        }
+    }
+
+    /// Register the schemas of all topics, with at most
+    /// [DEFAULT_MAX_CONCURRENT_REGISTRATIONS] requests to the schema registry
+    /// in flight.
     pub async fn register_schema(&self) -> HashMap<String, Result<RegisteredSchema, SRCError>> {
+        self.register_schema_with_limit(DEFAULT_MAX_CONCURRENT_REGISTRATIONS)
+            .await
+    }
+
+    /// Register the schemas of all topics, with at most
+    /// `max_concurrent_registrations` requests to the schema registry in
+    /// flight.
+    ///
+    /// Large components have hundreds of topics, registering them all at
+    /// once may overwhelm the registry.
+    pub async fn register_schema_with_limit(
+        &self,
+        max_concurrent_registrations: usize,
+    ) -> HashMap<String, Result<RegisteredSchema, SRCError>> {
         let sr_settings = SalInfo::make_sr_settings();
 
         let topic_schema = self.component_info.get_topic_schemas();
 
-        join_all(topic_schema.iter().map(|(topic, schema)| async {
-            let supplied_schema = SuppliedSchema {
-                name: Some(self.make_schema_registry_topic_name(topic)),
-                schema_type: SchemaType::Avro,
-                schema: schema.to_owned(),
-                references: vec![],
-            };
-
-            (
-                topic.to_owned(),
-                post_schema(&sr_settings, self.make_subject_name(topic), supplied_schema).await,
-            )
-        }))
+        run_bounded(
+            topic_schema.iter().map(|(topic, schema)| async {
+                let supplied_schema = SuppliedSchema {
+                    name: Some(self.make_schema_registry_topic_name(topic)),
+                    schema_type: SchemaType::Avro,
+                    schema: schema.to_owned(),
+                    references: vec![],
+                };
+
+                (
+                    topic.to_owned(),
+                    post_schema(&sr_settings, self.make_subject_name(topic), supplied_schema).await,
+                )
+            }),
+            max_concurrent_registrations,
+        )
+        .await
+        .into_iter()
+        .collect()
+    }
+
+    /// Format a sample of a topic as a readable multi-line report.
+    ///
+    /// Each field is written in its own line with its value and, if the
+    /// field has any, its units, e.g. `  temperature: 12.5 deg_C`. Fields not
+    /// declared in the topic schema are shown without units.
+    pub fn format_sample(&self, topic_name: &str, sample: &Value) -> String {
+        let fields = match sample {
+            Value::Record(fields) => fields,
+            sample => return format!("{topic_name}: {}", avro_to_json(sample)),
+        };
+
+        let topic_info = self.get_topic_info(topic_name);
+
+        let mut report = format!("{topic_name}:");
+        for (field_name, value) in fields {
+            report.push_str(&format!("\n  {field_name}: {}", avro_to_json(value)));
+            if let Some(field) = topic_info.and_then(|topic_info| topic_info.get_field(field_name))
+            {
+                if !field.is_unitless() {
+                    report.push_str(&format!(" {}", field.get_units()));
+                }
+            }
+        }
+        report
+    }
+
+    /// Build a topic record from a map of field names to json values.
+    ///
+    /// Values are coerced into the avro types declared in the topic schema.
+    /// All fields, except private fields and `salIndex`, which are filled
+    /// when the record is written, must be provided.
+    pub fn build_record(
+        &self,
+        topic_name: &str,
+        fields: HashMap<String, serde_json::Value>,
+    ) -> SalObjResult<Record<'_>> {
+        let schema = self
+            .get_topic_info(topic_name)
+            .and_then(|topic_info| topic_info.get_schema_ref())
+            .ok_or_else(|| SalObjError::new(&format!("No topic {topic_name}.")))?;
+
+        let record_fields = if let Schema::Record { fields, .. } = schema {
+            fields
+        } else {
+            return Err(SalObjError::new(&format!(
+                "Schema for {topic_name} is not a record."
+            )));
+        };
+
+        if let Some(field_name) = fields
+            .keys()
+            .find(|field_name| !record_fields.iter().any(|field| field.name == **field_name))
+        {
+            return Err(SalObjError::new(&format!(
+                "Field {field_name} not in {topic_name}."
+            )));
+        }
+
+        let mut record = Record::new(schema)
+            .ok_or_else(|| SalObjError::new(&format!("Failed to create {topic_name} record.")))?;
+
+        for field in record_fields {
+            match fields.get(&field.name) {
+                Some(value) => record.put(&field.name, json_to_avro(value, &field.schema)?),
+                None if field.name.starts_with("private_") || field.name == "salIndex" => {}
+                None => {
+                    return Err(SalObjError::new(&format!(
+                        "Field {} of {topic_name} is not set.",
+                        field.name
+                    )))
+                }
+            }
+        }
+        Ok(record)
+    }
+
+    /// Register only the schemas that are missing from the registry or that
+    /// differ from the registered ones.
+    ///
+    /// Return the result of registering each of those schemas, so topics
+    /// that were already up to date are not in the returned map.
+    pub async fn register_missing_schemas(
+        &self,
+    ) -> HashMap<String, Result<RegisteredSchema, SRCError>> {
+        let sr_settings = SalInfo::make_sr_settings();
+
+        let topic_schema = self.component_info.get_topic_schemas();
+
+        run_bounded(
+            topic_schema.iter().map(|(topic, schema)| async {
+                let subject_name_strategy = SubjectNameStrategy::TopicNameStrategy(
+                    self.make_schema_registry_topic_name(topic),
+                    false,
+                );
+
+                if let Ok(registered_schema) =
+                    get_schema_by_subject(&sr_settings, &subject_name_strategy).await
+                {
+                    if SalInfo::same_schema(&registered_schema.schema, schema) {
+                        return None;
+                    }
+                }
+
+                let supplied_schema = SuppliedSchema {
+                    name: Some(self.make_schema_registry_topic_name(topic)),
+                    schema_type: SchemaType::Avro,
+                    schema: schema.to_owned(),
+                    references: vec![],
+                };
+
+                Some((
+                    topic.to_owned(),
+                    post_schema(&sr_settings, self.make_subject_name(topic), supplied_schema).await,
+                ))
+            }),
+            DEFAULT_MAX_CONCURRENT_REGISTRATIONS,
+        )
         .await
         .into_iter()
+        .flatten()
         .collect()
     }
 
+    /// Are the two json schemas equivalent?
+    ///
+    /// Schemas are compared in their canonical form, so formatting and
+    /// documentation differences are ignored.
+    fn same_schema(schema: &str, other: &str) -> bool {
+        match (Schema::parse_str(schema), Schema::parse_str(other)) {
+            (Ok(schema), Ok(other)) => schema.canonical_form() == other.canonical_form(),
+            _ => false,
+        }
+    }
+
     pub fn make_sr_settings() -> SrSettings {
         SrSettings::new(Domain::get_schema_registry_url())
     }
@@ -350,10 +669,32 @@ impl SalInfo {
     }
 }
 
+/// Run `futures` with at most `limit` of them in flight at a time.
+///
+/// Results are returned in completion order. A `limit` of zero is treated
+/// as one.
+async fn run_bounded<F: Future>(
+    futures: impl IntoIterator<Item = F>,
+    limit: usize,
+) -> Vec<F::Output> {
+    stream::iter(futures)
+        .buffer_unordered(limit.max(1))
+        .collect()
+        .await
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::{csc::test_csc::topics::scalars::Scalars, utils::testing::with_env_vars};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
 
     #[test]
     fn sal_info_get_command_names() {
@@ -388,6 +729,27 @@ mod tests {
         let _ = SalInfo::new("ATMCS", 1).unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "Invalid component name ''.")]
+    fn panic_if_empty_name() {
+        let _ = SalInfo::new("", 0).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid component name 'Test/../ATMCS'.")]
+    fn panic_if_name_with_slash() {
+        let _ = SalInfo::new("Test/../ATMCS", 0).unwrap();
+    }
+
+    #[test]
+    fn validate_component_name() {
+        assert!(SalInfo::validate_component_name("Test").is_ok());
+        assert!(SalInfo::validate_component_name("MTM1M3").is_ok());
+        assert!(SalInfo::validate_component_name(" Test").is_err());
+        assert!(SalInfo::validate_component_name("1Test").is_err());
+        assert!(SalInfo::validate_component_name("Test\\").is_err());
+    }
+
     #[test]
     fn get_name_index_indexed() {
         let sal_info = SalInfo::new("Test", 1).unwrap();
@@ -395,6 +757,175 @@ mod tests {
         assert_eq!(sal_info.get_name_index(), "Test:1")
     }
 
+    #[test]
+    fn get_all_subject_names() {
+        let sal_info = with_env_vars(&[("LSST_TOPIC_SUBNAME", Some("unit_test"))], || {
+            SalInfo::new("Test", 1)
+        })
+        .unwrap();
+
+        let subject_names = sal_info.get_all_subject_names();
+
+        assert_eq!(subject_names.len(), sal_info.get_topics_name().len());
+        assert!(subject_names.contains(&"lsst.unit_test.Test.command_setScalars-value".to_owned()));
+        assert!(subject_names.contains(&"lsst.unit_test.Test.ackcmd-value".to_owned()));
+    }
+
+    #[test]
+    fn build_record() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let fields: HashMap<String, serde_json::Value> = serde_json::from_str(
+            r#"{
+                "boolean0": true,
+                "byte0": 1,
+                "short0": 2,
+                "int0": 3,
+                "long0": 4,
+                "longLong0": 5,
+                "unsignedShort0": 6,
+                "unsignedInt0": 7,
+                "float0": 8.5,
+                "double0": 9.5,
+                "string0": "ten"
+            }"#,
+        )
+        .unwrap();
+
+        let record = sal_info.build_record("command_setScalars", fields).unwrap();
+
+        assert!(record
+            .fields
+            .contains(&("longLong0".to_owned(), Value::Long(5))));
+        assert!(record
+            .fields
+            .contains(&("float0".to_owned(), Value::Float(8.5))));
+        assert!(record
+            .fields
+            .contains(&("string0".to_owned(), Value::String("ten".to_owned()))));
+    }
+
+    #[test]
+    fn format_sample_scalars() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let mut scalars = Scalars::default();
+        scalars.int0 = 17;
+        let sample = apache_avro::to_value(scalars).unwrap();
+
+        let report = sal_info.format_sample("scalars", &sample);
+
+        assert!(report.starts_with("scalars:\n"));
+        assert!(report.lines().any(|line| line == "  int0: 17"));
+        assert!(report
+            .lines()
+            .any(|line| line.starts_with("  private_sndStamp: ") && line.ends_with(" second")));
+    }
+
+    #[test]
+    fn build_record_missing_field() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let fields = HashMap::from([("int0".to_owned(), serde_json::json!(3))]);
+
+        assert!(sal_info.build_record("command_setScalars", fields).is_err());
+    }
+
+    #[test]
+    fn build_record_unknown_field() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let fields = HashMap::from([
+            ("configurationOverride".to_owned(), serde_json::json!("")),
+            ("badField".to_owned(), serde_json::json!(1)),
+        ]);
+
+        assert!(sal_info.build_record("command_start", fields).is_err());
+    }
+
+    #[test]
+    fn same_schema() {
+        let schema =
+            r#"{"type": "record", "name": "scalars", "fields": [{"name": "int0", "type": "int"}]}"#;
+        let other = r#"{
+            "type": "record",
+            "name": "scalars",
+            "fields": [{"name": "int0", "type": "int", "description": "An int."}],
+            "description": "Scalars."
+        }"#;
+        let different = r#"{"type": "record", "name": "scalars", "fields": [{"name": "int0", "type": "long"}]}"#;
+
+        assert!(SalInfo::same_schema(schema, other));
+        assert!(!SalInfo::same_schema(schema, different));
+    }
+
+    #[tokio::test]
+    async fn register_missing_schemas() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        sal_info.register_missing_schemas().await;
+
+        assert!(sal_info.register_missing_schemas().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn run_bounded_limits_concurrency() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let results = run_bounded(
+            (0..40).map(|value| {
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                async move {
+                    let n_in_flight = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(n_in_flight, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    value
+                }
+            }),
+            DEFAULT_MAX_CONCURRENT_REGISTRATIONS,
+        )
+        .await;
+
+        assert_eq!(results.len(), 40);
+        assert_eq!(
+            max_in_flight.load(Ordering::SeqCst),
+            DEFAULT_MAX_CONCURRENT_REGISTRATIONS
+        );
+    }
+
+    #[tokio::test]
+    async fn register_schema_with_limit() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let registered_schemas = sal_info.register_schema_with_limit(2).await;
+
+        assert_eq!(
+            registered_schemas.len(),
+            sal_info.component_info.get_topic_schemas().len()
+        );
+        assert!(registered_schemas.values().all(|result| result.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn register_schema_for_domain_assume_registered() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let domain = Domain::new().with_assume_schemas_registered(true);
+
+        assert!(sal_info
+            .register_schema_for_domain(&domain)
+            .await
+            .is_empty());
+    }
+
+    #[test]
+    fn make_client_id() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let client_id = sal_info.make_client_id(1234);
+
+        assert!(client_id.contains("Test"));
+        assert_eq!(client_id, "Test-1-1234")
+    }
+
     #[test]
     fn get_name_index_non_indexed() {
         let sal_info = SalInfo::new("ATMCS", 0).unwrap();
@@ -462,6 +993,121 @@ mod tests {
         sal_info.get_topic_info(&"ackcmd").unwrap();
     }
 
+    #[test]
+    fn get_topics_name_readonly() {
+        let sal_info = SalInfo::new("Sensor", 0).unwrap();
+
+        assert!(sal_info.is_readonly());
+        assert!(sal_info.get_topic_info("ackcmd").is_none());
+        assert!(!sal_info
+            .get_topics_name()
+            .contains(&sal_info.make_schema_registry_topic_name("ackcmd")));
+    }
+
+    #[test]
+    fn get_enum_definitions() {
+        let enum_definitions = SalInfo::new("Test", 1).unwrap().get_enum_definitions();
+
+        assert_eq!(
+            enum_definitions["Enum"],
+            vec![
+                ("One".to_owned(), 0),
+                ("Two".to_owned(), 1),
+                ("Three".to_owned(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn get_parse_errors() {
+        assert!(SalInfo::new("Test", 1)
+            .unwrap()
+            .get_parse_errors()
+            .is_empty());
+        assert!(SalInfo::new("Broken", 1)
+            .unwrap()
+            .get_parse_errors()
+            .iter()
+            .any(|(topic_name, _)| topic_name == "logevent_malformed"));
+    }
+
+    #[test]
+    fn is_configurable() {
+        assert!(SalInfo::new("Test", 1).unwrap().is_configurable());
+        assert!(!SalInfo::new("Sensor", 0).unwrap().is_configurable());
+    }
+
+    #[test]
+    fn command_has_progress() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let (wait, enable, bad_command) =
+            with_env_vars(&[("LSST_LONG_RUNNING_COMMANDS", None)], || {
+                (
+                    sal_info.command_has_progress("command_wait"),
+                    sal_info.command_has_progress("command_enable"),
+                    sal_info.command_has_progress("command_badCommand"),
+                )
+            });
+
+        assert!(wait);
+        assert!(!enable);
+        assert!(!bad_command);
+    }
+
+    #[test]
+    fn parse_long_running_commands() {
+        let commands = super::parse_long_running_commands(Some(
+            "Test:command_enable, ATDome:command_moveAzimuth,bad,:x".to_owned(),
+        ));
+
+        assert_eq!(
+            commands,
+            vec![
+                ("Test".to_owned(), "command_enable".to_owned()),
+                ("ATDome".to_owned(), "command_moveAzimuth".to_owned()),
+            ]
+        );
+        assert!(super::parse_long_running_commands(None).is_empty());
+    }
+
+    #[test]
+    fn command_has_progress_from_env() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let (enable, wait, start) = with_env_vars(
+            &[(
+                "LSST_LONG_RUNNING_COMMANDS",
+                Some("Test:command_enable,ATDome:command_start"),
+            )],
+            || {
+                (
+                    sal_info.command_has_progress("command_enable"),
+                    sal_info.command_has_progress("command_wait"),
+                    sal_info.command_has_progress("command_start"),
+                )
+            },
+        );
+
+        assert!(enable);
+        assert!(wait);
+        assert!(!start);
+    }
+
+    #[test]
+    fn get_topic_info_field_units() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let scalars = sal_info.get_topic_info("scalars").unwrap();
+
+        assert!(scalars.get_field("double0").unwrap().is_unitless());
+        assert_eq!(
+            scalars.get_field("private_sndStamp").unwrap().get_units(),
+            "second"
+        );
+        assert!(scalars.get_field("badField").is_none());
+    }
+
     #[test]
     fn get_topic_info_command() {
         let sal_info = SalInfo::new("Test", 1).unwrap();
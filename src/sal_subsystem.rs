@@ -1,10 +1,53 @@
 use crate::error::errors::{SalObjError, SalObjResult};
-use crate::topics::topic_info::{self, TopicInfo};
-use crate::utils::schema_utils::{glob_schema_files, parse_hash_table};
+use crate::topics::topic_info::{self, FieldMetadata, TopicInfo};
+use crate::utils::schema_utils::{
+    apply_schema_overrides, glob_schema_files, parse_field_enums, parse_global_enums,
+    parse_hash_table,
+};
 use crate::utils::types::SALSubsystemInfoRet;
 use crate::utils::xml_utils::convert_sal_name_to_topic_name;
 use apache_avro::Schema;
 use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+
+/// Parse component aliases in the form `Old:New,Other:New`.
+///
+/// Malformed entries are ignored.
+pub(crate) fn parse_component_aliases(aliases: Option<String>) -> HashMap<String, String> {
+    aliases
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|alias| {
+            let (old_name, new_name) = alias.split_once(':')?;
+            let (old_name, new_name) = (old_name.trim(), new_name.trim());
+            if old_name.is_empty() || new_name.is_empty() {
+                None
+            } else {
+                Some((old_name.to_owned(), new_name.to_owned()))
+            }
+        })
+        .collect()
+}
+
+/// Resolve the name of a renamed component.
+///
+/// Aliases are read from the LSST_COMPONENT_ALIASES environment variable.
+/// Names without an alias are returned unchanged. Aliases are resolved a
+/// single time, in [SALSubsystemInfo::new]; other code should take the name
+/// from [SALSubsystemInfo::get_name].
+fn resolve_component_name(name: &str) -> String {
+    resolve_component_alias(
+        name,
+        &parse_component_aliases(env::var("LSST_COMPONENT_ALIASES").ok()),
+    )
+}
+
+fn resolve_component_alias(name: &str, aliases: &HashMap<String, String>) -> String {
+    aliases
+        .get(name)
+        .map_or_else(|| name.to_owned(), |new_name| new_name.to_owned())
+}
 
 pub struct SALSubsystemInfo {
     name: String,
@@ -15,12 +58,16 @@ pub struct SALSubsystemInfo {
 
 impl SALSubsystemInfo {
     pub fn new(name: &str) -> SALSubsystemInfoRet {
-        let topic_schema = glob_schema_files(name)?;
+        let name = &resolve_component_name(name);
+        let mut topic_schema = glob_schema_files(name)?;
+        if let Ok(override_dir) = env::var("LSST_SCHEMA_OVERRIDE_DIR") {
+            apply_schema_overrides(&mut topic_schema, Path::new(&override_dir), name)?;
+        }
         let hash_table = parse_hash_table(topic_schema.get(&format!("{name}_hash_table")).ok_or(
             SalObjError::new("Could not find hash table for component in schema directory."),
         )?)?;
 
-        let indexed = topic_schema.get(&format!("{name}_logevent_heartbeat")).ok_or(SalObjError::new("No heartbeat topic defined for component. Cannot determine if it is indexed. This is a mandatory topic so something might be wrong with the component topic list."))?.contains("salIndex");
+        let indexed = topic_schema.get(&format!("{name}_logevent_heartbeat")).or(topic_schema.get(&format!("{name}_ackcmd"))).ok_or(SalObjError::new("No heartbeat or ackcmd topic defined for component. Cannot determine if it is indexed. These are mandatory topics so something might be wrong with the component topic list."))?.contains("salIndex");
 
         Ok(SALSubsystemInfo {
             name: name.to_owned(),
@@ -30,6 +77,11 @@ impl SALSubsystemInfo {
         })
     }
 
+    /// Name of the component, with its alias resolved.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
     /// Is the component indexed?
     pub fn is_indexed(&self) -> bool {
         self.indexed
@@ -54,6 +106,42 @@ impl SALSubsystemInfo {
             .collect()
     }
 
+    /// Get the enumerations defined in the `<name>_global_enums` and
+    /// `<name>_field_enums` schemas, as `(item name, value)` pairs.
+    ///
+    /// Global enumerations are keyed by their name and field enumerations
+    /// by `<topic>.<field>`, e.g. `logevent_scalars.int0`. Schemas that cannot
+    /// be parsed are ignored with a warning.
+    pub fn get_enum_definitions(&self) -> HashMap<String, Vec<(String, i64)>> {
+        let mut enum_definitions = HashMap::new();
+
+        if let Some(global_enums) = self
+            .topic_schemas
+            .get(&format!("{}_global_enums", self.name))
+        {
+            match parse_global_enums(global_enums) {
+                Ok(global_enums) => enum_definitions.extend(global_enums),
+                Err(error) => log::warn!("Ignoring global enumerations of {}: {error}", self.name),
+            }
+        }
+
+        if let Some(field_enums) = self
+            .topic_schemas
+            .get(&format!("{}_field_enums", self.name))
+        {
+            match parse_field_enums(field_enums) {
+                Ok(field_enums) => {
+                    enum_definitions.extend(field_enums.into_iter().map(|(field, items)| {
+                        (convert_sal_name_to_topic_name(&self.name, &field), items)
+                    }))
+                }
+                Err(error) => log::warn!("Ignoring field enumerations of {}: {error}", self.name),
+            }
+        }
+
+        enum_definitions
+    }
+
     /// Get all commands from the component, including generics.
     pub fn get_commands(&self, topic_subname: &str) -> HashMap<String, topic_info::TopicInfo> {
         let commands = self
@@ -61,11 +149,8 @@ impl SALSubsystemInfo {
             .iter()
             .filter_map(|(topic_name, topic_schema)| {
                 if topic_name.contains("_command_") {
-                    if let Ok(schema) = Schema::parse_str(topic_schema) {
-                        Some((topic_name.to_owned(), schema))
-                    } else {
-                        None
-                    }
+                    SALSubsystemInfo::parse_schema(topic_name, topic_schema)
+                        .map(|schema| (topic_name.to_owned(), schema))
                 } else {
                     None
                 }
@@ -75,13 +160,15 @@ impl SALSubsystemInfo {
     }
 
     pub fn get_ackcmd(&self, topic_subname: &str) -> SalObjResult<topic_info::TopicInfo> {
-        let ackcmd = Schema::parse_str(self.topic_schemas.get(&format!("{}_ackcmd", self.name)).ok_or(SalObjError::new(&format!("No ackcmd topic found for {}. This is a mandatory topic so something might be wrong with the component topic list.", self.name)))?)?;
+        let ackcmd_str = self.topic_schemas.get(&format!("{}_ackcmd", self.name)).ok_or(SalObjError::new(&format!("No ackcmd topic found for {}. This is a mandatory topic so something might be wrong with the component topic list.", self.name)))?;
+        let ackcmd = Schema::parse_str(ackcmd_str)?;
 
         Ok(TopicInfo::new()
             .with_component(&self.name)
             .with_topic_name("ackcmd")
             .with_topic_subname(topic_subname)
             .with_schema(ackcmd)
+            .with_fields(FieldMetadata::from_schema_str(ackcmd_str))
             .with_rev_code(self.hash_table.get("ackcmd").map(|x| x.as_str()))
             .with_indexed(self.indexed))
     }
@@ -93,11 +180,8 @@ impl SALSubsystemInfo {
             .iter()
             .filter_map(|(topic_name, topic_schema)| {
                 if topic_name.contains("_logevent_") {
-                    if let Ok(schema) = Schema::parse_str(topic_schema) {
-                        Some((topic_name.to_owned(), schema))
-                    } else {
-                        None
-                    }
+                    SALSubsystemInfo::parse_schema(topic_name, topic_schema)
+                        .map(|schema| (topic_name.to_owned(), schema))
                 } else {
                     None
                 }
@@ -119,11 +203,8 @@ impl SALSubsystemInfo {
                     && !topic_name.contains("_command_")
                     && !topic_name.contains("_ackcmd")
                 {
-                    if let Ok(schema) = Schema::parse_str(topic_schema) {
-                        Some((topic_name.to_owned(), schema))
-                    } else {
-                        None
-                    }
+                    SALSubsystemInfo::parse_schema(topic_name, topic_schema)
+                        .map(|schema| (topic_name.to_owned(), schema))
                 } else {
                     None
                 }
@@ -132,6 +213,36 @@ impl SALSubsystemInfo {
         self.make_topic_info(telemetry, topic_subname)
     }
 
+    /// Get the topics whose schema cannot be parsed, together with the
+    /// parsing error.
+    ///
+    /// These topics are left out of the component commands, events and
+    /// telemetry.
+    pub fn get_parse_errors(&self) -> Vec<(String, String)> {
+        let mut parse_errors: Vec<(String, String)> = self
+            .get_topic_schemas()
+            .into_iter()
+            .filter_map(|(topic_name, topic_schema)| {
+                Schema::parse_str(&topic_schema)
+                    .err()
+                    .map(|error| (topic_name, error.to_string()))
+            })
+            .collect();
+        parse_errors.sort();
+        parse_errors
+    }
+
+    /// Parse a topic schema, logging a warning if it fails.
+    fn parse_schema(topic_name: &str, topic_schema: &str) -> Option<Schema> {
+        match Schema::parse_str(topic_schema) {
+            Ok(schema) => Some(schema),
+            Err(error) => {
+                log::warn!("Ignoring topic {topic_name}; failed to parse schema: {error}.");
+                None
+            }
+        }
+    }
+
     fn make_topic_info<T>(
         &self,
         topic_schemas: T,
@@ -149,6 +260,17 @@ impl SALSubsystemInfo {
                         .with_topic_name(&name)
                         .with_topic_subname(topic_subname)
                         .with_schema(schema.to_owned())
+                        .with_fields(
+                            self.topic_schemas
+                                .get(&name)
+                                .map(|schema_str| FieldMetadata::from_schema_str(schema_str))
+                                .unwrap_or_default(),
+                        )
+                        .with_timeout(
+                            self.topic_schemas
+                                .get(&name)
+                                .and_then(|schema_str| TopicInfo::parse_timeout(schema_str)),
+                        )
                         .with_rev_code(self.hash_table.get(&name).map(|x| x.as_str()))
                         .with_indexed(self.indexed),
                 )
@@ -162,6 +284,43 @@ mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    #[test]
+    fn parse_component_aliases() {
+        let aliases =
+            super::parse_component_aliases(Some("OldTest:Test, Old:New,bad,:x".to_owned()));
+
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases.get("OldTest").unwrap(), "Test");
+        assert_eq!(aliases.get("Old").unwrap(), "New");
+        assert!(super::parse_component_aliases(None).is_empty());
+    }
+
+    #[test]
+    fn resolve_component_alias() {
+        let aliases = super::parse_component_aliases(Some("OldTest:Test".to_owned()));
+
+        let name = super::resolve_component_alias("OldTest", &aliases);
+        let sal_subsystem_info = SALSubsystemInfo::new(&name).unwrap();
+
+        assert_eq!(name, "Test");
+        assert_eq!(sal_subsystem_info.name, "Test");
+        assert_eq!(super::resolve_component_alias("Sensor", &aliases), "Sensor");
+    }
+
+    #[test]
+    fn get_parse_errors() {
+        let sal_subsystem_info = SALSubsystemInfo::new("Broken").unwrap();
+
+        let parse_errors = sal_subsystem_info.get_parse_errors();
+
+        assert!(parse_errors
+            .iter()
+            .any(|(topic_name, _)| topic_name == "logevent_malformed"));
+        assert!(!sal_subsystem_info
+            .get_events("unit_test")
+            .contains_key("Broken_logevent_malformed"));
+    }
+
     #[test]
     fn is_indexed() {
         let sal_subsystem_info = SALSubsystemInfo::new("Test").unwrap();
@@ -195,4 +354,29 @@ mod tests {
             commands.keys().cloned().collect(),
         );
     }
+
+    #[test]
+    fn get_commands_timeout_override() {
+        let mut sal_subsystem_info = SALSubsystemInfo::new("Test").unwrap();
+
+        assert_eq!(
+            sal_subsystem_info.get_commands("unit_test")["Test_command_wait"].get_timeout(),
+            None
+        );
+
+        apply_schema_overrides(
+            &mut sal_subsystem_info.topic_schemas,
+            Path::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/data/schema_overrides/command_timeout/"
+            )),
+            "Test",
+        )
+        .unwrap();
+
+        assert_eq!(
+            sal_subsystem_info.get_commands("unit_test")["Test_command_wait"].get_timeout(),
+            Some(std::time::Duration::from_secs_f64(2.5))
+        );
+    }
 }
@@ -0,0 +1,101 @@
+//! Read a single topic from a SAL component.
+//!
+//! This is useful for focused monitoring, where creating a full
+//! [Remote](crate::remote::Remote) would subscribe to the entire interface
+//! of the component.
+
+use crate::domain;
+use crate::error::errors::{SalObjError, SalObjResult};
+use crate::sal_info;
+use crate::topics::read_topic::ReadTopic;
+use apache_avro::types::Value;
+use std::time::Duration;
+
+/// Reader bound to a single topic of a SAL component.
+pub struct SingleTopicReader<'a> {
+    sal_info: sal_info::SalInfo,
+    reader: ReadTopic<'a>,
+}
+
+impl<'a> SingleTopicReader<'a> {
+    pub fn new(
+        domain: &mut domain::Domain,
+        name: &str,
+        index: isize,
+        topic_name: &str,
+        max_history: usize,
+    ) -> SalObjResult<SingleTopicReader<'a>> {
+        let sal_info = sal_info::SalInfo::new(name, index)?;
+
+        if sal_info.get_topic_info(topic_name).is_none() {
+            return Err(SalObjError::new(&format!(
+                "Topic {topic_name} not in the list of topics of {name}."
+            )));
+        }
+
+        if let Err(error) =
+            domain.register_topics(&[sal_info.make_schema_registry_topic_name(topic_name)])
+        {
+            log::warn!("Failed to register topics: {error:?}. Continuing...");
+        }
+
+        let reader = ReadTopic::new(topic_name, &sal_info, domain, max_history);
+
+        Ok(SingleTopicReader { sal_info, reader })
+    }
+
+    /// Get component name.
+    pub fn get_name(&self) -> String {
+        self.sal_info.get_name()
+    }
+
+    /// Get component index.
+    pub fn get_index(&self) -> isize {
+        self.sal_info.get_index()
+    }
+
+    /// Get the name of the topic.
+    pub fn get_topic_name(&self) -> String {
+        self.reader.get_topic_name()
+    }
+
+    /// Get the most recent message, or `None` if no data has ever been seen.
+    pub fn get(&self) -> Option<Value> {
+        self.reader.get()
+    }
+
+    /// Pop and return the oldest message from the queue, waiting up to
+    /// `timeout` for a new message if the queue is empty.
+    pub async fn pop_front(&mut self, flush: bool, timeout: Duration) -> Option<Value> {
+        self.reader.pop_front(flush, timeout).await
+    }
+
+    /// Pop and return the newest message from the queue, waiting up to
+    /// `timeout` for a new message if the queue is empty.
+    pub async fn pop_back(&mut self, flush: bool, timeout: Duration) -> Option<Value> {
+        self.reader.pop_back(flush, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_bad_topic() {
+        let mut domain = domain::Domain::new();
+
+        assert!(SingleTopicReader::new(&mut domain, "Test", 1, "logevent_badTopic", 0).is_err())
+    }
+
+    #[test]
+    fn get_topic_name() {
+        let mut domain = domain::Domain::new();
+        let reader =
+            SingleTopicReader::new(&mut domain, "Test", 1, "logevent_heartbeat", 0).unwrap();
+
+        assert_eq!(reader.get_name(), "Test");
+        assert_eq!(reader.get_index(), 1);
+        assert_eq!(reader.get_topic_name(), "logevent_heartbeat");
+    }
+}
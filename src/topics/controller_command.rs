@@ -1,24 +1,43 @@
 //! Handles reading command topic and writing acknowledgements.
 
-use apache_avro::types::Value;
+use apache_avro::{from_value, types::Value};
 use log;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::{
+    controller::CommandAuthorizer,
     domain::Domain,
     error::errors::{SalObjError, SalObjResult},
+    generics::empty_topic::EmptyTopic,
     sal_info::SalInfo,
     topics::{base_sal_topic::BaseSALTopic, read_topic::ReadTopic, write_topic::WriteTopic},
     utils::{command_ack::CommandAck, types::WriteTopicResult},
 };
 
+#[cfg(feature = "tracing")]
+use crate::utils::command_span;
+
+/// Command received by [ControllerCommand::receive_command].
+pub(crate) enum ReceivedCommand {
+    /// Command allowed by the command authorizer, if any, with its data.
+    Authorized(Value),
+    /// Command rejected by the command authorizer, with the `CmdNoperm` ack
+    /// already published for it.
+    Rejected(CommandAck),
+}
+
 pub struct ControllerCommand<'a> {
     command_name: String,
+    #[cfg(feature = "tracing")]
+    component_name: String,
     command_reader: ReadTopic<'a>,
     ack_writer: WriteTopic<'a>,
     command_type: usize,
     origin: u32,
     identity: String,
+    sal_index: Option<i32>,
+    /// Hook deciding whether a received command may be executed.
+    authorizer: Option<CommandAuthorizer>,
 }
 
 impl<'a> ControllerCommand<'a> {
@@ -30,11 +49,15 @@ impl<'a> ControllerCommand<'a> {
         if let Some(command_type) = sal_info.get_command_type(command_name) {
             Ok(ControllerCommand {
                 command_name: command_name.to_owned(),
+                #[cfg(feature = "tracing")]
+                component_name: sal_info.get_name(),
                 command_reader: ReadTopic::new(command_name, sal_info, domain, 0),
                 ack_writer: WriteTopic::new("ackcmd", sal_info, domain),
                 command_type,
                 origin: domain.get_origin(),
                 identity: domain.get_identity(),
+                sal_index: sal_info.get_optional_index(),
+                authorizer: None,
             })
         } else {
             Err(SalObjError::new(&format!(
@@ -55,34 +78,186 @@ impl<'a> ControllerCommand<'a> {
         self.command_type as i64
     }
 
-    pub async fn process_command(&mut self) -> SalObjResult<Value> {
-        let start = Instant::now();
+    /// Is the command addressed to this controller?
+    ///
+    /// For indexed components only commands sent to the controller index are
+    /// accepted, unless the controller index is 0, which accepts commands
+    /// for any index. Non-indexed components accept all commands.
+    pub fn is_addressed_to_me(&self, cmd_data: &Value) -> bool {
+        ReadTopic::same_index(&self.sal_index, cmd_data)
+    }
 
-        log::trace!("process_command {} start", self.command_name);
-        if let Some(cmd_data) = self
-            .command_reader
-            .pop_front(false, std::time::Duration::from_millis(100))
+    /// Set the hook deciding whether a received command may be executed,
+    /// see [Controller::set_command_authorizer](crate::controller::Controller::set_command_authorizer).
+    pub(crate) fn set_authorizer(&mut self, authorizer: Option<CommandAuthorizer>) {
+        self.authorizer = authorizer;
+    }
+
+    /// Wait for the next command addressed to this controller.
+    ///
+    /// Commands rejected by the command authorizer are acknowledged with
+    /// `CmdNoperm` and returned as an error.
+    pub async fn process_command(&mut self) -> SalObjResult<Value> {
+        self.process_command_with_timeout(Duration::from_millis(100))
             .await
+    }
+
+    /// Same as [ControllerCommand::process_command] but waiting up to
+    /// `timeout` for a command to arrive.
+    pub async fn process_command_with_timeout(&mut self, timeout: Duration) -> SalObjResult<Value> {
+        match self.receive_command(timeout).await? {
+            Some(ReceivedCommand::Authorized(cmd_data)) => Ok(cmd_data),
+            Some(ReceivedCommand::Rejected(command_ack)) => {
+                Err(SalObjError::new(command_ack.get_result()))
+            }
+            None => Err(SalObjError::new("No command received.")),
+        }
+    }
+
+    /// Wait up to `timeout` for the next command addressed to this
+    /// controller and check it against the command authorizer.
+    ///
+    /// Return `None` if no command addressed to this controller arrived, and
+    /// an error if reading the command topic failed. Rejected commands are
+    /// acknowledged here, so they never reach a handler. With the `tracing`
+    /// feature this opens the `controller_command` span of the command,
+    /// closed by its final ack.
+    pub(crate) async fn receive_command(
+        &mut self,
+        timeout: Duration,
+    ) -> SalObjResult<Option<ReceivedCommand>> {
+        let cmd_data = match self.read_command(timeout).await? {
+            Some(cmd_data) => cmd_data,
+            None => return Ok(None),
+        };
+
+        #[cfg(feature = "tracing")]
         {
-            let duration = start.elapsed();
-            log::trace!(
-                "process_command {} finished took {duration:?} to take data.",
-                self.command_name
+            let command = from_value::<EmptyTopic>(&cmd_data).unwrap_or_default();
+            command_span::start_controller_command(
+                command_span::CommandKey::new(
+                    &self.component_name,
+                    self.get_command_type() as i32,
+                    command.get_private_seq_num(),
+                    command.get_private_origin(),
+                ),
+                &self.command_name,
             );
-            Ok(cmd_data)
-        } else {
-            log::trace!("process_command {} finished no data.", self.command_name);
-            Err(SalObjError::new("No command received."))
         }
+
+        let authorized = match &self.authorizer {
+            Some(authorizer) => authorizer(&self.command_name, &cmd_data),
+            None => true,
+        };
+        if authorized {
+            return Ok(Some(ReceivedCommand::Authorized(cmd_data)));
+        }
+
+        log::info!("Rejecting unauthorized {}.", self.command_name);
+        let command_ack = CommandAck::make_noperm(
+            from_value::<EmptyTopic>(&cmd_data).unwrap_or_default(),
+            &format!("Not authorized to execute {}.", self.command_name),
+        );
+        self.ack(command_ack.clone()).await?;
+        Ok(Some(ReceivedCommand::Rejected(command_ack)))
     }
 
+    /// Wait up to `timeout` for the next command addressed to this
+    /// controller.
+    ///
+    /// Return `None` if no command arrived or the command is for another
+    /// index.
+    async fn read_command(&mut self, timeout: Duration) -> SalObjResult<Option<Value>> {
+        let start = Instant::now();
+
+        log::trace!("process_command {} start", self.command_name);
+        match self.command_reader.try_pop_front(timeout).await? {
+            Some(cmd_data) => {
+                let duration = start.elapsed();
+                log::trace!(
+                    "process_command {} finished took {duration:?} to take data.",
+                    self.command_name
+                );
+                if self.is_addressed_to_me(&cmd_data) {
+                    Ok(Some(cmd_data))
+                } else {
+                    log::debug!(
+                        "process_command {} ignoring command for another index.",
+                        self.command_name
+                    );
+                    Ok(None)
+                }
+            }
+            None => {
+                log::trace!("process_command {} finished no data.", self.command_name);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Publish the acknowledgement for a command received by this topic.
+    ///
+    /// The `cmdtype` of the ack is set to the type of this command.
     pub async fn ack<'si>(&mut self, command_ack: CommandAck) -> WriteTopicResult {
+        let command_ack = command_ack.with_cmdtype(self.get_command_type() as i32);
         let ackcmd = command_ack
             .to_ackcmd()
             .with_timestamps()
             .with_private_origin(self.get_origin() as i32)
             .with_private_identity(&self.get_identity())
-            .with_private_seq_num(command_ack.get_seq_num());
-        self.ack_writer.write_typed(&ackcmd).await
+            .with_private_seq_num(command_ack.get_seq_num())
+            .with_sal_index(self.ack_writer.get_index());
+        self.ack_writer.set_seq_num(ackcmd.get_private_seq_num());
+        let result = self.ack_writer.write_typed(&ackcmd).await;
+        #[cfg(feature = "tracing")]
+        command_span::record_controller_ack(&self.component_name, &command_ack);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_command_data(sal_index: i32) -> Value {
+        Value::Record(vec![
+            ("salIndex".to_owned(), Value::Int(sal_index)),
+            (
+                "configurationOverride".to_owned(),
+                Value::String("".to_owned()),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_is_addressed_to_me() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 123).unwrap();
+        let controller_command =
+            ControllerCommand::new("command_start", &domain, &sal_info).unwrap();
+
+        assert!(controller_command.is_addressed_to_me(&make_command_data(123)));
+        assert!(!controller_command.is_addressed_to_me(&make_command_data(5)));
+    }
+
+    #[test]
+    fn test_is_addressed_to_me_wildcard() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 0).unwrap();
+        let controller_command =
+            ControllerCommand::new("command_start", &domain, &sal_info).unwrap();
+
+        assert!(controller_command.is_addressed_to_me(&make_command_data(123)));
+        assert!(controller_command.is_addressed_to_me(&make_command_data(5)));
+    }
+
+    #[test]
+    fn test_is_addressed_to_me_non_indexed() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Switch", 0).unwrap();
+        let controller_command =
+            ControllerCommand::new("command_standby", &domain, &sal_info).unwrap();
+
+        assert!(controller_command.is_addressed_to_me(&Value::Record(Vec::new())));
     }
 }
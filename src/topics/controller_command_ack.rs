@@ -1,6 +1,10 @@
 //! Handles reading command topic and writing acknowledgements.
 
-use tokio::{sync::mpsc, task};
+use tokio::{
+    sync::mpsc,
+    task,
+    time::{timeout, Duration},
+};
 
 use crate::{
     domain::Domain,
@@ -9,6 +13,9 @@ use crate::{
     utils::command_ack::CommandAck,
 };
 
+#[cfg(feature = "tracing")]
+use crate::utils::command_span;
+
 pub struct ControllerCommandAck {
     pub ack_sender: mpsc::Sender<CommandAck>,
     ack_task: task::JoinHandle<()>,
@@ -22,6 +29,8 @@ impl ControllerCommandAck {
         let sal_index: i32 = sal_info.get_index() as i32;
         let identity = domain.get_identity();
         let origin = domain.get_origin() as i32;
+        #[cfg(feature = "tracing")]
+        let component_name = sal_info.get_name();
 
         let ack_task = task::spawn(async move {
             while let Some(command_ack) = ack_receiver.recv().await {
@@ -37,6 +46,8 @@ impl ControllerCommandAck {
                 if let Err(error) = ack_writer.write_typed(&ackcmd).await {
                     log::error!("Failed to write ackcmd: {error}");
                 }
+                #[cfg(feature = "tracing")]
+                command_span::record_controller_ack(&component_name, &command_ack);
             }
         });
         ControllerCommandAck {
@@ -48,4 +59,22 @@ impl ControllerCommandAck {
     pub async fn is_finished(&self) -> bool {
         self.ack_task.is_finished()
     }
+
+    /// Stop accepting new acknowledgements and wait up to `wait_time` for
+    /// the pending ones to be written.
+    ///
+    /// Acknowledgements are only flushed once every clone of `ack_sender` is
+    /// dropped, otherwise the writing task is aborted after `wait_time`.
+    pub async fn close(self, wait_time: Duration) {
+        let ControllerCommandAck {
+            ack_sender,
+            mut ack_task,
+        } = self;
+        drop(ack_sender);
+
+        if timeout(wait_time, &mut ack_task).await.is_err() {
+            log::warn!("Pending acknowledgements not written after {wait_time:?}.");
+            ack_task.abort();
+        }
+    }
 }
@@ -10,5 +10,7 @@ pub mod controller_command_ack;
 pub mod read_topic;
 pub mod remote_command;
 pub mod topic;
+pub mod topic_enum;
 pub mod topic_info;
+pub mod topic_names;
 pub mod write_topic;
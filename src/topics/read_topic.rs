@@ -5,20 +5,91 @@ use crate::{
     error::errors::{SalObjError, SalObjResult},
     sal_info::SalInfo,
     topics::base_topic::BaseTopic,
+    utils::avro_utils::avro_to_json,
 };
-use apache_avro::types::Value;
+use apache_avro::{from_value, types::Value};
+use chrono::Utc;
 use kafka::{
-    consumer::{Consumer, FetchOffset, GroupOffsetStorage},
+    consumer::{Consumer, FetchOffset, GroupOffsetStorage, Message},
     error::Result as KafkaResult,
 };
 use schema_registry_converter::async_impl::avro::AvroDecoder;
-use std::collections::VecDeque;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::Arc,
+};
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 
 // Default value for the ``queue_len`` constructor argument.
 const DEFAULT_QUEUE_LEN: usize = 100;
 
-const POOL_WAIT_TIME: std::time::Duration = std::time::Duration::from_micros(50);
+/// Default minimum time to wait between polls that return no data.
+const POOL_MIN_WAIT_TIME: std::time::Duration = std::time::Duration::from_micros(50);
+
+/// Default maximum time to wait between polls that return no data.
+const POOL_MAX_WAIT_TIME: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// How long [ReadTopic::pop_front_cancellable] waits for data before
+/// checking the cancellation token again.
+const CANCEL_CHECK_TIME: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Adaptive wait time between polls of a topic.
+///
+/// The wait time starts at `min_wait` and doubles every time a poll returns
+/// no data, up to `max_wait`. It goes back to `min_wait` as soon as data
+/// arrives, so an idle reader does not busy-spin while a busy one stays
+/// responsive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolBackoff {
+    min_wait: Duration,
+    max_wait: Duration,
+    current_wait: Duration,
+}
+
+impl Default for PoolBackoff {
+    fn default() -> Self {
+        Self::new(POOL_MIN_WAIT_TIME, POOL_MAX_WAIT_TIME)
+    }
+}
+
+impl PoolBackoff {
+    pub fn new(min_wait: Duration, max_wait: Duration) -> PoolBackoff {
+        let max_wait = max_wait.max(min_wait);
+        PoolBackoff {
+            min_wait,
+            max_wait,
+            current_wait: min_wait,
+        }
+    }
+
+    /// Get the time to wait before the next poll and grow the wait time.
+    fn next_wait(&mut self) -> Duration {
+        let wait = self.current_wait;
+        self.current_wait = (self.current_wait * 2).min(self.max_wait);
+        wait
+    }
+
+    /// Go back to the minimum wait time.
+    fn reset(&mut self) {
+        self.current_wait = self.min_wait;
+    }
+}
+
+/// A data sample together with its position in the topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// The decoded data.
+    pub value: Value,
+    /// Offset of the message in the topic partition.
+    pub offset: i64,
+    /// Partition the message was read from.
+    pub partition: i32,
+    /// Id of the schema, in the schema registry, used to decode the message.
+    pub schema_id: u32,
+}
 
 /// Base struct for reading a topic.
 pub struct ReadTopic<'a> {
@@ -40,12 +111,31 @@ pub struct ReadTopic<'a> {
     max_history: usize,
     /// Sample of the last data seen.
     current_data: Option<Value>,
+    /// Last data seen for each `salIndex`.
+    latest_per_index: HashMap<i32, Value>,
     /// Data queue.
-    data_queue: VecDeque<Value>,
+    data_queue: VecDeque<Sample>,
     /// Topic consumer.
     consumer: KafkaResult<Consumer>,
-    decoder: AvroDecoder<'a>,
+    /// Avro decoder shared with the domain.
+    decoder: Arc<AvroDecoder<'static>>,
     sal_index: Option<i32>,
+    /// Have the historical samples been trimmed to `max_history`?
+    history_trimmed: bool,
+    /// Log-end offset of each partition when the reader started reading,
+    /// the end of the historical samples. Fetched on the first poll.
+    history_end_offsets: Option<HashMap<i32, i64>>,
+    /// Wait time strategy between polls that return no data.
+    pool_backoff: PoolBackoff,
+    /// Number of times the consumer was polled.
+    n_polls: usize,
+    /// Sort each polled batch by (partition, offset) before enqueuing it?
+    order_by_offset: bool,
+    /// Offset the consumer started from on each partition, used to compute
+    /// the lag of partitions nothing was consumed from yet. Fetched on the
+    /// first call to [ReadTopic::lag] or with the history offsets.
+    initial_offsets: Option<HashMap<i32, i64>>,
+    _lifetime: PhantomData<&'a ()>,
 }
 
 impl<'a> BaseTopic for ReadTopic<'a> {}
@@ -63,32 +153,61 @@ impl<'a> ReadTopic<'a> {
             )
         }
 
-        let fetch_offset = if max_history > 0 {
-            FetchOffset::Earliest
-        } else {
-            FetchOffset::Latest
-        };
+        let fetch_offset = ReadTopic::get_fallback_offset(max_history);
 
         let sal_index = sal_info.get_optional_index();
+        let topic_publish_name = sal_info.make_schema_registry_topic_name(topic_name);
+
+        let consumer = domain
+            .get_kafka_extra_config()
+            .apply_to_consumer(
+                Consumer::from_hosts(Domain::get_client_hosts())
+                    .with_topic(topic_publish_name.clone())
+                    .with_fallback_offset(fetch_offset)
+                    .with_group(format!("{}", domain.get_origin()))
+                    .with_client_id(sal_info.make_client_id(domain.get_origin()))
+                    .with_fetch_max_wait_time(Duration::from_millis(1))
+                    .with_offset_storage(GroupOffsetStorage::Kafka),
+            )
+            .create();
 
         ReadTopic {
             topic_name: topic_name.to_owned(),
-            topic_publish_name: sal_info.make_schema_registry_topic_name(topic_name),
+            topic_publish_name,
             max_history,
             data_queue: VecDeque::with_capacity(DEFAULT_QUEUE_LEN),
-            consumer: Consumer::from_hosts(Domain::get_client_hosts())
-                .with_topic(sal_info.make_schema_registry_topic_name(topic_name))
-                .with_fallback_offset(fetch_offset)
-                .with_group(format!("{}", domain.get_origin()))
-                .with_fetch_max_wait_time(Duration::from_millis(1))
-                .with_offset_storage(GroupOffsetStorage::Kafka)
-                .create(),
+            consumer,
             current_data: None,
-            decoder: SalInfo::make_decoder(),
+            latest_per_index: HashMap::new(),
+            decoder: domain.decoder(),
             sal_index,
+            history_trimmed: max_history == 0,
+            history_end_offsets: None,
+            pool_backoff: PoolBackoff::default(),
+            n_polls: 0,
+            order_by_offset: false,
+            initial_offsets: None,
+            _lifetime: PhantomData,
         }
     }
 
+    /// Set the minimum and maximum time to wait between polls that return
+    /// no data. See [PoolBackoff].
+    pub fn with_pool_wait_time(mut self, min_wait: Duration, max_wait: Duration) -> Self {
+        self.pool_backoff = PoolBackoff::new(min_wait, max_wait);
+        self
+    }
+
+    /// Enqueue the messages of each poll ordered by (partition, offset),
+    /// instead of in the order they were polled.
+    ///
+    /// This makes replays deterministic when a poll returns data from
+    /// several partitions.
+    pub fn with_order_by_offset(mut self, order_by_offset: bool) -> Self {
+        self.order_by_offset = order_by_offset;
+        self
+    }
+
     /// Get the name of the topic.
     pub fn get_topic_name(&self) -> String {
         self.topic_name.to_owned()
@@ -109,6 +228,40 @@ impl<'a> ReadTopic<'a> {
         self.current_data.is_some()
     }
 
+    /// Were the samples published before the reader started all read and
+    /// popped from the queue?
+    pub(crate) fn is_caught_up(&self) -> bool {
+        self.history_trimmed && self.data_queue.is_empty()
+    }
+
+    /// Check that the reader can switch to `sal_index`, without changing
+    /// it.
+    ///
+    /// Readers of all indices (`Some(0)`) can only keep one sample of
+    /// history.
+    pub(crate) fn check_sal_index(&self, sal_index: Option<i32>) -> SalObjResult<()> {
+        if sal_index == Some(0) && self.max_history > 1 {
+            return Err(SalObjError::new(&format!(
+                "max_history={} must be 0 or 1 for an indexed component with index=0.",
+                self.max_history
+            )));
+        }
+        Ok(())
+    }
+
+    /// Change the index whose data this reader returns.
+    ///
+    /// Topic names do not depend on the index, so the consumer is kept and
+    /// only the `salIndex` filter changes. Queued data and the current sample
+    /// belong to the previous index and are discarded.
+    pub(crate) fn set_sal_index(&mut self, sal_index: Option<i32>) -> SalObjResult<()> {
+        self.check_sal_index(sal_index)?;
+        self.sal_index = sal_index;
+        self.current_data = None;
+        self.flush();
+        Ok(())
+    }
+
     /// Flush the queue used by `get_oldest` and `next`.
     ///
     /// This makes `get_oldest` return `None` and `next` wait,
@@ -127,6 +280,15 @@ impl<'a> ReadTopic<'a> {
         self.current_data.to_owned()
     }
 
+    /// Get the most recent message seen for each `salIndex`.
+    ///
+    /// This is mostly useful for readers of indexed components with index 0,
+    /// which receive the interleaved data of all indices. The map is updated
+    /// every time new data is pooled, independently of the read queue.
+    pub fn get_latest_per_index(&self) -> HashMap<i32, Value> {
+        self.latest_per_index.clone()
+    }
+
     /// Pop and return the newest message from the queue, or `None` if the
     /// queue is empty.
     ///
@@ -134,6 +296,20 @@ impl<'a> ReadTopic<'a> {
     /// message. This method affects which message will be returned by `next`,
     /// but not which message will be returned by `aget` or `get`.
     pub async fn pop_back(&mut self, flush: bool, timeout: std::time::Duration) -> Option<Value> {
+        self.pop_back_sample(flush, timeout)
+            .await
+            .map(|sample| sample.value)
+    }
+
+    /// Same as [ReadTopic::pop_back] but return the data together with its
+    /// offset and partition in the topic.
+    ///
+    /// Offsets can be used to detect lost messages.
+    pub async fn pop_back_sample(
+        &mut self,
+        flush: bool,
+        timeout: std::time::Duration,
+    ) -> Option<Sample> {
         if flush {
             self.flush();
         }
@@ -150,6 +326,7 @@ impl<'a> ReadTopic<'a> {
                 log::warn!("Error pooling new data: {error}.");
             }
         }
+        self.trim_history();
         self.data_queue.pop_back()
     }
 
@@ -160,6 +337,163 @@ impl<'a> ReadTopic<'a> {
     /// This method affects the data returned by `get_oldest`, but not the data
     /// returned by `aget` or `get`.
     pub async fn pop_front(&mut self, flush: bool, timeout: std::time::Duration) -> Option<Value> {
+        self.pop_front_sample(flush, timeout)
+            .await
+            .map(|sample| sample.value)
+    }
+
+    /// Same as [ReadTopic::pop_front] but return `None` early if `cancel` is
+    /// triggered.
+    ///
+    /// The wait is split in slices of at most 50 ms and the token checked in
+    /// between, rather than dropping a poll half way through, so messages
+    /// already fetched from the broker are never lost; they stay in the queue
+    /// for the next read.
+    pub async fn pop_front_cancellable(
+        &mut self,
+        flush: bool,
+        timeout: std::time::Duration,
+        cancel: CancellationToken,
+    ) -> Option<Value> {
+        if flush {
+            self.flush();
+        }
+        let deadline = Instant::now() + timeout;
+
+        while !cancel.is_cancelled() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if let Some(value) = self
+                .pop_front(false, remaining.min(CANCEL_CHECK_TIME))
+                .await
+            {
+                return Some(value);
+            }
+            if remaining <= CANCEL_CHECK_TIME {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Same as [ReadTopic::pop_front] but deserialize the message into `T`.
+    ///
+    /// Return `Ok(None)` if no message arrives before the timeout.
+    pub async fn pop_front_typed<T>(
+        &mut self,
+        flush: bool,
+        timeout: std::time::Duration,
+    ) -> SalObjResult<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        match self.pop_front(flush, timeout).await {
+            Some(value) => Ok(Some(from_value::<T>(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Same as [ReadTopic::pop_front] but convert the message into json.
+    ///
+    /// This gives a generic view of any topic without defining a struct for
+    /// it. See [avro_to_json] for how the avro values are converted.
+    pub async fn pop_front_json(
+        &mut self,
+        flush: bool,
+        timeout: std::time::Duration,
+    ) -> Option<serde_json::Value> {
+        self.pop_front(flush, timeout)
+            .await
+            .map(|value| avro_to_json(&value))
+    }
+
+    /// Same as [ReadTopic::pop_front_typed] but return the raw message if it
+    /// cannot be deserialized into `T`.
+    ///
+    /// This is useful to debug mismatches between the topic schema and `T`.
+    pub async fn pop_front_typed_or_raw<T>(
+        &mut self,
+        flush: bool,
+        timeout: std::time::Duration,
+    ) -> Option<Result<T, Value>>
+    where
+        T: DeserializeOwned,
+    {
+        self.pop_front(flush, timeout)
+            .await
+            .map(ReadTopic::typed_or_raw)
+    }
+
+    /// Deserialize a message into `T`, returning the message if it fails.
+    fn typed_or_raw<T: DeserializeOwned>(value: Value) -> Result<T, Value> {
+        match from_value::<T>(&value) {
+            Ok(data) => Ok(data),
+            Err(error) => {
+                log::debug!("Failed to deserialize message: {error}.");
+                Err(value)
+            }
+        }
+    }
+
+    /// Collect up to `n` messages, oldest first, waiting up to `timeout` in
+    /// total.
+    ///
+    /// Return fewer than `n` messages if the timeout expires first.
+    pub async fn collect(&mut self, n: usize, timeout: std::time::Duration) -> Vec<Value> {
+        let deadline = Instant::now() + timeout;
+        let mut samples = Vec::with_capacity(n);
+
+        while samples.len() < n {
+            match self
+                .pop_front(false, deadline.saturating_duration_since(Instant::now()))
+                .await
+            {
+                Some(value) => samples.push(value),
+                None => break,
+            }
+        }
+
+        samples
+    }
+
+    /// Same as [ReadTopic::pop_front] but also return the id of the schema,
+    /// in the schema registry, used to decode the message.
+    ///
+    /// This is useful for debugging decoding problems.
+    pub async fn pop_front_with_schema_id(
+        &mut self,
+        flush: bool,
+        timeout: std::time::Duration,
+    ) -> Option<(Value, u32)> {
+        self.pop_front_sample(flush, timeout)
+            .await
+            .map(|sample| (sample.value, sample.schema_id))
+    }
+
+    /// Same as [ReadTopic::pop_front] but return an error if polling the
+    /// topic fails, instead of logging it and returning `None`.
+    ///
+    /// Samples decoded before the error stay in the queue for the next call.
+    pub async fn try_pop_front(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> SalObjResult<Option<Value>> {
+        if self.data_queue.is_empty() {
+            let pooled = self.pool(timeout).await;
+            self.trim_history();
+            pooled?;
+        }
+        Ok(self.data_queue.pop_front().map(|sample| sample.value))
+    }
+
+    /// Same as [ReadTopic::pop_front] but return the data together with its
+    /// offset and partition in the topic.
+    ///
+    /// Offsets can be used to detect lost messages.
+    pub async fn pop_front_sample(
+        &mut self,
+        flush: bool,
+        timeout: std::time::Duration,
+    ) -> Option<Sample> {
         if flush {
             self.flush();
         }
@@ -177,6 +511,7 @@ impl<'a> ReadTopic<'a> {
                     log::warn!("Error pooling new data: {error}.");
                 }
             }
+            self.trim_history();
         }
         self.data_queue.pop_front()
     }
@@ -191,6 +526,9 @@ impl<'a> ReadTopic<'a> {
     /// implementation will check the message offset and reset it to the head
     /// of the data queue, avoiding unnecessary reads.
     async fn pool(&mut self, timeout: std::time::Duration) -> SalObjResult<usize> {
+        if !self.history_trimmed && self.history_end_offsets.is_none() {
+            self.fetch_history_offsets();
+        }
         match &mut self.consumer {
             Ok(consumer) => {
                 let timer_task = tokio::spawn(async move {
@@ -198,49 +536,60 @@ impl<'a> ReadTopic<'a> {
                 });
 
                 let mut n_messages = 0;
+                let deadline = Instant::now() + timeout;
+                self.pool_backoff.reset();
 
                 while !timer_task.is_finished() {
                     let start = Instant::now();
+                    self.n_polls += 1;
                     match consumer.poll() {
                         Ok(messages) => {
                             let duration = start.elapsed();
 
                             let no_data = messages.is_empty();
                             log::trace!(
-                                "pool {} took {duration:?} to consume data, is empty? {no_data}.",
-                                self.topic_name
+                                "pool {} took {duration:?} to consume data, is empty? {no_data}. \
+                                Poll count: {}.",
+                                self.topic_name,
+                                self.n_polls,
                             );
-                            for ms in messages.iter() {
-                                for m in ms.messages() {
-                                    let start = Instant::now();
-                                    match self.decoder.decode(Some(m.value)).await {
-                                        Ok(data) => {
-                                            let duration = start.elapsed();
-                                            log::trace!(
-                                                "pool {} took {duration:?} to decode data.",
-                                                self.topic_name
-                                            );
-                                            let data_value = data.value;
-                                            if !ReadTopic::same_index(&self.sal_index, &data_value)
-                                            {
-                                                continue;
-                                            }
-                                            self.current_data = Some(data_value.clone());
-                                            self.data_queue.push_back(data_value);
-                                            n_messages += 1;
-                                        }
-                                        Err(error) => return Err(SalObjError::from_error(error)),
-                                    };
-                                }
-                                if let Err(error) = consumer.consume_messageset(ms) {
-                                    return Err(SalObjError::from_error(error));
-                                }
+                            let mut message_sets: Vec<_> = messages.iter().collect();
+                            if self.order_by_offset {
+                                message_sets.sort_by_key(|ms| ms.partition());
+                            }
+                            for ms in message_sets {
+                                let decoded = ReadTopic::decode_message_set(
+                                    &self.decoder,
+                                    &self.topic_name,
+                                    &self.sal_index,
+                                    ms.partition(),
+                                    ms.messages(),
+                                )
+                                .await;
+                                n_messages += ReadTopic::enqueue_message_set(
+                                    &mut self.data_queue,
+                                    &mut self.current_data,
+                                    &mut self.latest_per_index,
+                                    decoded,
+                                    self.order_by_offset,
+                                    || {
+                                        consumer
+                                            .consume_messageset(ms)
+                                            .map_err(SalObjError::from_error)
+                                    },
+                                )?;
                             }
                             if n_messages > 0 && no_data {
                                 timer_task.abort();
                                 return Ok(n_messages);
                             }
-                            sleep(POOL_WAIT_TIME).await;
+                            if no_data {
+                                let wait = self.pool_backoff.next_wait();
+                                sleep(wait.min(deadline.saturating_duration_since(Instant::now())))
+                                    .await;
+                            } else {
+                                self.pool_backoff.reset();
+                            }
                         }
                         Err(error) => {
                             return Err(SalObjError::from_error(error));
@@ -253,29 +602,339 @@ impl<'a> ReadTopic<'a> {
         }
     }
 
-    fn same_index(sal_index: &Option<i32>, data_value: &Value) -> bool {
-        if let Some(sal_index) = sal_index {
-            if let Value::Record(data_record) = &data_value {
-                let data_sal_index: Vec<&Value> = data_record
-                    .iter()
-                    .filter_map(|(field, value)| {
-                        if field == "salIndex" {
-                            Some(value)
-                        } else {
-                            None
-                        }
+    /// Get the consumer lag, the number of messages published to the topic
+    /// that were not consumed yet, summed over all partitions.
+    ///
+    /// Messages already consumed but still in the data queue do not count.
+    /// This takes `&mut self` because fetching the log-end offsets goes
+    /// through the kafka client held by the consumer.
+    ///
+    /// The offsets the consumer started from are fetched on the first call,
+    /// or the first poll if `max_history > 0`, so for readers with
+    /// `max_history=0` messages published before then on partitions nothing
+    /// was consumed from are not counted.
+    pub fn lag(&mut self) -> SalObjResult<i64> {
+        match &mut self.consumer {
+            Ok(consumer) => {
+                let initial_offsets = match &self.initial_offsets {
+                    Some(initial_offsets) => initial_offsets,
+                    None => self.initial_offsets.insert(ReadTopic::fetch_offsets(
+                        consumer,
+                        &self.topic_publish_name,
+                        ReadTopic::get_fallback_offset(self.max_history),
+                    )?),
+                };
+                let end_offsets = ReadTopic::fetch_offsets(
+                    consumer,
+                    &self.topic_publish_name,
+                    FetchOffset::Latest,
+                )?;
+
+                Ok(end_offsets
+                    .into_iter()
+                    .map(|(partition, end_offset)| {
+                        let position = consumer
+                            .last_consumed_message(&self.topic_publish_name, partition)
+                            .map(|offset| offset + 1)
+                            .or_else(|| initial_offsets.get(&partition).copied())
+                            .unwrap_or(end_offset);
+                        (end_offset - position).max(0)
                     })
-                    .collect();
-                if let Some(Value::Int(data_sal_index)) = data_sal_index.get(0) {
-                    sal_index == data_sal_index
-                } else {
-                    false
+                    .sum())
+            }
+            Err(error) => Err(SalObjError::new(&error.to_string())),
+        }
+    }
+
+    /// Get the offset to read from on partitions without a committed offset;
+    /// historical data is only read if `max_history > 0`.
+    fn get_fallback_offset(max_history: usize) -> FetchOffset {
+        if max_history > 0 {
+            FetchOffset::Earliest
+        } else {
+            FetchOffset::Latest
+        }
+    }
+
+    /// Fetch the offsets of all partitions of a topic.
+    fn fetch_offsets(
+        consumer: &mut Consumer,
+        topic: &str,
+        offset: FetchOffset,
+    ) -> SalObjResult<HashMap<i32, i64>> {
+        consumer
+            .client_mut()
+            .fetch_topic_offsets(topic, offset)
+            .map(|offsets| {
+                offsets
+                    .into_iter()
+                    .map(|partition_offset| (partition_offset.partition, partition_offset.offset))
+                    .collect()
+            })
+            .map_err(SalObjError::from_error)
+    }
+
+    /// Store the last data seen, overall and for its `salIndex`.
+    fn store_current_data(
+        current_data: &mut Option<Value>,
+        latest_per_index: &mut HashMap<i32, Value>,
+        data_value: &Value,
+    ) {
+        if let Some(sal_index) = ReadTopic::get_sal_index(data_value) {
+            latest_per_index.insert(sal_index, data_value.clone());
+        }
+        *current_data = Some(data_value.clone());
+    }
+
+    /// Push the samples of a message set into the data queue, updating the
+    /// current data as they go.
+    ///
+    /// If `order_by_offset` is set, the samples are first sorted by
+    /// (partition, offset). This is an associated function, rather than a
+    /// method, so it can be called while the consumer is borrowed.
+    fn enqueue_batch(
+        data_queue: &mut VecDeque<Sample>,
+        current_data: &mut Option<Value>,
+        latest_per_index: &mut HashMap<i32, Value>,
+        mut batch: Vec<Sample>,
+        order_by_offset: bool,
+    ) {
+        if order_by_offset {
+            batch.sort_by_key(|sample| (sample.partition, sample.offset));
+        }
+        for sample in batch {
+            ReadTopic::store_current_data(current_data, latest_per_index, &sample.value);
+            data_queue.push_back(sample);
+        }
+    }
+
+    /// Decode the messages of a message set, skipping those for another
+    /// index.
+    ///
+    /// Decoding stops at the first message that fails to decode; the samples
+    /// decoded before it are returned together with the error.
+    async fn decode_message_set(
+        decoder: &AvroDecoder<'static>,
+        topic_name: &str,
+        sal_index: &Option<i32>,
+        partition: i32,
+        messages: &[Message<'_>],
+    ) -> (Vec<Sample>, SalObjResult<()>) {
+        let mut batch = Vec::with_capacity(messages.len());
+        for m in messages {
+            let start = Instant::now();
+            match decoder.decode(Some(m.value)).await {
+                Ok(data) => {
+                    let duration = start.elapsed();
+                    log::trace!("pool {topic_name} took {duration:?} to decode data.");
+                    if !ReadTopic::same_index(sal_index, &data.value) {
+                        continue;
+                    }
+                    let data_value = ReadTopic::with_rcv_stamp(
+                        data.value,
+                        Utc::now().timestamp_micros() as f64 * 1e-6,
+                    );
+                    batch.push(Sample {
+                        value: data_value,
+                        offset: m.offset,
+                        partition,
+                        schema_id: ReadTopic::get_schema_id(m.value).unwrap_or_default(),
+                    });
                 }
-            } else {
-                false
+                Err(error) => return (batch, Err(SalObjError::from_error(error))),
+            }
+        }
+        (batch, Ok(()))
+    }
+
+    /// Enqueue the samples decoded from a message set, then mark the set as
+    /// consumed with `consume`.
+    ///
+    /// The samples are enqueued before anything else can fail, so an error
+    /// decoding or consuming a later message set of the same poll does not
+    /// drop them. Return the number of samples enqueued, or the decode or
+    /// consume error.
+    fn enqueue_message_set(
+        data_queue: &mut VecDeque<Sample>,
+        current_data: &mut Option<Value>,
+        latest_per_index: &mut HashMap<i32, Value>,
+        (batch, decoded): (Vec<Sample>, SalObjResult<()>),
+        order_by_offset: bool,
+        consume: impl FnOnce() -> SalObjResult<()>,
+    ) -> SalObjResult<usize> {
+        let n_samples = batch.len();
+        ReadTopic::enqueue_batch(
+            data_queue,
+            current_data,
+            latest_per_index,
+            batch,
+            order_by_offset,
+        );
+        decoded?;
+        consume()?;
+        Ok(n_samples)
+    }
+
+    /// Fetch the offsets delimiting the historical samples, see
+    /// [ReadTopic::trim_history].
+    fn fetch_history_offsets(&mut self) {
+        if let Ok(consumer) = &mut self.consumer {
+            self.history_end_offsets =
+                ReadTopic::fetch_offsets(consumer, &self.topic_publish_name, FetchOffset::Latest)
+                    .ok();
+            if self.initial_offsets.is_none() {
+                self.initial_offsets = ReadTopic::fetch_offsets(
+                    consumer,
+                    &self.topic_publish_name,
+                    ReadTopic::get_fallback_offset(self.max_history),
+                )
+                .ok();
+            }
+        }
+    }
+
+    /// Drop historical samples in excess of `max_history`.
+    ///
+    /// When the reader starts with `max_history > 0` it reads all the data
+    /// available in the topic. Until that backlog is read, which may take
+    /// many polls, only the `max_history` most recent samples for each index
+    /// are kept in the queue. If the end of the backlog is unknown only the
+    /// first read is trimmed.
+    fn trim_history(&mut self) {
+        if self.history_trimmed {
+            return;
+        }
+
+        if !self.data_queue.is_empty() {
+            self.trim_queue();
+        }
+
+        self.history_trimmed = match &self.history_end_offsets {
+            Some(end_offsets) => {
+                ReadTopic::is_history_read(end_offsets, &self.get_consumer_positions())
             }
+            None => !self.data_queue.is_empty(),
+        };
+    }
+
+    /// Were all the historical samples read?
+    ///
+    /// `positions` holds the offset of the next message to read in each
+    /// partition; partitions without one are read from offset 0.
+    fn is_history_read(end_offsets: &HashMap<i32, i64>, positions: &HashMap<i32, i64>) -> bool {
+        end_offsets.iter().all(|(partition, end_offset)| {
+            positions.get(partition).copied().unwrap_or(0) >= *end_offset
+        })
+    }
+
+    /// Get the offset of the next message to read in each partition, if
+    /// known.
+    fn get_consumer_positions(&self) -> HashMap<i32, i64> {
+        let initial_offsets = self.initial_offsets.clone().unwrap_or_default();
+        match &self.consumer {
+            Ok(consumer) => self
+                .history_end_offsets
+                .iter()
+                .flat_map(|end_offsets| end_offsets.keys())
+                .filter_map(|partition| {
+                    consumer
+                        .last_consumed_message(&self.topic_publish_name, *partition)
+                        .map(|offset| offset + 1)
+                        .or_else(|| initial_offsets.get(partition).copied())
+                        .map(|position| (*partition, position))
+                })
+                .collect(),
+            Err(_) => initial_offsets,
+        }
+    }
+
+    /// Keep only the `max_history` most recent samples for each index.
+    fn trim_queue(&mut self) {
+        let mut samples_per_index: HashMap<Option<i32>, usize> = HashMap::new();
+        let mut trimmed_queue = VecDeque::with_capacity(DEFAULT_QUEUE_LEN);
+
+        for sample in self.data_queue.drain(..).rev() {
+            let n_samples = samples_per_index
+                .entry(ReadTopic::get_sal_index(&sample.value))
+                .or_insert(0);
+            if *n_samples < self.max_history {
+                *n_samples += 1;
+                trimmed_queue.push_front(sample);
+            }
+        }
+        self.data_queue = trimmed_queue;
+    }
+
+    /// Get the value of the `salIndex` field of a data sample, if any.
+    pub(crate) fn get_sal_index(data_value: &Value) -> Option<i32> {
+        if let Value::Record(data_record) = data_value {
+            data_record.iter().find_map(|(field, value)| {
+                match (field.as_str(), ReadTopic::unwrap_union(value)) {
+                    ("salIndex", Value::Int(sal_index)) => Some(*sal_index),
+                    _ => None,
+                }
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Unwrap the value held by a (possibly nested) union.
+    ///
+    /// Optional fields are encoded as unions (e.g. `["null", "double"]`),
+    /// so a value read from a topic may come wrapped in `Value::Union`
+    /// even if the field is a plain scalar.
+    pub(crate) fn unwrap_union(value: &Value) -> &Value {
+        match value {
+            Value::Union(_, inner) => ReadTopic::unwrap_union(inner),
+            value => value,
+        }
+    }
+
+    /// Set the `private_rcvStamp` field of a decoded message.
+    ///
+    /// The kafka client does not expose the message timestamp, so the time
+    /// the message was received is used instead.
+    fn with_rcv_stamp(value: Value, rcv_stamp: f64) -> Value {
+        match value {
+            Value::Record(fields) => Value::Record(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| match (name.as_str(), value) {
+                        ("private_rcvStamp", Value::Union(index, _)) => (
+                            name,
+                            Value::Union(index, Box::new(Value::Double(rcv_stamp))),
+                        ),
+                        ("private_rcvStamp", _) => (name, Value::Double(rcv_stamp)),
+                        (_, value) => (name, value),
+                    })
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    /// Get the schema registry id from the header of an encoded message.
+    ///
+    /// Messages encoded with the schema registry start with a magic byte
+    /// (0) followed by the schema id as a 4 bytes big-endian integer.
+    fn get_schema_id(bytes: &[u8]) -> Option<u32> {
+        if bytes.len() > 4 && bytes[0] == 0 {
+            Some(u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]))
         } else {
-            false
+            None
+        }
+    }
+
+    /// Does the data sample belong to the given index?
+    ///
+    /// An index of 0 acts as a wildcard and matches samples for any index.
+    /// Samples of non-indexed components, `None`, have no `salIndex` and
+    /// always match.
+    pub(crate) fn same_index(sal_index: &Option<i32>, data_value: &Value) -> bool {
+        match sal_index {
+            Some(0) | None => true,
+            Some(sal_index) => ReadTopic::get_sal_index(data_value) == Some(*sal_index),
         }
     }
 }
@@ -283,6 +942,11 @@ impl<'a> ReadTopic<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        csc::test_csc::topics::scalars::Scalars,
+        topics::{base_sal_topic::BaseSALTopic, write_topic::WriteTopic},
+    };
+    use apache_avro::to_value;
 
     #[test]
     #[should_panic(
@@ -323,4 +987,536 @@ mod tests {
         // There's no data in the queue.
         assert!(!read_topic.has_data());
     }
+
+    fn make_scalars_sample(sal_index: i32, int0: i32) -> Sample {
+        Sample {
+            value: Value::Record(vec![
+                ("salIndex".to_owned(), Value::Int(sal_index)),
+                ("int0".to_owned(), Value::Int(int0)),
+            ]),
+            offset: int0 as i64,
+            partition: 0,
+            schema_id: 1,
+        }
+    }
+
+    #[test]
+    fn store_current_data_per_index() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 0).unwrap();
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 0);
+
+        for (sal_index, int0) in [(1, 0), (2, 1), (1, 2)] {
+            ReadTopic::store_current_data(
+                &mut read_topic.current_data,
+                &mut read_topic.latest_per_index,
+                &make_scalars_sample(sal_index, int0).value,
+            );
+        }
+
+        let latest_per_index = read_topic.get_latest_per_index();
+
+        assert_eq!(latest_per_index.len(), 2);
+        assert_eq!(latest_per_index[&1], make_scalars_sample(1, 2).value);
+        assert_eq!(latest_per_index[&2], make_scalars_sample(2, 1).value);
+        assert_eq!(read_topic.get(), Some(make_scalars_sample(1, 2).value));
+    }
+
+    #[test]
+    fn enqueue_batch_order_by_offset() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let mut read_topic =
+            ReadTopic::new("scalars", &sal_info, &domain, 0).with_order_by_offset(true);
+
+        let make_batch = |samples: &[(i32, i32)]| -> Vec<Sample> {
+            samples
+                .iter()
+                .map(|(partition, int0)| Sample {
+                    partition: *partition,
+                    ..make_scalars_sample(1, *int0)
+                })
+                .collect()
+        };
+
+        for batch in [
+            make_batch(&[(1, 4), (0, 2), (0, 1), (1, 3)]),
+            make_batch(&[(0, 6), (0, 5)]),
+        ] {
+            ReadTopic::enqueue_batch(
+                &mut read_topic.data_queue,
+                &mut read_topic.current_data,
+                &mut read_topic.latest_per_index,
+                batch,
+                read_topic.order_by_offset,
+            );
+        }
+
+        let positions: Vec<(i32, i64)> = read_topic
+            .data_queue
+            .iter()
+            .map(|sample| (sample.partition, sample.offset))
+            .collect();
+
+        assert_eq!(
+            positions,
+            vec![(0, 1), (0, 2), (1, 3), (1, 4), (0, 5), (0, 6)]
+        );
+        assert_eq!(read_topic.get(), Some(make_scalars_sample(1, 6).value));
+    }
+
+    #[test]
+    fn enqueue_batch_poll_order() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 0);
+
+        ReadTopic::enqueue_batch(
+            &mut read_topic.data_queue,
+            &mut read_topic.current_data,
+            &mut read_topic.latest_per_index,
+            vec![make_scalars_sample(1, 2), make_scalars_sample(1, 1)],
+            read_topic.order_by_offset,
+        );
+
+        let offsets: Vec<i64> = read_topic
+            .data_queue
+            .iter()
+            .map(|sample| sample.offset)
+            .collect();
+
+        assert_eq!(offsets, vec![2, 1]);
+    }
+
+    #[test]
+    fn enqueue_message_set_keeps_samples_on_error() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 0);
+        let mut consumed = Vec::new();
+
+        let n_samples = ReadTopic::enqueue_message_set(
+            &mut read_topic.data_queue,
+            &mut read_topic.current_data,
+            &mut read_topic.latest_per_index,
+            (
+                vec![make_scalars_sample(1, 1), make_scalars_sample(1, 2)],
+                Ok(()),
+            ),
+            read_topic.order_by_offset,
+            || {
+                consumed.push(1);
+                Ok(())
+            },
+        );
+        assert_eq!(n_samples.unwrap(), 2);
+
+        let failed_decode = ReadTopic::enqueue_message_set(
+            &mut read_topic.data_queue,
+            &mut read_topic.current_data,
+            &mut read_topic.latest_per_index,
+            (
+                vec![make_scalars_sample(1, 3)],
+                Err(SalObjError::new("Failed to decode.")),
+            ),
+            read_topic.order_by_offset,
+            || {
+                consumed.push(2);
+                Ok(())
+            },
+        );
+        assert!(failed_decode.is_err());
+
+        let failed_consume = ReadTopic::enqueue_message_set(
+            &mut read_topic.data_queue,
+            &mut read_topic.current_data,
+            &mut read_topic.latest_per_index,
+            (vec![make_scalars_sample(1, 4)], Ok(())),
+            read_topic.order_by_offset,
+            || Err(SalObjError::new("Failed to consume.")),
+        );
+        assert!(failed_consume.is_err());
+
+        let offsets: Vec<i64> = read_topic
+            .data_queue
+            .iter()
+            .map(|sample| sample.offset)
+            .collect();
+
+        assert_eq!(offsets, vec![1, 2, 3, 4]);
+        assert_eq!(consumed, vec![1]);
+        assert_eq!(read_topic.get(), Some(make_scalars_sample(1, 4).value));
+    }
+
+    #[tokio::test]
+    async fn get_latest_per_index() {
+        let mut domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 0).unwrap();
+
+        domain.register_topics(&sal_info.get_topics_name()).unwrap();
+        sal_info.register_schema().await;
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 0);
+
+        let mut expected_int0 = HashMap::new();
+        for (sal_index, int0) in [(1, 10), (2, 20), (1, 11)] {
+            let sal_info = SalInfo::new("Test", sal_index).unwrap();
+            let mut write_topic = WriteTopic::new("scalars", &sal_info, &domain);
+
+            let mut scalars = Scalars::default()
+                .with_sal_index(write_topic.get_index())
+                .with_private_seq_num(write_topic.get_seq_num());
+            scalars.int0 = int0;
+            write_topic.write_typed(&scalars).await.unwrap();
+
+            expected_int0.insert(sal_index as i32, int0);
+        }
+
+        assert_eq!(read_topic.collect(3, Duration::from_secs(5)).await.len(), 3);
+
+        let latest_per_index = read_topic.get_latest_per_index();
+
+        for (sal_index, int0) in expected_int0 {
+            let scalars = from_value::<Scalars>(&latest_per_index[&sal_index]).unwrap();
+            assert_eq!(scalars.int0, int0);
+        }
+    }
+
+    #[test]
+    fn trim_history() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 1);
+
+        for int0 in 0..50 {
+            read_topic
+                .data_queue
+                .push_back(make_scalars_sample(1, int0));
+        }
+        read_topic.trim_history();
+
+        assert_eq!(read_topic.data_queue.len(), 1);
+        assert_eq!(read_topic.data_queue[0], make_scalars_sample(1, 49));
+
+        // Only the first read is trimmed.
+        read_topic.data_queue.push_back(make_scalars_sample(1, 50));
+        read_topic.trim_history();
+
+        assert_eq!(read_topic.data_queue.len(), 2);
+    }
+
+    #[test]
+    fn trim_history_per_index() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 0).unwrap();
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 1);
+
+        for int0 in 0..50 {
+            read_topic
+                .data_queue
+                .push_back(make_scalars_sample(int0 % 2 + 1, int0));
+        }
+        read_topic.trim_history();
+
+        assert_eq!(read_topic.data_queue.len(), 2);
+        assert_eq!(read_topic.data_queue[0], make_scalars_sample(1, 48));
+        assert_eq!(read_topic.data_queue[1], make_scalars_sample(2, 49));
+    }
+
+    #[test]
+    fn trim_history_until_end_offsets() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 1);
+        read_topic.history_end_offsets = Some(HashMap::from([(0, 100)]));
+
+        // The backlog arrives over several reads, each one is trimmed.
+        for batch in [0..50, 50..60] {
+            for int0 in batch {
+                read_topic
+                    .data_queue
+                    .push_back(make_scalars_sample(1, int0));
+            }
+            read_topic.trim_history();
+
+            assert_eq!(read_topic.data_queue.len(), 1);
+            assert!(!read_topic.history_trimmed);
+        }
+        assert_eq!(read_topic.data_queue[0], make_scalars_sample(1, 59));
+    }
+
+    #[test]
+    fn is_history_read() {
+        let end_offsets = HashMap::from([(0, 100), (1, 0)]);
+
+        assert!(!ReadTopic::is_history_read(
+            &end_offsets,
+            &HashMap::from([(0, 50)])
+        ));
+        assert!(ReadTopic::is_history_read(
+            &end_offsets,
+            &HashMap::from([(0, 100)])
+        ));
+    }
+
+    #[test]
+    fn same_index_union_sal_index() {
+        let data_value = Value::Record(vec![(
+            "salIndex".to_owned(),
+            Value::Union(0, Box::new(Value::Int(5))),
+        )]);
+
+        assert!(ReadTopic::same_index(&Some(5), &data_value));
+        assert!(ReadTopic::same_index(&Some(0), &data_value));
+        assert!(!ReadTopic::same_index(&Some(6), &data_value));
+    }
+
+    #[test]
+    fn same_index_not_indexed() {
+        let data_value = Value::Record(vec![("heartbeat".to_owned(), Value::Boolean(true))]);
+
+        assert!(ReadTopic::same_index(&None, &data_value));
+    }
+
+    #[test]
+    fn unwrap_union() {
+        assert_eq!(
+            ReadTopic::unwrap_union(&Value::Union(1, Box::new(Value::Double(1.5)))),
+            &Value::Double(1.5)
+        );
+        assert_eq!(ReadTopic::unwrap_union(&Value::Int(3)), &Value::Int(3));
+    }
+
+    #[test]
+    fn pool_backoff() {
+        let mut pool_backoff = PoolBackoff::new(Duration::from_millis(1), Duration::from_millis(5));
+
+        assert_eq!(pool_backoff.next_wait(), Duration::from_millis(1));
+        assert_eq!(pool_backoff.next_wait(), Duration::from_millis(2));
+        assert_eq!(pool_backoff.next_wait(), Duration::from_millis(4));
+        assert_eq!(pool_backoff.next_wait(), Duration::from_millis(5));
+        assert_eq!(pool_backoff.next_wait(), Duration::from_millis(5));
+
+        pool_backoff.reset();
+
+        assert_eq!(pool_backoff.next_wait(), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn pool_backoff_idle_poll_count() {
+        let mut pool_backoff = PoolBackoff::default();
+        let interval = Duration::from_secs(1);
+
+        let mut elapsed = Duration::ZERO;
+        let mut n_polls = 0;
+        while elapsed < interval {
+            elapsed += pool_backoff.next_wait();
+            n_polls += 1;
+        }
+
+        // Once the backoff saturates an idle reader polls at most once
+        // every POOL_MAX_WAIT_TIME.
+        assert!(n_polls <= 2 * (interval.as_millis() / POOL_MAX_WAIT_TIME.as_millis()) as usize);
+    }
+
+    #[tokio::test]
+    async fn pool_idle_reader_poll_count() {
+        let mut domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        domain.register_topics(&sal_info.get_topics_name()).unwrap();
+
+        let mut read_topic = ReadTopic::new("logevent_arrays", &sal_info, &domain, 0)
+            .with_pool_wait_time(Duration::from_micros(50), Duration::from_millis(20));
+
+        let data = read_topic.pop_front(false, Duration::from_secs(1)).await;
+
+        assert!(data.is_none());
+        assert!(
+            read_topic.n_polls <= 100,
+            "Idle reader polled {} times.",
+            read_topic.n_polls
+        );
+    }
+
+    #[test]
+    fn typed_or_raw() {
+        let value = to_value(Scalars::default().with_sal_index(1)).unwrap();
+
+        let scalars = ReadTopic::typed_or_raw::<Scalars>(value).unwrap();
+
+        assert_eq!(scalars.get_sal_index(), 1);
+    }
+
+    #[test]
+    fn typed_or_raw_type_mismatch() {
+        let value = match to_value(Scalars::default()).unwrap() {
+            Value::Record(fields) => Value::Record(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| match name.as_str() {
+                        "int0" => (name, Value::String("not an int".to_owned())),
+                        _ => (name, value),
+                    })
+                    .collect(),
+            ),
+            value => panic!("Scalars should be a record, got {value:?}."),
+        };
+
+        assert_eq!(
+            ReadTopic::typed_or_raw::<Scalars>(value.clone()).unwrap_err(),
+            value
+        );
+    }
+
+    #[test]
+    fn with_rcv_stamp() {
+        let value = Value::Record(vec![
+            ("private_rcvStamp".to_owned(), Value::Double(0.0)),
+            ("double0".to_owned(), Value::Double(0.0)),
+        ]);
+
+        assert_eq!(
+            ReadTopic::with_rcv_stamp(value, 1.5),
+            Value::Record(vec![
+                ("private_rcvStamp".to_owned(), Value::Double(1.5)),
+                ("double0".to_owned(), Value::Double(0.0)),
+            ])
+        );
+    }
+
+    /// Make a reader and a writer of the `scalars` topic of `Test:1`, with
+    /// the topics and schemas registered in the broker.
+    async fn make_scalars_reader_writer() -> (ReadTopic<'static>, WriteTopic<'static>) {
+        let mut domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        domain.register_topics(&sal_info.get_topics_name()).unwrap();
+        sal_info.register_schema().await;
+
+        (
+            ReadTopic::new("scalars", &sal_info, &domain, 0),
+            WriteTopic::new("scalars", &sal_info, &domain),
+        )
+    }
+
+    /// Write `n_samples` default `scalars` samples.
+    async fn write_scalars(write_topic: &mut WriteTopic<'_>, n_samples: usize) {
+        for _ in 0..n_samples {
+            let scalars = Scalars::default()
+                .with_sal_index(write_topic.get_index())
+                .with_private_seq_num(write_topic.get_seq_num());
+            write_topic.write_typed(&scalars).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn pop_front_rcv_stamp() {
+        let (mut read_topic, mut write_topic) = make_scalars_reader_writer().await;
+
+        write_scalars(&mut write_topic, 1).await;
+
+        let scalars = read_topic
+            .pop_front_typed::<Scalars>(false, Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(scalars.get_private_rcv_stamp() > 0.0);
+    }
+
+    #[test]
+    fn get_schema_id() {
+        assert_eq!(ReadTopic::get_schema_id(&[0, 0, 0, 1, 2, 10]), Some(258));
+        assert_eq!(ReadTopic::get_schema_id(&[1, 0, 0, 1, 2, 10]), None);
+        assert_eq!(ReadTopic::get_schema_id(&[0, 0, 0]), None);
+    }
+
+    #[tokio::test]
+    async fn pop_front_with_schema_id() {
+        let (mut read_topic, mut write_topic) = make_scalars_reader_writer().await;
+
+        write_scalars(&mut write_topic, 1).await;
+
+        let (data, schema_id) = read_topic
+            .pop_front_with_schema_id(false, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert!(matches!(data, Value::Record(_)));
+        assert!(schema_id > 0);
+    }
+
+    #[tokio::test]
+    async fn pop_front_sample() {
+        let (mut read_topic, mut write_topic) = make_scalars_reader_writer().await;
+
+        write_scalars(&mut write_topic, 2).await;
+
+        let first_sample = read_topic
+            .pop_front_sample(false, Duration::from_secs(5))
+            .await
+            .unwrap();
+        let second_sample = read_topic
+            .pop_front_sample(false, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(first_sample.partition, second_sample.partition);
+        assert!(second_sample.offset > first_sample.offset);
+    }
+
+    #[tokio::test]
+    async fn pop_front_cancellable() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let mut read_topic = ReadTopic::new("scalars", &sal_info, &domain, 0);
+        let cancel = CancellationToken::new();
+
+        let cancel_task = tokio::spawn({
+            let cancel = cancel.clone();
+            async move {
+                sleep(Duration::from_millis(100)).await;
+                cancel.cancel();
+            }
+        });
+
+        let start = Instant::now();
+        let data = read_topic
+            .pop_front_cancellable(false, Duration::from_secs(10), cancel)
+            .await;
+
+        cancel_task.await.unwrap();
+
+        assert!(data.is_none());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn lag_decreases_when_consumed() {
+        let (mut read_topic, mut write_topic) = make_scalars_reader_writer().await;
+
+        // The first call fetches the offsets the reader starts from.
+        read_topic.lag().unwrap();
+
+        write_scalars(&mut write_topic, 3).await;
+
+        let initial_lag = read_topic.lag().unwrap();
+
+        read_topic
+            .pop_front(false, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert!(initial_lag >= 3);
+        assert!(read_topic.lag().unwrap() < initial_lag);
+    }
 }
@@ -3,34 +3,100 @@
 
 use crate::{
     domain::Domain,
+    error::errors::{SalObjError, SalObjResult},
+    generics::ackcmd::AckCmdRecord,
     sal_enums,
     sal_info::SalInfo,
     topics::{base_sal_topic::BaseSALTopic, read_topic::ReadTopic, write_topic::WriteTopic},
     utils::command_ack::CommandAck,
 };
+
+#[cfg(feature = "tracing")]
+use crate::utils::command_span;
 use apache_avro::{
+    to_value,
     types::{Record, Value},
     Schema,
 };
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 use std::fmt::Debug;
-use std::{collections::HashMap, time::Duration};
+use std::time::{Duration, Instant};
 
 pub type AckCmdResult = std::result::Result<CommandAck, CommandAck>;
 
+/// Timeout used for commands that do not declare one in their interface.
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct RemoteCommand<'a> {
+    component_name: String,
     command_writer: WriteTopic<'a>,
     ack_reader: ReadTopic<'a>,
+    default_timeout: Duration,
+    /// Type of the command, as sent back in the `cmdtype` field of the acks.
+    command_type: Option<i32>,
+    /// Fail if the `cmdtype` of the acks does not match the command type?
+    verify_cmdtype: bool,
+    /// Parameters of the last command sent.
+    last_parameters: Option<Value>,
 }
 
 impl<'a> RemoteCommand<'a> {
     pub fn new(command_name: &str, domain: &Domain, sal_info: &SalInfo) -> RemoteCommand<'a> {
         RemoteCommand {
+            component_name: sal_info.get_name(),
             command_writer: WriteTopic::new(command_name, sal_info, domain),
             ack_reader: ReadTopic::new("ackcmd", sal_info, domain, 0),
+            default_timeout: sal_info
+                .get_topic_info(command_name)
+                .and_then(|topic_info| topic_info.get_timeout())
+                .unwrap_or(DEFAULT_COMMAND_TIMEOUT),
+            command_type: sal_info
+                .get_command_type(command_name)
+                .map(|command_type| command_type as i32),
+            verify_cmdtype: false,
+            last_parameters: None,
+        }
+    }
+
+    /// Verify that the `cmdtype` of the acks matches the type of this
+    /// command, rejecting acks that do not.
+    pub fn with_verify_cmdtype(mut self, verify_cmdtype: bool) -> Self {
+        self.verify_cmdtype = verify_cmdtype;
+        self
+    }
+
+    /// Check the `cmdtype` of an ack if verification is enabled.
+    fn check_cmdtype(&self, command_ack: &CommandAck) -> Result<(), String> {
+        match self.command_type {
+            Some(command_type)
+                if self.verify_cmdtype && command_ack.get_cmdtype() != command_type =>
+            {
+                Err(format!(
+                    "Ack cmdtype {} does not match command type {command_type}.",
+                    command_ack.get_cmdtype()
+                ))
+            }
+            _ => Ok(()),
         }
     }
 
+    /// Get the timeout used by [RemoteCommand::run_with_default_timeout].
+    ///
+    /// This is the timeout declared in the command interface or
+    /// [DEFAULT_COMMAND_TIMEOUT] if the command does not declare one.
+    pub fn get_default_timeout(&self) -> Duration {
+        self.default_timeout
+    }
+
+    /// Same as [RemoteCommand::run] using the command default timeout.
+    pub async fn run_with_default_timeout<'b>(
+        &mut self,
+        parameters: &mut Record<'b>,
+        wait_done: bool,
+    ) -> AckCmdResult {
+        self.run(parameters, self.default_timeout, wait_done).await
+    }
+
     pub fn get_schema(&self) -> &Schema {
         self.command_writer.get_schema()
     }
@@ -39,6 +105,18 @@ impl<'a> RemoteCommand<'a> {
         self.command_writer.get_index()
     }
 
+    /// Change the index of the component the command is sent to.
+    pub(crate) fn set_index(&mut self, index: i32) -> SalObjResult<()> {
+        self.check_index(index)?;
+        self.command_writer.set_index(index);
+        self.ack_reader.set_sal_index(Some(index))
+    }
+
+    /// Check that the command can switch to `index`, without changing it.
+    pub(crate) fn check_index(&self, index: i32) -> SalObjResult<()> {
+        self.ack_reader.check_sal_index(Some(index))
+    }
+
     pub fn get_origin(&self) -> i32 {
         self.command_writer.get_origin()
     }
@@ -51,78 +129,44 @@ impl<'a> RemoteCommand<'a> {
         self.command_writer.get_seq_num()
     }
 
+    /// Get the name of the component the command is sent to.
+    pub fn get_component_name(&self) -> &str {
+        &self.component_name
+    }
+
+    /// Get the parameters of the last command sent.
+    ///
+    /// Commands sent with [`run`](Self::run) are returned as written, so
+    /// defaulted fields and the private fields filled in by the writer are
+    /// included. Typed commands are returned as given, with the private
+    /// fields set by the caller.
+    ///
+    /// Return `None` if no command was sent successfully yet.
+    pub fn last_parameters(&self) -> Option<Value> {
+        self.last_parameters.clone()
+    }
+
     pub async fn run<'b>(
         &mut self,
         parameters: &mut Record<'b>,
         timeout: Duration,
         wait_done: bool,
     ) -> AckCmdResult {
-        let identity = self.command_writer.get_identity();
-        let origin = self.command_writer.get_origin();
         self.ack_reader.flush();
+        let issued = Instant::now();
         match self.command_writer.write(parameters).await {
-            Ok(seq_num) => loop {
-                if let Some(Value::Record(ack_cmd)) = self.ack_reader.pop_back(false, timeout).await
-                {
-                    let data_dict: HashMap<String, Value> = ack_cmd
-                        .into_iter()
-                        .map(|(field, value)| (field, value))
-                        .collect();
-
-                    if *data_dict.get("origin").unwrap_or(&Value::Int(0)) == Value::Int(origin)
-                        && *data_dict
-                            .get("identity")
-                            .unwrap_or(&Value::String("".to_owned()))
-                            == Value::String(identity.to_owned())
-                        && *data_dict.get("private_seqNum").unwrap_or(&Value::Int(0))
-                            == Value::Int(seq_num)
-                    {
-                        let ack = sal_enums::get_ackcmd_code(data_dict.get("ack")).clone();
-                        let error: isize = match data_dict.get("error") {
-                            Some(Value::Long(error)) => *error as isize,
-                            _ => 0,
-                        };
-                        let result = match data_dict.get("result") {
-                            Some(Value::String(result)) => result.to_owned(),
-                            _ => "".to_string(),
-                        };
-
-                        let command_ack = CommandAck::new(
-                            ack,
-                            error,
-                            result,
-                            identity.clone(),
-                            origin,
-                            timeout,
-                            seq_num,
-                        );
-
-                        if !wait_done {
-                            return Ok(command_ack);
-                        } else if command_ack.is_final() {
-                            if command_ack.is_good() {
-                                return Ok(command_ack);
-                            } else {
-                                return Err(command_ack);
-                            };
-                        }
-                    }
-                } else {
-                    return Err(CommandAck::new(
-                        sal_enums::SalRetCode::CmdNoack,
-                        -1,
-                        "No acknowledgment seen.".to_string(),
-                        identity,
-                        origin,
-                        timeout,
-                        seq_num,
-                    ));
-                }
-            },
+            Ok(seq_num) => {
+                self.last_parameters = Some(Value::Record(parameters.fields.clone()));
+                self.wait_ack(seq_num, issued, timeout, wait_done).await
+            }
             Err(error) => Err(CommandAck::invalid_command(&error.to_string())),
         }
     }
 
+    /// Send the command and wait for its acknowledgement.
+    ///
+    /// With the `tracing` feature the command runs in a `remote_command`
+    /// span, see [command_span](crate::utils::command_span).
     pub async fn run_typed<'b, T>(
         &mut self,
         data: &T,
@@ -132,86 +176,366 @@ impl<'a> RemoteCommand<'a> {
     where
         T: BaseSALTopic + Serialize + Debug,
     {
+        #[cfg(feature = "tracing")]
+        let span = command_span::remote_command_span(
+            &self.component_name,
+            &self.command_writer.get_topic_name(),
+        );
+
+        #[cfg(feature = "tracing")]
+        let run_span = span.clone();
+
+        #[cfg(feature = "tracing")]
+        let key = command_span::CommandKey::new(
+            &self.component_name,
+            self.command_type.unwrap_or_default(),
+            data.get_private_seq_num(),
+            data.get_private_origin(),
+        );
+        #[cfg(feature = "tracing")]
+        command_span::start_remote_command(key.clone(), &span);
+
+        let run = async {
+            self.ack_reader.flush();
+            log::debug!("Sending command...");
+            let issued = Instant::now();
+            match self.command_writer.write_typed(data).await {
+                Ok(seq_num) => {
+                    #[cfg(feature = "tracing")]
+                    command_span::record_seq_num(&run_span, seq_num);
+                    self.last_parameters = to_value(data).ok();
+                    self.wait_ack(seq_num, issued, timeout, wait_done).await
+                }
+                Err(error) => Err(CommandAck::invalid_command(&error.to_string())),
+            }
+        };
+
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::instrument(run, span.clone());
+
+        let result = run.await;
+
+        #[cfg(feature = "tracing")]
+        {
+            command_span::finish_remote_command(&key);
+            match &result {
+                Ok(command_ack) | Err(command_ack) => command_span::record_ack(&span, command_ack),
+            }
+        }
+
+        result
+    }
+
+    /// Send the command, wait for it to complete and read its response.
+    ///
+    /// SAL commands cannot return data in their acknowledgement, so
+    /// components that reply with data publish it in an event before
+    /// acknowledging the command as complete. `response_reader` is the
+    /// reader of that event, e.g. `logevent_scalars` for the `setScalars`
+    /// command of the Test component; it is flushed before the command is
+    /// sent, so only a sample published while the command runs is returned.
+    ///
+    /// Return the final acknowledgement if the command fails, or if no
+    /// response is received within `timeout` of its completion.
+    pub async fn run_for_result<T, R>(
+        &mut self,
+        data: &T,
+        response_reader: &mut ReadTopic<'_>,
+        timeout: Duration,
+    ) -> Result<R, CommandAck>
+    where
+        T: BaseSALTopic + Serialize + Debug,
+        R: DeserializeOwned,
+    {
+        response_reader.flush();
+        self.run_typed(data, timeout, true).await?;
+
+        match response_reader.pop_front_typed::<R>(false, timeout).await {
+            Ok(Some(response)) => Ok(response),
+            Ok(None) => Err(CommandAck::invalid_command(&format!(
+                "Command completed but no {} response received.",
+                response_reader.get_topic_name()
+            ))),
+            Err(error) => Err(CommandAck::invalid_command(&format!(
+                "Failed to read {} response: {error}",
+                response_reader.get_topic_name()
+            ))),
+        }
+    }
+
+    /// Send the command and return every acknowledgement received for it, in
+    /// the order they arrived, up to and including the final one.
+    ///
+    /// This is useful for commands that report progress with several
+    /// in-progress acks before completing. The final ack is included even if
+    /// the command failed; an error is returned only if the command could
+    /// not be sent or no final ack arrived within `timeout` of the previous
+    /// ack.
+    pub async fn run_collecting<T>(
+        &mut self,
+        data: &T,
+        timeout: Duration,
+    ) -> SalObjResult<Vec<CommandAck>>
+    where
+        T: BaseSALTopic + Serialize + Debug,
+    {
+        self.ack_reader.flush();
+        let issued = Instant::now();
+        let seq_num = self.command_writer.write_typed(data).await?;
+        self.last_parameters = to_value(data).ok();
+
         let identity = self.command_writer.get_identity();
         let origin = self.command_writer.get_origin();
-        self.ack_reader.flush();
-        log::debug!("Sending command...");
-        match self.command_writer.write_typed(data).await {
-            Ok(seq_num) => {
-                log::debug!("Wainting ack for seq_num {seq_num}.");
-
-                loop {
-                    if let Some(Value::Record(ack_cmd)) =
-                        self.ack_reader.pop_back(false, timeout).await
-                    {
-                        log::debug!("Got {ack_cmd:?}");
-
-                        let data_dict: HashMap<String, Value> = ack_cmd
-                            .into_iter()
-                            .map(|(field, value)| (field, value))
-                            .collect();
-
-                        if *data_dict.get("origin").unwrap_or(&Value::Int(0)) == Value::Int(origin)
-                            && *data_dict
-                                .get("identity")
-                                .unwrap_or(&Value::String("".to_owned()))
-                                == Value::String(identity.to_owned())
-                            && *data_dict.get("private_seqNum").unwrap_or(&Value::Int(0))
-                                == Value::Int(seq_num)
-                        {
-                            let ack = sal_enums::get_ackcmd_code(data_dict.get("ack")).clone();
-                            let error: isize = match data_dict.get("error") {
-                                Some(Value::Int(error)) => *error as isize,
-                                _ => 0,
-                            };
-                            let result = match data_dict.get("result") {
-                                Some(Value::String(result)) => result.to_owned(),
-                                _ => "".to_string(),
-                            };
-
-                            let command_ack = CommandAck::new(
-                                ack,
-                                error,
-                                result,
-                                identity.clone(),
-                                origin,
-                                timeout,
-                                seq_num,
-                            );
-
-                            log::debug!(
-                                "Command ack: {:?}; wait done {} is final? {}, is good? {}",
-                                command_ack.get_ack_enum(),
-                                wait_done,
-                                command_ack.is_final(),
-                                command_ack.is_good()
-                            );
-                            if !wait_done {
-                                return Ok(command_ack);
-                            } else if command_ack.is_final() {
-                                if command_ack.is_good() {
-                                    return Ok(command_ack);
-                                } else {
-                                    return Err(command_ack);
-                                };
-                            }
+        let mut command_acks = Vec::new();
+
+        while let Some(ack_cmd) = self.ack_reader.pop_front(false, timeout).await {
+            match AckCmdRecord::from_value(&ack_cmd) {
+                Ok(ackcmd_record) if ackcmd_record.is_reply_to(origin, &identity, seq_num) => {
+                    let command_ack = CommandAck::from_ackcmd_record(&ackcmd_record);
+                    self.check_cmdtype(&command_ack)
+                        .map_err(|error| SalObjError::new(&error))?;
+                    if command_ack.is_final() {
+                        command_acks.push(command_ack.with_duration(issued.elapsed()));
+                        return Ok(command_acks);
+                    }
+                    command_acks.push(command_ack);
+                }
+                _ => log::debug!("Discarding ack: {ack_cmd:?}"),
+            }
+        }
+        Err(SalObjError::new(&format!(
+            "No final acknowledgment seen for seq_num {seq_num}; received {} acks.",
+            command_acks.len()
+        )))
+    }
+
+    /// Wait for the acknowledgement of the command with the given sequence
+    /// number.
+    ///
+    /// If `wait_done` is true wait for the final acknowledgement, otherwise
+    /// return the first one. Final acks carry the time elapsed since the
+    /// command was `issued`.
+    async fn wait_ack(
+        &mut self,
+        seq_num: i32,
+        issued: Instant,
+        timeout: Duration,
+        wait_done: bool,
+    ) -> AckCmdResult {
+        let identity = self.command_writer.get_identity();
+        let origin = self.command_writer.get_origin();
+
+        log::debug!("Waiting ack for seq_num {seq_num}.");
+
+        while let Some(ack_cmd) = self.ack_reader.pop_back(false, timeout).await {
+            match AckCmdRecord::from_value(&ack_cmd) {
+                Ok(ackcmd_record) if ackcmd_record.is_reply_to(origin, &identity, seq_num) => {
+                    let mut command_ack = CommandAck::from_ackcmd_record(&ackcmd_record);
+                    if let Err(error) = self.check_cmdtype(&command_ack) {
+                        return Err(CommandAck::invalid_command(&error));
+                    }
+                    if command_ack.is_final() {
+                        command_ack = command_ack.with_duration(issued.elapsed());
+                    }
+
+                    log::debug!(
+                        "Command ack: {:?}; wait done {} is final? {}, is good? {}",
+                        command_ack.get_ack_enum(),
+                        wait_done,
+                        command_ack.is_final(),
+                        command_ack.is_good()
+                    );
+                    if !wait_done {
+                        return Ok(command_ack);
+                    } else if command_ack.is_final() {
+                        if command_ack.is_good() {
+                            return Ok(command_ack);
                         } else {
-                            log::debug!("Discarding ack: {data_dict:?}");
-                        }
-                    } else {
-                        return Err(CommandAck::new(
-                            sal_enums::SalRetCode::CmdNoack,
-                            -1,
-                            "No acknowledgment seen.".to_string(),
-                            identity,
-                            origin,
-                            timeout,
-                            seq_num,
-                        ));
+                            return Err(command_ack);
+                        };
                     }
                 }
+                _ => log::debug!("Discarding ack: {ack_cmd:?}"),
             }
-            Err(error) => Err(CommandAck::invalid_command(&error.to_string())),
         }
+        Err(CommandAck::new(
+            sal_enums::SalRetCode::CmdNoack,
+            -1,
+            "No acknowledgment seen.".to_string(),
+            identity,
+            origin,
+            timeout,
+            seq_num,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        controller::Controller,
+        csc::test_csc::topics::{scalars::Scalars, wait::Wait},
+        generics::start::Start,
+        sal_enums::SalRetCode,
+    };
+    use apache_avro::from_value;
+
+    #[test]
+    fn default_timeout() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+
+        let remote_command = RemoteCommand::new("command_wait", &domain, &sal_info);
+
+        assert_eq!(
+            remote_command.get_default_timeout(),
+            DEFAULT_COMMAND_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn check_cmdtype() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let command_type = sal_info.get_command_type("command_setScalars").unwrap() as i32;
+
+        let remote_command = RemoteCommand::new("command_setScalars", &domain, &sal_info);
+        let wrong_ack = CommandAck::default().with_cmdtype(command_type + 1);
+
+        assert!(remote_command.check_cmdtype(&wrong_ack).is_ok());
+
+        let remote_command = remote_command.with_verify_cmdtype(true);
+
+        assert!(remote_command.check_cmdtype(&wrong_ack).is_err());
+        assert!(remote_command
+            .check_cmdtype(&CommandAck::default().with_cmdtype(command_type))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn last_parameters() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let mut remote_command = RemoteCommand::new("command_start", &domain, &sal_info);
+
+        assert!(remote_command.last_parameters().is_none());
+
+        let start = Start::default()
+            .with_configuration_override("fast.yaml")
+            .with_private_seq_num(remote_command.get_seq_num())
+            .with_sal_index(remote_command.get_index());
+
+        // Nobody acknowledges the command; only the parameters matter here.
+        let _ = remote_command
+            .run_typed(&start, Duration::from_millis(100), false)
+            .await;
+
+        let last_start = from_value::<Start>(&remote_command.last_parameters().unwrap()).unwrap();
+
+        assert_eq!(last_start.get_configuration_override(), "fast.yaml");
+    }
+
+    #[tokio::test]
+    async fn run_collecting() {
+        let mut controller_domain = Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 146).unwrap();
+
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 146).unwrap();
+        let mut remote_command = RemoteCommand::new("command_wait", &domain, &sal_info);
+
+        let timeout = Duration::from_secs(10);
+        let wait = Wait::default()
+            .with_timestamps()
+            .with_private_seq_num(remote_command.get_seq_num())
+            .with_private_origin(remote_command.get_origin())
+            .with_private_identity(&remote_command.get_identity())
+            .with_sal_index(remote_command.get_index());
+
+        let controller_command = controller.commands.get_mut("command_wait").unwrap();
+
+        let (command_acks, _) =
+            tokio::join!(remote_command.run_collecting(&wait, timeout), async {
+                let cmd_data = controller_command
+                    .process_command_with_timeout(timeout)
+                    .await
+                    .unwrap();
+                let wait = from_value::<Wait>(&cmd_data).unwrap();
+                for result in ["Started.", "Half way."] {
+                    controller_command
+                        .ack(CommandAck::make_in_progress(wait.clone(), timeout, result))
+                        .await
+                        .unwrap();
+                }
+                controller_command
+                    .ack(CommandAck::make_complete(wait))
+                    .await
+                    .unwrap();
+            });
+
+        let command_acks = command_acks.unwrap();
+        let acks: Vec<(&SalRetCode, &str)> = command_acks
+            .iter()
+            .map(|command_ack| (command_ack.get_ack_enum(), command_ack.get_result()))
+            .collect();
+
+        assert_eq!(
+            acks,
+            vec![
+                (&SalRetCode::CmdInprogress, "Started."),
+                (&SalRetCode::CmdInprogress, "Half way."),
+                (&SalRetCode::CmdComplete, ""),
+            ]
+        );
+        assert!(command_acks[2].get_duration().is_some());
+    }
+
+    #[tokio::test]
+    async fn run_for_result() {
+        let mut controller_domain = Domain::new();
+        let mut controller = Controller::new(&mut controller_domain, "Test", 148).unwrap();
+
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 148).unwrap();
+        let mut remote_command = RemoteCommand::new("command_setScalars", &domain, &sal_info);
+        let mut response_reader = ReadTopic::new("logevent_scalars", &sal_info, &domain, 0);
+        let mut response_writer = WriteTopic::new("logevent_scalars", &sal_info, &domain);
+
+        let timeout = Duration::from_secs(10);
+        let mut set_scalars = Scalars::default()
+            .with_private_seq_num(remote_command.get_seq_num())
+            .with_private_origin(remote_command.get_origin())
+            .with_private_identity(&remote_command.get_identity())
+            .with_sal_index(remote_command.get_index());
+        set_scalars.int0 = 42;
+
+        let controller_command = controller.commands.get_mut("command_setScalars").unwrap();
+
+        let (response, _) = tokio::join!(
+            remote_command.run_for_result::<Scalars, Scalars>(
+                &set_scalars,
+                &mut response_reader,
+                timeout
+            ),
+            async {
+                let cmd_data = controller_command
+                    .process_command_with_timeout(timeout)
+                    .await
+                    .unwrap();
+                let set_scalars = from_value::<Scalars>(&cmd_data).unwrap();
+                let mut scalars = Scalars::default()
+                    .with_private_seq_num(response_writer.get_seq_num())
+                    .with_sal_index(response_writer.get_index());
+                scalars.int0 = set_scalars.int0;
+                response_writer.write_typed(&scalars).await.unwrap();
+                controller_command
+                    .ack(CommandAck::make_complete(set_scalars))
+                    .await
+                    .unwrap();
+            }
+        );
+
+        assert_eq!(response.unwrap().int0, 42);
     }
 }
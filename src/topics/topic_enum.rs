@@ -0,0 +1,25 @@
+//! Enumeration over the topics of a component.
+
+use apache_avro::types::Value;
+
+// Used by the code generated by `#[derive(TopicEnum)]`, so callers do not
+// need apache_avro as a direct dependency.
+#[doc(hidden)]
+pub use apache_avro;
+
+/// An enum with one variant per topic, holding the topic data.
+///
+/// Derive it with `#[derive(TopicEnum)]`, naming the topic of each variant
+/// with `#[topic("topic_name")]`, e.g.
+/// [TestTelemetry](crate::csc::test_csc::topics::telemetry::TestTelemetry).
+/// This allows reading samples of many topics as a single stream, see
+/// [Remote::event_stream](crate::remote::Remote::event_stream).
+pub trait TopicEnum: Sized {
+    /// Names of the topics in the enum.
+    fn get_topic_names() -> Vec<&'static str>;
+
+    /// Decode a sample of `topic_name` into the matching variant.
+    ///
+    /// Return `None` if the topic is not in the enum.
+    fn from_topic_data(topic_name: &str, data: &Value) -> Option<Result<Self, apache_avro::Error>>;
+}
@@ -5,10 +5,86 @@
 //! avro schema.
 //!
 
-use apache_avro::Schema;
+use apache_avro::{rabin::Rabin, Schema};
+use std::time::Duration;
 
 use crate::error::errors::{SalObjError, SalObjResult};
 
+/// Units value used in the schemas for dimensionless fields.
+const UNITLESS: &str = "unitless";
+
+/// Metadata of one topic field, as declared in the topic schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMetadata {
+    name: String,
+    field_type: String,
+    description: String,
+    units: String,
+    default: Option<serde_json::Value>,
+}
+
+impl FieldMetadata {
+    /// Parse the metadata of all fields in a json topic schema.
+    pub fn from_schema_str(schema: &str) -> Vec<FieldMetadata> {
+        match serde_json::from_str::<serde_json::Value>(schema) {
+            Ok(schema) => schema["fields"]
+                .as_array()
+                .map(|fields| fields.iter().map(FieldMetadata::from_json).collect())
+                .unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn from_json(field: &serde_json::Value) -> FieldMetadata {
+        let units = field["units"].as_str().unwrap_or_default();
+        FieldMetadata {
+            name: field["name"].as_str().unwrap_or_default().to_owned(),
+            field_type: match &field["type"] {
+                serde_json::Value::String(field_type) => field_type.to_owned(),
+                field_type => field_type.to_string(),
+            },
+            description: field["description"].as_str().unwrap_or_default().to_owned(),
+            units: if units == UNITLESS {
+                String::new()
+            } else {
+                units.to_owned()
+            },
+            default: field.get("default").cloned(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the avro type of the field.
+    pub fn get_type(&self) -> &str {
+        &self.field_type
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    /// Get the units of the field.
+    ///
+    /// Dimensionless fields, declared as "unitless" in the schema, return
+    /// an empty string.
+    pub fn get_units(&self) -> &str {
+        &self.units
+    }
+
+    /// Is the field dimensionless?
+    pub fn is_unitless(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Get the default value declared in the schema, if any.
+    pub fn get_default(&self) -> Option<&serde_json::Value> {
+        self.default.as_ref()
+    }
+}
+
 /// Information about one topic.
 pub struct TopicInfo {
     component_name: String,
@@ -19,6 +95,8 @@ pub struct TopicInfo {
     rev_code: Option<String>,
     description: String,
     partitions: usize,
+    fields: Vec<FieldMetadata>,
+    timeout: Option<Duration>,
 }
 
 impl Default for TopicInfo {
@@ -39,6 +117,8 @@ impl TopicInfo {
             rev_code: None,
             description: String::new(),
             partitions: 0,
+            fields: Vec::new(),
+            timeout: None,
         }
     }
 
@@ -77,6 +157,16 @@ impl TopicInfo {
         self
     }
 
+    pub fn with_fields(mut self, fields: Vec<FieldMetadata>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
     pub fn with_rev_code(mut self, rev_code: Option<&str>) -> Self {
         if let Some(rev_code) = rev_code {
             self.rev_code = Some(rev_code.to_owned());
@@ -108,10 +198,47 @@ impl TopicInfo {
         format!("{}_{}", self.component_name, self.topic_name)
     }
 
+    /// Get the expected duration of a command, if declared in the topic
+    /// schema with a top-level `timeout` attribute (in seconds).
+    ///
+    /// The interface schemas do not declare command durations, so this
+    /// attribute is only present when added to a command schema through
+    /// the schema overrides (`LSST_SCHEMA_OVERRIDE_DIR`).
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Parse the `timeout` attribute, in seconds, of a json topic schema.
+    pub fn parse_timeout(schema: &str) -> Option<Duration> {
+        serde_json::from_str::<serde_json::Value>(schema)
+            .ok()?
+            .get("timeout")?
+            .as_f64()
+            .filter(|timeout| *timeout > 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    /// Get metadata of all the topic fields.
+    pub fn get_fields(&self) -> &[FieldMetadata] {
+        &self.fields
+    }
+
+    /// Get metadata of a topic field.
+    pub fn get_field(&self, field_name: &str) -> Option<&FieldMetadata> {
+        self.fields
+            .iter()
+            .find(|field| field.get_name() == field_name)
+    }
+
     pub fn get_schema(&self) -> Option<Schema> {
         self.schema.clone()
     }
 
+    /// Get a reference to the topic schema.
+    pub fn get_schema_ref(&self) -> Option<&Schema> {
+        self.schema.as_ref()
+    }
+
     /// Get number of partitions in this topic.
     ///
     /// This is a Kafka QoS property.
@@ -131,6 +258,16 @@ impl TopicInfo {
         }
     }
 
+    /// Get a fingerprint of the topic schema.
+    ///
+    /// This is the CRC-64-AVRO (Rabin) fingerprint of the schema canonical
+    /// form, as a hex string. It is stable across processes and only
+    /// changes when the schema does, which makes it suitable for caching and
+    /// change detection.
+    pub fn schema_fingerprint(&self) -> SalObjResult<String> {
+        Ok(self.make_schema()?.fingerprint::<Rabin>().to_string())
+    }
+
     /// Make schema for the topic.
     pub fn make_schema(&self) -> SalObjResult<apache_avro::Schema> {
         if let Some(schema) = &self.schema {
@@ -192,6 +329,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn field_metadata_from_schema_str() {
+        let schema = r#"{
+            "type": "record",
+            "name": "scalars",
+            "fields": [
+                {"name": "private_sndStamp", "type": "double", "description": "Time of instance publication", "units": "second"},
+                {"name": "double0", "type": "double", "default": 1.5, "description": "A double.", "units": "unitless"}
+            ]
+        }"#;
+
+        let fields = FieldMetadata::from_schema_str(schema);
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].get_name(), "private_sndStamp");
+        assert_eq!(fields[0].get_units(), "second");
+        assert!(!fields[0].is_unitless());
+        assert_eq!(fields[1].get_type(), "double");
+        assert_eq!(fields[1].get_description(), "A double.");
+        assert_eq!(fields[1].get_units(), "");
+        assert!(fields[1].is_unitless());
+        assert_eq!(fields[0].get_default(), None);
+        assert_eq!(fields[1].get_default(), Some(&serde_json::json!(1.5)));
+    }
+
+    #[test]
+    fn parse_timeout() {
+        assert_eq!(
+            TopicInfo::parse_timeout(r#"{"name": "command_wait", "timeout": 2.5}"#),
+            Some(Duration::from_secs_f64(2.5))
+        );
+        assert_eq!(
+            TopicInfo::parse_timeout(r#"{"name": "command_wait"}"#),
+            None
+        );
+        assert_eq!(
+            TopicInfo::parse_timeout(r#"{"name": "command_wait", "timeout": -1}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn schema_fingerprint() {
+        let make_topic_info = |schema: &str| {
+            TopicInfo::new()
+                .with_topic_name("scalars")
+                .with_schema(Schema::parse_str(schema).unwrap())
+        };
+        let schema = r#"{"type": "record", "name": "scalars", "fields": [{"name": "double0", "type": "double"}]}"#;
+        let same_schema = r#"{
+            "type": "record",
+            "name": "scalars",
+            "fields": [{"name": "double0", "type": "double", "description": "A double."}]
+        }"#;
+        let other_schema =
+            r#"{"type": "record", "name": "scalars", "fields": [{"name": "int0", "type": "int"}]}"#;
+
+        let fingerprint = make_topic_info(schema).schema_fingerprint().unwrap();
+
+        assert_eq!(
+            fingerprint,
+            make_topic_info(same_schema).schema_fingerprint().unwrap()
+        );
+        assert_ne!(
+            fingerprint,
+            make_topic_info(other_schema).schema_fingerprint().unwrap()
+        );
+        assert!(TopicInfo::new().schema_fingerprint().is_err());
+    }
+
     // #[test]
     // fn get_private_fields_indexed() {
     //     let private_fields = TopicInfo::get_private_fields(true);
@@ -0,0 +1,155 @@
+//! Convert between the different names of a topic.
+//!
+//! See the [sal_info](crate::sal_info) module documentation for a
+//! description of the naming conventions.
+
+use crate::error::errors::{SalObjError, SalObjResult};
+
+/// Suffix appended to the schema registry name to form the subject name.
+const SUBJECT_NAME_SUFFIX: &str = "-value";
+
+/// Names of the topics of a component with a given topic subname.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicNames {
+    component_name: String,
+    topic_subname: String,
+}
+
+impl TopicNames {
+    pub fn new(component_name: &str, topic_subname: &str) -> TopicNames {
+        TopicNames {
+            component_name: component_name.to_owned(),
+            topic_subname: topic_subname.to_owned(),
+        }
+    }
+
+    /// Get the component name.
+    pub fn get_component_name(&self) -> &str {
+        &self.component_name
+    }
+
+    /// Get the topic subname.
+    pub fn get_topic_subname(&self) -> &str {
+        &self.topic_subname
+    }
+
+    /// Make the `sal_name` of a topic, e.g. `Test_command_setScalars`.
+    pub fn make_sal_name(&self, topic_name: &str) -> String {
+        format!("{}_{topic_name}", self.component_name)
+    }
+
+    /// Make the `schema_registry_name` of a topic, e.g.
+    /// `lsst.test.Test.command_setScalars`.
+    pub fn make_schema_registry_name(&self, topic_name: &str) -> String {
+        format!("{}{topic_name}", self.make_schema_registry_prefix())
+    }
+
+    /// Make the `subject_name` of a topic, e.g.
+    /// `lsst.test.Test.command_setScalars-value`.
+    pub fn make_subject_name(&self, topic_name: &str) -> String {
+        format!(
+            "{}{SUBJECT_NAME_SUFFIX}",
+            self.make_schema_registry_name(topic_name)
+        )
+    }
+
+    /// Get the `topic_name` from any of the topic name forms.
+    ///
+    /// Return an error if the name belongs to a different component or topic
+    /// subname. Names of the form `{Component}_{topic}`, for a component
+    /// other than this one, are rejected; component names start with an
+    /// uppercase letter, topic names do not.
+    pub fn parse_topic_name(&self, name: &str) -> SalObjResult<String> {
+        let name = name.strip_suffix(SUBJECT_NAME_SUFFIX).unwrap_or(name);
+
+        let topic_name = if name.starts_with("lsst.") {
+            name.strip_prefix(&self.make_schema_registry_prefix())
+        } else if let Some(topic_name) = name.strip_prefix(&format!("{}_", self.component_name)) {
+            Some(topic_name)
+        } else if TopicNames::is_sal_name(name) {
+            None
+        } else {
+            Some(name)
+        };
+
+        match topic_name {
+            Some(topic_name) if !topic_name.is_empty() && !topic_name.contains('.') => {
+                Ok(topic_name.to_owned())
+            }
+            _ => Err(SalObjError::new(&format!(
+                "{name} is not a topic of {} with subname {}.",
+                self.component_name, self.topic_subname
+            ))),
+        }
+    }
+
+    /// Does `name` look like a `sal_name`, prefixed by a component name?
+    fn is_sal_name(name: &str) -> bool {
+        match name.split_once('_') {
+            Some((component_name, _)) => {
+                component_name.starts_with(|c: char| c.is_ascii_uppercase())
+            }
+            None => false,
+        }
+    }
+
+    fn make_schema_registry_prefix(&self) -> String {
+        format!("lsst.{}.{}.", self.topic_subname, self.component_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_names() {
+        let topic_names = TopicNames::new("Test", "test");
+
+        assert_eq!(
+            topic_names.make_sal_name("command_setScalars"),
+            "Test_command_setScalars"
+        );
+        assert_eq!(
+            topic_names.make_schema_registry_name("command_setScalars"),
+            "lsst.test.Test.command_setScalars"
+        );
+        assert_eq!(
+            topic_names.make_subject_name("command_setScalars"),
+            "lsst.test.Test.command_setScalars-value"
+        );
+    }
+
+    #[test]
+    fn parse_topic_name() {
+        let topic_names = TopicNames::new("Test", "test");
+
+        for name in [
+            "command_setScalars".to_owned(),
+            topic_names.make_sal_name("command_setScalars"),
+            topic_names.make_schema_registry_name("command_setScalars"),
+            topic_names.make_subject_name("command_setScalars"),
+        ] {
+            assert_eq!(
+                topic_names.parse_topic_name(&name).unwrap(),
+                "command_setScalars"
+            );
+        }
+        assert_eq!(topic_names.parse_topic_name("scalars").unwrap(), "scalars");
+    }
+
+    #[test]
+    fn parse_topic_name_other_component() {
+        let topic_names = TopicNames::new("Test", "test");
+
+        assert!(topic_names
+            .parse_topic_name("lsst.test.Other.command_setScalars")
+            .is_err());
+        assert!(topic_names
+            .parse_topic_name("lsst.other.Test.command_setScalars-value")
+            .is_err());
+        assert!(topic_names
+            .parse_topic_name("Other_command_setScalars")
+            .is_err());
+    }
+}
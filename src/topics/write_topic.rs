@@ -6,7 +6,7 @@ use apache_avro::{
 
 use crate::{
     domain::Domain,
-    error::errors::SalObjError,
+    error::errors::{SalObjError, SalObjResult},
     sal_info::SalInfo,
     topics::{base_sal_topic::BaseSALTopic, base_topic::BaseTopic},
     utils::types::WriteTopicResult,
@@ -15,10 +15,11 @@ use chrono::Utc;
 use kafka::{error::Result as KafkaResult, producer};
 use rand::Rng;
 use schema_registry_converter::{
-    async_impl::avro::AvroEncoder, schema_registry_common::SubjectNameStrategy,
+    async_impl::avro::AvroEncoder,
+    schema_registry_common::{get_subject, SubjectNameStrategy},
 };
 use serde::Serialize;
-use std::{fmt::Debug, time::Duration};
+use std::{fmt::Debug, marker::PhantomData, sync::Arc, time::Duration};
 
 /// Base struct for writing a topic.
 pub struct WriteTopic<'a> {
@@ -37,9 +38,13 @@ pub struct WriteTopic<'a> {
     /// Sequence number of the written samples. This number is incremented
     /// every time a sample is published.
     seq_num: i32,
-    encoder: AvroEncoder<'a>,
+    /// Avro encoder shared with the domain.
+    encoder: Arc<AvroEncoder<'static>>,
     schema_registry_topic_name: String,
+    /// Subject the topic schema is registered with.
+    subject_name: String,
     schema: Schema,
+    _lifetime: PhantomData<&'a ()>,
 }
 
 impl<'a> BaseTopic for WriteTopic<'a> {}
@@ -61,14 +66,21 @@ impl<'a> WriteTopic<'a> {
             indexed: sal_info.is_indexed(),
             origin: domain.get_origin() as i32,
             identity: domain.get_identity(),
-            producer: producer::Producer::from_hosts(Domain::get_client_hosts())
-                .with_ack_timeout(Duration::from_secs(1))
-                .with_required_acks(producer::RequiredAcks::One)
+            producer: domain
+                .get_kafka_extra_config()
+                .apply_to_producer(
+                    producer::Producer::from_hosts(Domain::get_client_hosts())
+                        .with_ack_timeout(Duration::from_secs(1))
+                        .with_required_acks(producer::RequiredAcks::One)
+                        .with_client_id(sal_info.make_client_id(domain.get_origin())),
+                )
                 .create(),
             seq_num,
-            encoder: SalInfo::make_encoder(),
+            encoder: domain.encoder(),
             schema_registry_topic_name: sal_info.make_schema_registry_topic_name(topic_name),
+            subject_name: sal_info.make_subject_name(topic_name),
             schema,
+            _lifetime: PhantomData,
         }
     }
     /// Get value of the origin identifier.
@@ -95,6 +107,11 @@ impl<'a> WriteTopic<'a> {
         self.index
     }
 
+    /// Change the index written with the data.
+    pub(crate) fn set_index(&mut self, index: i32) {
+        self.index = index;
+    }
+
     /// Get Schema
     pub fn get_schema(&self) -> &Schema {
         &self.schema
@@ -140,12 +157,7 @@ impl<'a> WriteTopic<'a> {
                 _ => continue,
             }
         }
-        let record_type = self.get_record_type();
-
-        let key_strategy = SubjectNameStrategy::TopicRecordNameStrategy(
-            self.schema_registry_topic_name.clone(),
-            record_type,
-        );
+        let key_strategy = self.make_key_strategy();
         let data_fields: Vec<(&str, Value)> =
             data.fields.iter().map(|(k, v)| (&**k, v.clone())).collect();
 
@@ -183,6 +195,13 @@ impl<'a> WriteTopic<'a> {
     {
         // read current time in microseconds, as int, convert to f32 then
         // convert to seconds.
+        if self.is_indexed() && data.get_sal_index() != self.get_index() {
+            return Err(SalObjError::new(&format!(
+                "Input data has wrong salIndex. Must be {}, got {}.",
+                self.get_index(),
+                data.get_sal_index(),
+            )));
+        }
         if data.get_private_seq_num() != self.seq_num {
             return Err(SalObjError::new(&format!(
                 "Input data has wrong sequence number. Must be {}, got {}.",
@@ -192,39 +211,84 @@ impl<'a> WriteTopic<'a> {
         }
         self.seq_num += 1;
 
-        if let Ok(data_value) = to_value(data) {
-            if let Value::Record(data_record) = data_value {
-                let record_type = self.get_record_type();
-
-                let key_strategy = SubjectNameStrategy::TopicRecordNameStrategy(
-                    self.schema_registry_topic_name.clone(),
-                    record_type,
-                );
-
-                let data_fields: Vec<(&str, Value)> =
-                    data_record.iter().map(|(k, v)| (&**k, v.clone())).collect();
-
-                match self.encoder.encode(data_fields, key_strategy).await {
-                    Ok(bytes) => match &mut self.producer {
-                        Ok(producer) => {
-                            match producer.send(&producer::Record::from_key_value(
-                                &self.schema_registry_topic_name,
-                                format!("{{ \"name\": \"{}\" }}", self.schema_registry_topic_name),
-                                bytes,
-                            )) {
-                                Ok(_) => Ok(data.get_private_seq_num()),
-                                Err(error) => Err(SalObjError::from_error(error)),
-                            }
-                        }
-                        Err(error) => Err(SalObjError::new(&error.to_string())),
-                    },
-                    Err(error) => Err(SalObjError::from_error(error)),
+        let data_value =
+            to_value(data).map_err(|_| SalObjError::new("Failed to serialize data."))?;
+        let data_record = WriteTopic::adapt_to_schema(data_value, &self.schema)?;
+
+        let key_strategy = self.make_key_strategy();
+
+        let data_fields: Vec<(&str, Value)> =
+            data_record.iter().map(|(k, v)| (&**k, v.clone())).collect();
+
+        match self.encoder.encode(data_fields, key_strategy).await {
+            Ok(bytes) => match &mut self.producer {
+                Ok(producer) => {
+                    match producer.send(&producer::Record::from_key_value(
+                        &self.schema_registry_topic_name,
+                        format!("{{ \"name\": \"{}\" }}", self.schema_registry_topic_name),
+                        bytes,
+                    )) {
+                        Ok(_) => Ok(data.get_private_seq_num()),
+                        Err(error) => Err(SalObjError::from_error(error)),
+                    }
                 }
-            } else {
-                Err(SalObjError::new("Failed to convert value to record."))
-            }
-        } else {
-            Err(SalObjError::new("Failed to serialize data."))
+                Err(error) => Err(SalObjError::new(&error.to_string())),
+            },
+            Err(error) => Err(SalObjError::from_error(error)),
+        }
+    }
+
+    /// Strategy used by the encoder to find the subject of the topic schema.
+    fn make_key_strategy(&self) -> SubjectNameStrategy {
+        SubjectNameStrategy::TopicRecordNameStrategy(
+            self.schema_registry_topic_name.clone(),
+            self.get_record_type(),
+        )
+    }
+
+    /// Verify that the record type leads the encoder to the topic schema.
+    ///
+    /// The encoder derives the subject from the topic name and the record
+    /// type, which must be the subject the schema is registered with, and
+    /// the registered schema must be the record of this topic. Otherwise
+    /// writing fails to find the schema.
+    pub fn verify_record_type(&self) -> SalObjResult<()> {
+        let subject = get_subject(&self.make_key_strategy()).map_err(SalObjError::from_error)?;
+        if subject != self.subject_name {
+            return Err(SalObjError::new(&format!(
+                "Record type {} of {} gives subject {subject}, but the schema is registered as {}.",
+                self.get_record_type(),
+                self.topic_name,
+                self.subject_name,
+            )));
+        }
+
+        match &self.schema {
+            Schema::Record { name, .. } if name.name == self.topic_name => Ok(()),
+            Schema::Record { name, .. } => Err(SalObjError::new(&format!(
+                "Schema registered as {subject} is for record {}, expected {}.",
+                name.name, self.topic_name
+            ))),
+            _ => Err(SalObjError::new(&format!(
+                "Schema registered as {subject} is not a record."
+            ))),
+        }
+    }
+
+    /// Adapt a serialized sample to the topic schema.
+    ///
+    /// Serde serializes plain values, e.g. `Value::Double`, but nullable
+    /// fields are declared as unions, e.g. `["null", "double"]`, and must be
+    /// written as `Value::Union`. Resolving the sample against the schema
+    /// wraps those values in the matching union variant and promotes numeric
+    /// types where the schema asks for it.
+    fn adapt_to_schema(data_value: Value, schema: &Schema) -> SalObjResult<Vec<(String, Value)>> {
+        match data_value
+            .resolve(schema)
+            .map_err(SalObjError::from_error)?
+        {
+            Value::Record(data_record) => Ok(data_record),
+            _ => Err(SalObjError::new("Failed to convert value to record.")),
         }
     }
 }
@@ -233,7 +297,46 @@ impl<'a> WriteTopic<'a> {
 mod tests {
 
     use super::*;
-    use crate::domain::Domain;
+    use crate::{csc::test_csc::topics::scalars::Scalars, domain::Domain};
+
+    #[test]
+    fn verify_record_type() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let write_topic = WriteTopic::new("scalars", &sal_info, &domain);
+
+        assert!(write_topic.verify_record_type().is_ok());
+    }
+
+    #[test]
+    fn adapt_to_schema_union_fields() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let scalars_schema = sal_info
+            .get_topic_info("scalars")
+            .and_then(|topic_info| topic_info.get_schema_ref())
+            .unwrap();
+        let schema_str = serde_json::to_string(scalars_schema)
+            .unwrap()
+            .replace(r#""type":"float""#, r#""type":["null","float"]"#)
+            .replace(r#""type":"double""#, r#""type":["null","double"]"#);
+        let schema = Schema::parse_str(&schema_str).unwrap();
+
+        let mut scalars = Scalars::default();
+        scalars.float0 = 1.5;
+        scalars.double0 = -2.25;
+
+        let data_record = WriteTopic::adapt_to_schema(to_value(scalars).unwrap(), &schema).unwrap();
+
+        assert!(data_record.contains(&(
+            "float0".to_owned(),
+            Value::Union(1, Box::new(Value::Float(1.5)))
+        )));
+        assert!(data_record.contains(&(
+            "double0".to_owned(),
+            Value::Union(1, Box::new(Value::Double(-2.25)))
+        )));
+        assert!(apache_avro::to_avro_datum(&schema, Value::Record(data_record)).is_ok());
+    }
 
     #[test]
     fn test_basics() {
@@ -246,6 +349,26 @@ mod tests {
         assert_eq!(write_topic.get_topic_name(), "scalars");
     }
 
+    #[tokio::test]
+    async fn write_typed_wrong_sal_index() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let mut write_topic = WriteTopic::new("scalars", &sal_info, &domain);
+        let seq_num = write_topic.get_seq_num();
+
+        let scalars = Scalars::default()
+            .with_private_seq_num(seq_num)
+            .with_sal_index(2);
+
+        let error = write_topic.write_typed(&scalars).await.unwrap_err();
+
+        assert_eq!(
+            error.get_error_message(),
+            "Input data has wrong salIndex. Must be 1, got 2."
+        );
+        assert_eq!(write_topic.get_seq_num(), seq_num);
+    }
+
     #[test]
     #[should_panic]
     fn new_with_bad_topic_name() {
@@ -254,4 +377,14 @@ mod tests {
 
         WriteTopic::new("inexistentTopic", &sal_info, &domain);
     }
+
+    #[test]
+    fn share_domain_encoder() {
+        let domain = Domain::new();
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let scalars = WriteTopic::new("scalars", &sal_info, &domain);
+        let arrays = WriteTopic::new("arrays", &sal_info, &domain);
+
+        assert!(Arc::ptr_eq(&scalars.encoder, &arrays.encoder));
+    }
 }
@@ -0,0 +1,328 @@
+//! Utilities to convert between avro values and other representations.
+
+use crate::error::errors::{SalObjError, SalObjResult};
+use apache_avro::{types::Value, Schema};
+
+/// Convert a json value into an avro value of the given schema.
+///
+/// Numbers are coerced into the numeric type declared in the schema, as
+/// long as they fit. Arrays are converted item by item, nested records field
+/// by field and unions resolve to the first variant that accepts the value.
+/// Other avro types (maps, enums, fixed, ...) are not supported and return
+/// an error.
+pub fn json_to_avro(value: &serde_json::Value, schema: &Schema) -> SalObjResult<Value> {
+    let avro_value = match (schema, value) {
+        (Schema::Null, serde_json::Value::Null) => Some(Value::Null),
+        (Schema::Boolean, serde_json::Value::Bool(value)) => Some(Value::Boolean(*value)),
+        (Schema::Int, serde_json::Value::Number(value)) => value
+            .as_i64()
+            .and_then(|value| i32::try_from(value).ok())
+            .map(Value::Int),
+        (Schema::Long, serde_json::Value::Number(value)) => value.as_i64().map(Value::Long),
+        (Schema::Float, serde_json::Value::Number(value)) => {
+            value.as_f64().map(|value| Value::Float(value as f32))
+        }
+        (Schema::Double, serde_json::Value::Number(value)) => value.as_f64().map(Value::Double),
+        (Schema::String, serde_json::Value::String(value)) => Some(Value::String(value.to_owned())),
+        (Schema::Array(items), serde_json::Value::Array(values)) => Some(Value::Array(
+            values
+                .iter()
+                .map(|value| json_to_avro(value, items))
+                .collect::<SalObjResult<Vec<Value>>>()?,
+        )),
+        (Schema::Record { name, fields, .. }, serde_json::Value::Object(values)) => {
+            if let Some(field_name) = values
+                .keys()
+                .find(|field_name| !fields.iter().any(|field| field.name == **field_name))
+            {
+                return Err(SalObjError::new(&format!(
+                    "Field {field_name} not in record {}.",
+                    name.name
+                )));
+            }
+            Some(Value::Record(
+                fields
+                    .iter()
+                    .map(|field| match values.get(&field.name) {
+                        Some(value) => {
+                            Ok((field.name.to_owned(), json_to_avro(value, &field.schema)?))
+                        }
+                        None => Err(SalObjError::new(&format!(
+                            "Field {} of record {} is not set.",
+                            field.name, name.name
+                        ))),
+                    })
+                    .collect::<SalObjResult<Vec<(String, Value)>>>()?,
+            ))
+        }
+        (Schema::Union(union_schema), value) => union_schema
+            .variants()
+            .iter()
+            .enumerate()
+            .find_map(|(index, variant)| {
+                json_to_avro(value, variant)
+                    .ok()
+                    .map(|value| Value::Union(index as u32, Box::new(value)))
+            }),
+        (
+            Schema::Null
+            | Schema::Boolean
+            | Schema::Int
+            | Schema::Long
+            | Schema::Float
+            | Schema::Double
+            | Schema::String
+            | Schema::Array(_)
+            | Schema::Record { .. },
+            _,
+        ) => None,
+        (schema, _) => {
+            return Err(SalObjError::new(&format!(
+                "Unsupported avro type {schema:?}. Only primitive types, arrays, records \
+                and unions are supported."
+            )))
+        }
+    };
+
+    avro_value.ok_or_else(|| {
+        SalObjError::new(&format!(
+            "Cannot convert {value} into avro type {:?}.",
+            schema
+        ))
+    })
+}
+
+/// Convert an avro value into a json value.
+///
+/// Unions are replaced by the value they hold, records and maps become json
+/// objects and arrays json arrays. Bytes and fixed values become arrays of
+/// numbers, enums their symbol and logical types their underlying value.
+/// Floating point values that cannot be represented in json (NaN and
+/// infinity) become `null`.
+pub fn avro_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Boolean(value) => serde_json::Value::Bool(*value),
+        Value::Int(value) | Value::Date(value) | Value::TimeMillis(value) => {
+            serde_json::Value::from(*value)
+        }
+        Value::Long(value)
+        | Value::TimeMicros(value)
+        | Value::TimestampMillis(value)
+        | Value::TimestampMicros(value) => serde_json::Value::from(*value),
+        Value::Float(value) => float_to_json(*value as f64),
+        Value::Double(value) => float_to_json(*value),
+        Value::String(value) | Value::Enum(_, value) => serde_json::Value::String(value.to_owned()),
+        Value::Bytes(values) | Value::Fixed(_, values) => {
+            serde_json::Value::from(values.to_owned())
+        }
+        Value::Uuid(value) => serde_json::Value::String(value.to_string()),
+        Value::Union(_, value) => avro_to_json(value),
+        Value::Array(values) => serde_json::Value::Array(values.iter().map(avro_to_json).collect()),
+        Value::Map(values) => serde_json::Value::Object(
+            values
+                .iter()
+                .map(|(key, value)| (key.to_owned(), avro_to_json(value)))
+                .collect(),
+        ),
+        Value::Record(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(field, value)| (field.to_owned(), avro_to_json(value)))
+                .collect(),
+        ),
+        _ => serde_json::Value::Null,
+    }
+}
+
+fn float_to_json(value: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(value)
+        .map(serde_json::Value::Number)
+        .unwrap_or(serde_json::Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{csc::test_csc::topics::scalars::Scalars, topics::base_sal_topic::BaseSALTopic};
+    use apache_avro::to_value;
+    use serde_json::json;
+
+    #[test]
+    fn json_to_avro_primitives() {
+        assert_eq!(
+            json_to_avro(&json!(true), &Schema::Boolean).unwrap(),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            json_to_avro(&json!(3), &Schema::Int).unwrap(),
+            Value::Int(3)
+        );
+        assert_eq!(
+            json_to_avro(&json!(3), &Schema::Long).unwrap(),
+            Value::Long(3)
+        );
+        assert_eq!(
+            json_to_avro(&json!(1.5), &Schema::Float).unwrap(),
+            Value::Float(1.5)
+        );
+        assert_eq!(
+            json_to_avro(&json!(2), &Schema::Double).unwrap(),
+            Value::Double(2.0)
+        );
+        assert_eq!(
+            json_to_avro(&json!("abc"), &Schema::String).unwrap(),
+            Value::String("abc".to_owned())
+        );
+    }
+
+    #[test]
+    fn json_to_avro_array() {
+        let schema = Schema::parse_str(r#"{"type": "array", "items": "int"}"#).unwrap();
+
+        assert_eq!(
+            json_to_avro(&json!([1, 2]), &schema).unwrap(),
+            Value::Array(vec![Value::Int(1), Value::Int(2)])
+        );
+    }
+
+    #[test]
+    fn json_to_avro_union() {
+        let schema = Schema::parse_str(r#"["null", "double"]"#).unwrap();
+
+        assert_eq!(
+            json_to_avro(&json!(null), &schema).unwrap(),
+            Value::Union(0, Box::new(Value::Null))
+        );
+        assert_eq!(
+            json_to_avro(&json!(1.5), &schema).unwrap(),
+            Value::Union(1, Box::new(Value::Double(1.5)))
+        );
+    }
+
+    #[test]
+    fn json_to_avro_nested_record() {
+        let schema = Schema::parse_str(
+            r#"{
+                "type": "record",
+                "name": "outer",
+                "fields": [
+                    {"name": "name", "type": "string"},
+                    {
+                        "name": "position",
+                        "type": {
+                            "type": "record",
+                            "name": "position",
+                            "fields": [
+                                {"name": "x", "type": "double"},
+                                {"name": "y", "type": "double"}
+                            ]
+                        }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let value = json_to_avro(
+            &json!({"name": "target", "position": {"x": 1.0, "y": 2}}),
+            &schema,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            Value::Record(vec![
+                ("name".to_owned(), Value::String("target".to_owned())),
+                (
+                    "position".to_owned(),
+                    Value::Record(vec![
+                        ("x".to_owned(), Value::Double(1.0)),
+                        ("y".to_owned(), Value::Double(2.0)),
+                    ])
+                ),
+            ])
+        );
+        assert!(value.validate(&schema));
+        assert!(json_to_avro(&json!({"name": "target", "position": {"x": 1.0}}), &schema).is_err());
+    }
+
+    #[test]
+    fn avro_to_json_values() {
+        let value = Value::Record(vec![
+            ("flag".to_owned(), Value::Boolean(true)),
+            ("count".to_owned(), Value::Long(3)),
+            ("ratio".to_owned(), Value::Float(0.5)),
+            ("invalid".to_owned(), Value::Double(f64::NAN)),
+            (
+                "optional".to_owned(),
+                Value::Union(1, Box::new(Value::Double(1.5))),
+            ),
+            (
+                "values".to_owned(),
+                Value::Array(vec![Value::Int(1), Value::Int(2)]),
+            ),
+            (
+                "position".to_owned(),
+                Value::Record(vec![("x".to_owned(), Value::Double(1.0))]),
+            ),
+        ]);
+
+        assert_eq!(
+            avro_to_json(&value),
+            json!({
+                "flag": true,
+                "count": 3,
+                "ratio": 0.5,
+                "invalid": null,
+                "optional": 1.5,
+                "values": [1, 2],
+                "position": {"x": 1.0}
+            })
+        );
+    }
+
+    #[test]
+    fn scalars_to_json() {
+        let mut scalars = Scalars::default().with_sal_index(1);
+        scalars.int0 = 3;
+        scalars.string0 = "json".to_owned();
+
+        let json = avro_to_json(&to_value(scalars).unwrap());
+
+        let object = json.as_object().unwrap();
+        for key in [
+            "salIndex",
+            "private_seqNum",
+            "boolean0",
+            "int0",
+            "longLong0",
+            "unsignedInt0",
+            "double0",
+            "string0",
+        ] {
+            assert!(object.contains_key(key), "{key} not in {json}");
+        }
+        assert_eq!(object["salIndex"], 1);
+        assert_eq!(object["int0"], 3);
+        assert_eq!(object["string0"], "json");
+    }
+
+    #[test]
+    fn json_to_avro_unsupported_type() {
+        let schema = Schema::parse_str(r#"{"type": "map", "values": "int"}"#).unwrap();
+
+        let error = json_to_avro(&json!({"a": 1}), &schema).unwrap_err();
+
+        assert!(error
+            .get_error_message()
+            .starts_with("Unsupported avro type"));
+    }
+
+    #[test]
+    fn json_to_avro_bad_type() {
+        assert!(json_to_avro(&json!("abc"), &Schema::Int).is_err());
+        assert!(json_to_avro(&json!(1.5), &Schema::Int).is_err());
+        assert!(json_to_avro(&json!(i64::MAX), &Schema::Int).is_err());
+    }
+}
@@ -1,7 +1,12 @@
 use std::fmt;
 
-use crate::generics::ackcmd::AckCmd;
+use apache_avro::types::{Record, Value};
+use chrono::Utc;
+
+use crate::error::errors::{SalObjError, SalObjResult};
+use crate::generics::ackcmd::{AckCmd, AckCmdRecord};
 use crate::sal_enums::{self, SalRetCode};
+use crate::sal_info::SalInfo;
 use crate::topics::base_sal_topic::BaseSALTopic;
 
 #[derive(Debug, Clone)]
@@ -21,6 +26,8 @@ pub struct CommandAck {
     timeout: std::time::Duration,
     /// Sequence number of the command issued.
     seq_num: i32,
+    /// Time between issuing the command and receiving the final ack.
+    duration: Option<std::time::Duration>,
 }
 
 impl fmt::Display for CommandAck {
@@ -40,6 +47,7 @@ impl Default for CommandAck {
             cmdtype: 0,
             timeout: std::time::Duration::from_secs(0),
             seq_num: 0,
+            duration: None,
         }
     }
 }
@@ -66,16 +74,87 @@ impl CommandAck {
         }
     }
 
+    /// Make the ackcmd topic for this acknowledgement.
+    ///
+    /// The ackcmd `timeout` is in seconds, see [CommandAck::to_ackcmd_record].
     pub fn to_ackcmd(&self) -> AckCmd {
-        AckCmd::new(
-            self.get_ack(),
-            self.get_error(),
-            self.get_result(),
-            self.get_identity(),
-            self.get_origin(),
-            self.get_cmdtype(),
-            self.get_timeout().as_secs_f64(),
-        )
+        AckCmd::from(&self.to_ackcmd_record())
+    }
+
+    /// Make a command acknowledgement from an ackcmd topic.
+    pub fn from_ackcmd(ackcmd: &AckCmd) -> CommandAck {
+        CommandAck::from_ackcmd_record(&AckCmdRecord::from(ackcmd))
+    }
+
+    /// Make the ackcmd record for this acknowledgement, ready to publish.
+    ///
+    /// Besides the ack fields, `salIndex`, the timestamps and the private
+    /// identity, origin and sequence number are filled in, as done by
+    /// [ControllerCommandAck](crate::topics::controller_command_ack::ControllerCommandAck).
+    pub fn to_record<'s>(&self, sal_info: &'s SalInfo) -> SalObjResult<Record<'s>> {
+        let schema = sal_info
+            .get_topic_info("ackcmd")
+            .and_then(|topic_info| topic_info.get_schema_ref())
+            .ok_or_else(|| {
+                SalObjError::new(&format!("No ackcmd topic for {}.", sal_info.get_name()))
+            })?;
+
+        let mut record = self.to_ackcmd_record().to_record(schema)?;
+
+        let timestamp = Value::Double(Utc::now().timestamp_micros() as f64 * 1e-6);
+        record.put("private_sndStamp", timestamp.clone());
+        record.put("private_efdStamp", timestamp.clone());
+        record.put("private_kafkaStamp", timestamp);
+        record.put("private_rcvStamp", Value::Double(0.0));
+        record.put("private_revCode", Value::String("Not Set".to_owned()));
+        record.put("private_identity", Value::String(self.identity.to_owned()));
+        record.put("private_origin", Value::Int(self.origin));
+        record.put("private_seqNum", Value::Int(self.seq_num));
+        if sal_info.is_indexed() {
+            record.put("salIndex", Value::Int(sal_info.get_index() as i32));
+        }
+
+        Ok(record)
+    }
+
+    /// Get the fields of the ackcmd topic for this acknowledgement.
+    ///
+    /// The timeout is written in seconds as a double, the type of the ackcmd
+    /// `timeout` field.
+    pub fn to_ackcmd_record(&self) -> AckCmdRecord {
+        AckCmdRecord {
+            ack: self.get_ack(),
+            error: self.get_error(),
+            result: self.get_result().to_owned(),
+            identity: self.get_identity().to_owned(),
+            origin: self.get_origin(),
+            cmdtype: self.get_cmdtype(),
+            timeout: self.get_timeout().as_secs_f64(),
+            seq_num: self.get_seq_num(),
+        }
+    }
+
+    /// Make a command acknowledgement from the fields of an ackcmd sample.
+    pub fn from_ackcmd_record(ackcmd_record: &AckCmdRecord) -> CommandAck {
+        CommandAck {
+            ack: sal_enums::get_ackcmd_code(Some(&Value::Int(ackcmd_record.ack))),
+            error: ackcmd_record.error as isize,
+            result: ackcmd_record.result.to_owned(),
+            identity: ackcmd_record.identity.to_owned(),
+            origin: ackcmd_record.origin,
+            cmdtype: ackcmd_record.cmdtype,
+            timeout: CommandAck::timeout_from_secs(ackcmd_record.timeout),
+            seq_num: ackcmd_record.seq_num,
+            duration: None,
+        }
+    }
+
+    /// Convert an ackcmd timeout, in seconds, to a duration.
+    ///
+    /// Negative and NaN timeouts mean no timeout and are read as zero, and
+    /// timeouts too large for a duration saturate.
+    fn timeout_from_secs(timeout: f64) -> std::time::Duration {
+        std::time::Duration::try_from_secs_f64(timeout.max(0.0)).unwrap_or(std::time::Duration::MAX)
     }
 
     pub fn invalid_command(result: &str) -> CommandAck {
@@ -133,6 +212,39 @@ impl CommandAck {
         }
     }
 
+    pub fn make_noperm<T>(cmd: T, result: &str) -> CommandAck
+    where
+        T: BaseSALTopic,
+    {
+        CommandAck {
+            ack: SalRetCode::CmdNoperm,
+            error: 0,
+            result: result.to_owned(),
+            identity: cmd.get_private_identity().to_owned(),
+            origin: cmd.get_private_origin(),
+            timeout: std::time::Duration::new(0, 0),
+            seq_num: cmd.get_private_seq_num(),
+            ..Default::default()
+        }
+    }
+
+    /// Make the acknowledgement of a command aborted before it was executed.
+    pub fn make_aborted<T>(cmd: T, result: &str) -> CommandAck
+    where
+        T: BaseSALTopic,
+    {
+        CommandAck {
+            ack: SalRetCode::CmdAborted,
+            error: 1,
+            result: result.to_owned(),
+            identity: cmd.get_private_identity().to_owned(),
+            origin: cmd.get_private_origin(),
+            timeout: std::time::Duration::new(0, 0),
+            seq_num: cmd.get_private_seq_num(),
+            ..Default::default()
+        }
+    }
+
     /// Is the acknowledgement final?
     ///
     /// No more acks should be expected after this.
@@ -165,6 +277,26 @@ impl CommandAck {
         self.seq_num
     }
 
+    /// Set the type of the command being acknowledged, see
+    /// [SalInfo::get_command_type].
+    pub fn with_cmdtype(mut self, cmdtype: i32) -> Self {
+        self.cmdtype = cmdtype;
+        self
+    }
+
+    /// Set the time it took for the command to finish.
+    pub fn with_duration(mut self, duration: std::time::Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Get the time between issuing the command and receiving the final
+    /// ack, or `None` if the command is not finished or was not issued by a
+    /// remote.
+    pub fn get_duration(&self) -> Option<std::time::Duration> {
+        self.duration
+    }
+
     pub fn get_ack(&self) -> i32 {
         self.ack.clone() as i32
     }
@@ -183,4 +315,134 @@ impl CommandAck {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_duration() {
+        let command_ack = CommandAck::default();
+
+        assert_eq!(command_ack.get_duration(), None);
+        assert_eq!(
+            command_ack
+                .with_duration(std::time::Duration::from_secs(2))
+                .get_duration(),
+            Some(std::time::Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn with_cmdtype() {
+        let command_ack = CommandAck::default().with_cmdtype(4);
+
+        assert_eq!(command_ack.get_cmdtype(), 4);
+        assert_eq!(command_ack.to_ackcmd_record().cmdtype, 4);
+    }
+
+    #[test]
+    fn ackcmd_record_round_trip() {
+        let command_ack = CommandAck::new(
+            SalRetCode::CmdFailed,
+            2,
+            "Failed.".to_owned(),
+            "unit@test".to_owned(),
+            123,
+            std::time::Duration::from_secs(3),
+            321,
+        );
+
+        let ackcmd_record = command_ack.to_ackcmd_record();
+        let round_trip = CommandAck::from_ackcmd_record(&ackcmd_record);
+
+        assert_eq!(round_trip.get_ack_enum(), &SalRetCode::CmdFailed);
+        assert_eq!(round_trip.get_error(), 2);
+        assert_eq!(round_trip.get_result(), "Failed.");
+        assert_eq!(round_trip.get_identity(), "unit@test");
+        assert_eq!(round_trip.get_origin(), 123);
+        assert_eq!(round_trip.get_timeout(), std::time::Duration::from_secs(3));
+        assert_eq!(round_trip.get_seq_num(), 321);
+    }
+
+    #[test]
+    fn ackcmd_timeout_round_trip() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let timeout = std::time::Duration::from_secs(60);
+        let command_ack = CommandAck::make_in_progress(
+            AckCmd::default().with_private_seq_num(321),
+            timeout,
+            "Waiting.",
+        );
+
+        let record = command_ack.to_record(&sal_info).unwrap();
+        let ackcmd_record = AckCmdRecord::from_value(&Value::from(record)).unwrap();
+
+        assert_eq!(ackcmd_record.timeout, 60.0);
+        assert_eq!(
+            CommandAck::from_ackcmd_record(&ackcmd_record).get_timeout(),
+            timeout
+        );
+        assert_eq!(
+            CommandAck::from_ackcmd(&command_ack.to_ackcmd()).get_timeout(),
+            timeout
+        );
+    }
+
+    #[test]
+    fn timeout_from_secs() {
+        assert_eq!(
+            CommandAck::timeout_from_secs(1.5),
+            std::time::Duration::from_millis(1500)
+        );
+        assert_eq!(
+            CommandAck::timeout_from_secs(-1.0),
+            std::time::Duration::ZERO
+        );
+        assert_eq!(
+            CommandAck::timeout_from_secs(f64::NAN),
+            std::time::Duration::ZERO
+        );
+        assert_eq!(
+            CommandAck::timeout_from_secs(f64::INFINITY),
+            std::time::Duration::MAX
+        );
+    }
+
+    #[test]
+    fn to_record() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let command_ack = CommandAck::new(
+            SalRetCode::CmdComplete,
+            0,
+            "Done.".to_owned(),
+            "unit@test".to_owned(),
+            123,
+            std::time::Duration::from_secs(3),
+            321,
+        );
+
+        let record = command_ack.to_record(&sal_info).unwrap();
+
+        let field = |name: &str| {
+            record
+                .fields
+                .iter()
+                .find(|(field_name, _)| field_name == name)
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+        assert_eq!(field("salIndex"), Value::Int(1));
+        assert_eq!(field("private_seqNum"), Value::Int(321));
+        assert_eq!(field("private_origin"), Value::Int(123));
+
+        let ackcmd_record = AckCmdRecord::from_value(&Value::from(record)).unwrap();
+        let round_trip = CommandAck::from_ackcmd_record(&ackcmd_record);
+
+        assert_eq!(round_trip.get_ack_enum(), &SalRetCode::CmdComplete);
+        assert_eq!(round_trip.get_error(), 0);
+        assert_eq!(round_trip.get_result(), "Done.");
+        assert_eq!(round_trip.get_identity(), "unit@test");
+        assert_eq!(round_trip.get_origin(), 123);
+        assert_eq!(round_trip.get_timeout(), std::time::Duration::from_secs(3));
+        assert_eq!(round_trip.get_seq_num(), 321);
+    }
+}
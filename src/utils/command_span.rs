@@ -0,0 +1,312 @@
+//! Tracing spans around command execution.
+//!
+//! Only available with the `tracing` feature. The remote side opens a
+//! `remote_command` span when a command is issued and closes it when the
+//! final acknowledgement arrives, the controller side a `controller_command`
+//! span around dispatching the command and acknowledging it. Both carry the
+//! component, command and sequence number, and the result of the command.
+//!
+//! The controller span is opened when the command is received, by
+//! [ControllerCommand](crate::topics::controller_command::ControllerCommand),
+//! and closed when its final acknowledgement is written, whichever way the
+//! command is dispatched. Commands in flight are kept in a registry keyed by
+//! component, command type, sequence number and origin of the command.
+//!
+//! When both ends run in the same process the controller span follows from
+//! the remote span. Otherwise the spans are matched by component, command
+//! and sequence number.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use tracing::{field::Empty, Span};
+
+use crate::utils::command_ack::CommandAck;
+
+/// Identify a command in flight.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommandKey {
+    component: String,
+    cmdtype: i32,
+    seq_num: i32,
+    origin: i32,
+}
+
+impl CommandKey {
+    pub fn new(component: &str, cmdtype: i32, seq_num: i32, origin: i32) -> CommandKey {
+        CommandKey {
+            component: component.to_owned(),
+            cmdtype,
+            seq_num,
+            origin,
+        }
+    }
+
+    /// Key of the command an acknowledgement is for.
+    pub fn from_ack(component: &str, command_ack: &CommandAck) -> CommandKey {
+        CommandKey::new(
+            component,
+            command_ack.get_cmdtype(),
+            command_ack.get_seq_num(),
+            command_ack.get_origin(),
+        )
+    }
+}
+
+/// Spans of the commands issued by remotes in this process, waiting for
+/// their final acknowledgement.
+static REMOTE_SPANS: LazyLock<Mutex<HashMap<CommandKey, Span>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Spans of the commands received by controllers in this process, waiting
+/// for their final acknowledgement.
+static CONTROLLER_SPANS: LazyLock<Mutex<HashMap<CommandKey, Span>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Make the span of a command issued by a remote.
+///
+/// The sequence number is recorded once the command is written.
+pub fn remote_command_span(component: &str, command: &str) -> Span {
+    tracing::info_span!(
+        "remote_command",
+        component,
+        command,
+        seq_num = Empty,
+        result = Empty
+    )
+}
+
+/// Make the span of a command dispatched by a controller.
+pub fn controller_command_span(component: &str, command: &str, seq_num: i32) -> Span {
+    tracing::info_span!(
+        "controller_command",
+        component,
+        command,
+        seq_num,
+        result = Empty
+    )
+}
+
+/// Keep the span of a command issued by a remote until
+/// [finish_remote_command], so a controller in the same process can link
+/// to it.
+pub fn start_remote_command(key: CommandKey, span: &Span) {
+    if let Ok(mut spans) = REMOTE_SPANS.lock() {
+        spans.insert(key, span.clone());
+    }
+}
+
+/// Forget the span of a command issued by a remote.
+pub fn finish_remote_command(key: &CommandKey) {
+    if let Ok(mut spans) = REMOTE_SPANS.lock() {
+        spans.remove(key);
+    }
+}
+
+/// Open the span of a command received by a controller.
+///
+/// The span follows from the span of the remote that issued the command, if
+/// it runs in this process, and stays open until [record_controller_ack]
+/// records the final acknowledgement.
+pub fn start_controller_command(key: CommandKey, command: &str) -> Span {
+    let span = controller_command_span(&key.component, command, key.seq_num);
+    if let Some(remote_span) = REMOTE_SPANS
+        .lock()
+        .ok()
+        .and_then(|spans| spans.get(&key).cloned())
+    {
+        span.follows_from(&remote_span);
+    }
+    if let Ok(mut spans) = CONTROLLER_SPANS.lock() {
+        spans.insert(key, span.clone());
+    }
+    span
+}
+
+/// Get the span of a command received by a controller, if still open.
+pub fn get_controller_command(key: &CommandKey) -> Option<Span> {
+    CONTROLLER_SPANS
+        .lock()
+        .ok()
+        .and_then(|spans| spans.get(key).cloned())
+}
+
+/// Record an acknowledgement written by a controller in the span of its
+/// command, closing the span if the acknowledgement is final.
+pub fn record_controller_ack(component: &str, command_ack: &CommandAck) {
+    let key = CommandKey::from_ack(component, command_ack);
+    if let Ok(mut spans) = CONTROLLER_SPANS.lock() {
+        if let Some(span) = spans.get(&key) {
+            record_ack(span, command_ack);
+        }
+        if command_ack.is_final() {
+            spans.remove(&key);
+        }
+    }
+}
+
+/// Record the sequence number of the command in `span`.
+pub fn record_seq_num(span: &Span, seq_num: i32) {
+    span.record("seq_num", seq_num);
+}
+
+/// Record the acknowledgement code of the command in `span`.
+pub fn record_ack(span: &Span, command_ack: &CommandAck) {
+    span.record(
+        "result",
+        format!("{:?}", command_ack.get_ack_enum()).as_str(),
+    );
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    //! A minimal subscriber recording spans, to test instrumented code.
+
+    use std::{
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    /// Span recorded by [SpanRecorder], with its fields as strings.
+    #[derive(Debug, Clone, Default)]
+    pub struct RecordedSpan {
+        pub name: String,
+        pub fields: Vec<(String, String)>,
+        /// Position, in [SpanRecorder::get_spans], of the spans this one
+        /// follows from.
+        pub follows_from: Vec<usize>,
+    }
+
+    impl RecordedSpan {
+        pub fn get_field(&self, name: &str) -> Option<&str> {
+            self.fields
+                .iter()
+                .rev()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    impl Visit for RecordedSpan {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields
+                .push((field.name().to_owned(), value.to_owned()));
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.fields
+                .push((field.name().to_owned(), format!("{value:?}")));
+        }
+    }
+
+    /// Subscriber that keeps every span created while it is the default.
+    #[derive(Clone, Default)]
+    pub struct SpanRecorder {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+    }
+
+    impl SpanRecorder {
+        pub fn get_spans(&self) -> Vec<RecordedSpan> {
+            self.spans.lock().unwrap().clone()
+        }
+    }
+
+    impl Subscriber for SpanRecorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut span = RecordedSpan {
+                name: attributes.metadata().name().to_owned(),
+                ..Default::default()
+            };
+            attributes.record(&mut span);
+
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span);
+            // Span ids must be non-zero, so use the position in the list
+            // plus one.
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &Id, values: &Record<'_>) {
+            if let Some(span) = self
+                .spans
+                .lock()
+                .unwrap()
+                .get_mut(id.into_u64() as usize - 1)
+            {
+                values.record(span);
+            }
+        }
+
+        fn record_follows_from(&self, span: &Id, follows: &Id) {
+            if let Some(span) = self
+                .spans
+                .lock()
+                .unwrap()
+                .get_mut(span.into_u64() as usize - 1)
+            {
+                span.follows_from.push(follows.into_u64() as usize - 1);
+            }
+        }
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generics::empty_topic::EmptyTopic;
+    use crate::topics::base_sal_topic::BaseSALTopic;
+    use crate::utils::command_span::testing::SpanRecorder;
+
+    #[test]
+    fn controller_command_follows_remote_command() {
+        let recorder = SpanRecorder::default();
+        let _default = tracing::subscriber::set_default(recorder.clone());
+
+        let key = CommandKey::new("Test", 7, 41, 1234);
+        let remote_span = remote_command_span("Test", "command_wait");
+        start_remote_command(key.clone(), &remote_span);
+
+        let controller_span = start_controller_command(key.clone(), "command_wait");
+        drop(controller_span);
+
+        let command = EmptyTopic::default()
+            .with_private_seq_num(41)
+            .with_private_origin(1234);
+        let in_progress = CommandAck::make_in_progress(
+            command.clone(),
+            std::time::Duration::from_secs(1),
+            "Waiting.",
+        )
+        .with_cmdtype(7);
+        record_controller_ack("Test", &in_progress);
+        assert!(get_controller_command(&key).is_some());
+
+        record_controller_ack("Test", &CommandAck::make_complete(command).with_cmdtype(7));
+        assert!(get_controller_command(&key).is_none());
+        finish_remote_command(&key);
+
+        let spans = recorder.get_spans();
+        assert_eq!(spans[1].name, "controller_command");
+        assert_eq!(spans[1].follows_from, vec![0]);
+        assert_eq!(spans[1].get_field("seq_num"), Some("41"));
+        assert_eq!(spans[1].get_field("result"), Some("CmdComplete"));
+    }
+}
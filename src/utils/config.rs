@@ -0,0 +1,173 @@
+//! Utilities to read CSC configuration files.
+
+use std::{env, fs, path::Path};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::errors::{SalObjError, SalObjResult},
+    generics::large_file_object_available::LargeFileObjectAvailable,
+    utils::lfa,
+};
+
+/// Configuration file read when no configuration override is given.
+const DEFAULT_CONFIGURATION_FILE: &str = "_init.yaml";
+
+/// Read a YAML configuration file and parse it into a configuration struct.
+///
+/// The file is named by the configuration override and is read from the
+/// directory defined by the LSST_CONFIG_PATH environment variable. If the
+/// override is empty `_init.yaml` is read instead. The override must be a
+/// plain file name: absolute paths and overrides with a path separator or
+/// `..` are rejected, so only files in LSST_CONFIG_PATH can be read.
+pub fn read_configuration<C: DeserializeOwned>(configuration_override: &str) -> SalObjResult<C> {
+    if Path::new(configuration_override).is_absolute()
+        || configuration_override.contains(['/', '\\'])
+        || configuration_override.contains("..")
+    {
+        return Err(SalObjError::new(&format!(
+            "Invalid configuration override {configuration_override}; must be a file name \
+            in LSST_CONFIG_PATH."
+        )));
+    }
+
+    let config_dir = env::var("LSST_CONFIG_PATH")
+        .map_err(|_| SalObjError::new("Environment variable LSST_CONFIG_PATH not defined."))?;
+
+    let file_name = if configuration_override.is_empty() {
+        DEFAULT_CONFIGURATION_FILE
+    } else {
+        configuration_override
+    };
+    let config_file = Path::new(&config_dir).join(file_name);
+
+    let config = fs::read_to_string(&config_file).map_err(|error| {
+        SalObjError::new(&format!(
+            "Failed to read configuration file {}: {error}",
+            config_file.display()
+        ))
+    })?;
+
+    serde_yaml::from_str(&config).map_err(|error| {
+        SalObjError::new(&format!(
+            "Failed to parse configuration file {}: {error}",
+            config_file.display()
+        ))
+    })
+}
+
+/// List the configuration overrides available in a configuration directory.
+///
+/// These are the YAML files (`.yaml` or `.yml`) in the directory, sorted by
+/// name. Files starting with `_`, like `_init.yaml`, are always read and are
+/// not overrides, so they are left out.
+pub fn list_configuration_overrides(config_dir: &Path) -> SalObjResult<Vec<String>> {
+    let entries = fs::read_dir(config_dir).map_err(|error| {
+        SalObjError::new(&format!(
+            "Failed to read configuration directory {}: {error}",
+            config_dir.display()
+        ))
+    })?;
+
+    let mut overrides: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|file_name| {
+            !file_name.starts_with('_')
+                && (file_name.ends_with(".yaml") || file_name.ends_with(".yml"))
+        })
+        .collect();
+    overrides.sort();
+
+    Ok(overrides)
+}
+
+/// Fetch a YAML configuration announced as a Large File Object and parse
+/// it into a configuration struct.
+///
+/// The object is downloaded from the LFA URL and its contents verified
+/// against the `checkSum` field before parsing.
+pub async fn read_configuration_from_lfa<C: DeserializeOwned>(
+    large_file_object: &LargeFileObjectAvailable,
+) -> SalObjResult<C> {
+    let url = large_file_object.get_url();
+    let config = lfa::fetch_verified(&url, &large_file_object.get_check_sum()).await?;
+
+    serde_yaml::from_slice(&config)
+        .map_err(|error| SalObjError::new(&format!("Failed to parse configuration {url}: {error}")))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::testing::with_env_vars;
+
+    #[derive(Debug, Deserialize)]
+    struct TestConfig {
+        name: String,
+        wait_time: f64,
+        enabled: bool,
+    }
+
+    /// Configuration directory used by the tests.
+    const TEST_CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/config/");
+
+    #[test]
+    fn test_read_configuration() {
+        let config: TestConfig =
+            with_env_vars(&[("LSST_CONFIG_PATH", Some(TEST_CONFIG_PATH))], || {
+                read_configuration("test_config.yaml")
+            })
+            .unwrap();
+
+        assert_eq!(config.name, "test");
+        assert_eq!(config.wait_time, 1.5);
+        assert!(config.enabled);
+    }
+
+    #[test]
+    fn test_list_configuration_overrides() {
+        let overrides = list_configuration_overrides(Path::new(TEST_CONFIG_PATH)).unwrap();
+
+        assert!(overrides.contains(&"test_config.yaml".to_owned()));
+        assert!(overrides
+            .iter()
+            .all(|file_name| !file_name.starts_with('_')));
+    }
+
+    #[test]
+    fn test_list_configuration_overrides_missing_dir() {
+        assert!(list_configuration_overrides(Path::new("/no/such/config/dir")).is_err());
+    }
+
+    #[test]
+    fn test_read_configuration_missing_file() {
+        let config = with_env_vars(&[("LSST_CONFIG_PATH", Some(TEST_CONFIG_PATH))], || {
+            read_configuration::<TestConfig>("bad_config.yaml")
+        });
+
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_read_configuration_rejects_paths() {
+        for configuration_override in [
+            "../../etc/x.yaml",
+            "/etc/x.yaml",
+            "subdir/test_config.yaml",
+            "..",
+        ] {
+            let error = read_configuration::<TestConfig>(configuration_override).unwrap_err();
+
+            assert_eq!(
+                error.get_error_message(),
+                format!(
+                    "Invalid configuration override {configuration_override}; must be a file \
+                    name in LSST_CONFIG_PATH."
+                )
+            );
+        }
+    }
+}
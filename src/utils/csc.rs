@@ -118,6 +118,33 @@ pub fn compute_state_transitions(
     }
 }
 
+/// List the states a component can go to from `state`, each with the
+/// command that effects the transition.
+///
+/// A component in Fault can only go to Standby; an invalid state has no
+/// transitions.
+pub fn valid_transitions_from(state: State) -> Vec<(State, String)> {
+    let transitions: &[(State, &str)] = match state {
+        State::Offline => &[(State::Standby, "command_enterControl")],
+        State::Standby => &[
+            (State::Disabled, "command_start"),
+            (State::Offline, "command_exitControl"),
+        ],
+        State::Disabled => &[
+            (State::Enabled, "command_enable"),
+            (State::Standby, "command_standby"),
+        ],
+        State::Enabled => &[(State::Disabled, "command_disable")],
+        State::Fault => &[(State::Standby, "command_standby")],
+        State::Invalid => &[],
+    };
+
+    transitions
+        .iter()
+        .map(|(next_state, command)| (*next_state, command.to_string()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -196,4 +223,83 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn compute_state_transitions_offline_enabled_order() {
+        let state_transitions = compute_state_transitions(State::Offline, State::Enabled).unwrap();
+
+        assert_eq!(
+            state_transitions,
+            vec!["command_enterControl", "command_start", "command_enable"]
+        );
+    }
+
+    #[test]
+    fn compute_state_transitions_enabled_offline_order() {
+        let state_transitions = compute_state_transitions(State::Enabled, State::Offline).unwrap();
+
+        assert_eq!(
+            state_transitions,
+            vec!["command_disable", "command_standby", "command_exitControl"]
+        );
+    }
+
+    #[test]
+    fn compute_state_transitions_offline_standby_round_trip() {
+        assert_eq!(
+            compute_state_transitions(State::Offline, State::Standby).unwrap(),
+            vec!["command_enterControl"]
+        );
+        assert_eq!(
+            compute_state_transitions(State::Standby, State::Offline).unwrap(),
+            vec!["command_exitControl"]
+        );
+    }
+
+    #[test]
+    fn compute_state_transitions_fault_offline() {
+        let state_transitions = compute_state_transitions(State::Fault, State::Offline).unwrap();
+
+        assert_eq!(
+            state_transitions,
+            vec!["command_standby", "command_exitControl"]
+        );
+    }
+
+    #[test]
+    fn valid_transitions_from_disabled() {
+        assert_eq!(
+            valid_transitions_from(State::Disabled),
+            vec![
+                (State::Enabled, "command_enable".to_string()),
+                (State::Standby, "command_standby".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_transitions_from_fault_and_invalid() {
+        assert_eq!(
+            valid_transitions_from(State::Fault),
+            vec![(State::Standby, "command_standby".to_string())]
+        );
+        assert!(valid_transitions_from(State::Invalid).is_empty());
+    }
+
+    #[test]
+    fn valid_transitions_match_compute_state_transitions() {
+        for state in [
+            State::Offline,
+            State::Standby,
+            State::Disabled,
+            State::Enabled,
+        ] {
+            for (next_state, command) in valid_transitions_from(state) {
+                assert_eq!(
+                    compute_state_transitions(state, next_state).unwrap(),
+                    vec![command]
+                );
+            }
+        }
+    }
 }
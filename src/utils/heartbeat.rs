@@ -0,0 +1,86 @@
+//! Publish the `heartbeat` event in the background.
+//!
+//! Components announce they are alive by publishing `logevent_heartbeat`
+//! periodically. [start_heartbeat] runs that loop in a task, so both full
+//! CSCs and bare [Controller](crate::controller::Controller)s can use it.
+
+use tokio::{
+    sync::watch,
+    task,
+    time::{sleep, Duration},
+};
+
+use crate::{
+    error::errors::{SalObjError, SalObjResult},
+    generics::heartbeat::Heartbeat,
+    topics::{base_sal_topic::BaseSALTopic, write_topic::WriteTopic},
+};
+
+/// Handle to a running heartbeat task.
+pub struct HeartbeatHandle {
+    /// Request the heartbeat loop to stop after the current write.
+    stop: watch::Sender<bool>,
+    task: task::JoinHandle<()>,
+}
+
+impl HeartbeatHandle {
+    /// Has the heartbeat loop finished?
+    ///
+    /// The loop only finishes on its own if writing a heartbeat fails.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Stop the heartbeat task and wait for it to finish.
+    ///
+    /// The heartbeat loop is asked to stop rather than aborted, so it exits
+    /// cleanly between writes.
+    pub async fn stop(self) -> SalObjResult<()> {
+        let _ = self.stop.send(true);
+        self.task.await.map_err(SalObjError::from_error)
+    }
+}
+
+/// Publish a heartbeat with `heartbeat_writer` every `interval`, starting
+/// right away.
+pub fn start_heartbeat(
+    mut heartbeat_writer: WriteTopic<'static>,
+    interval: Duration,
+) -> HeartbeatHandle {
+    let (stop, mut stop_receiver) = watch::channel(false);
+
+    let task = task::spawn(async move {
+        let origin = heartbeat_writer.get_origin();
+        let identity = heartbeat_writer.get_identity();
+        let sal_index = heartbeat_writer.get_index();
+        // Toggled on every heartbeat so monitors can tell samples apart.
+        let mut heartbeat = false;
+        loop {
+            let seq_num = heartbeat_writer.get_seq_num();
+            heartbeat = !heartbeat;
+
+            let heartbeat_topic = Heartbeat::default()
+                .with_heartbeat(heartbeat)
+                .with_timestamps()
+                .with_sal_index(sal_index)
+                .with_private_origin(origin)
+                .with_private_identity(&identity)
+                .with_private_seq_num(seq_num);
+            let write_res = heartbeat_writer
+                .write_typed::<Heartbeat>(&heartbeat_topic)
+                .await;
+            if write_res.is_err() {
+                log::error!("Failed to write heartbeat data {write_res:?}.");
+                break;
+            }
+            // Only stop between writes, so a heartbeat is never dropped
+            // half way through.
+            tokio::select! {
+                _ = sleep(interval) => {}
+                _ = stop_receiver.changed() => break,
+            }
+        }
+    });
+
+    HeartbeatHandle { stop, task }
+}
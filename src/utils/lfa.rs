@@ -0,0 +1,190 @@
+//! Utilities to fetch Large File Objects (LFA).
+//!
+//! Components announce large files, like configurations or images, with the
+//! `largeFileObjectAvailable` event, which holds the URL of the object and
+//! its MD5 checksum. Objects are fetched over `http://` or `https://`.
+
+use std::time::Duration;
+
+use crate::error::errors::{SalObjError, SalObjResult};
+
+/// Maximum time to fetch an object.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum size of an object.
+const MAX_RESPONSE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Fetch the object at `url` with an HTTP GET and return its contents.
+///
+/// Fail if the server does not answer in time or the object is larger
+/// than 100 MiB.
+pub async fn fetch(url: &str) -> SalObjResult<Vec<u8>> {
+    fetch_with_limits(url, FETCH_TIMEOUT, MAX_RESPONSE_SIZE).await
+}
+
+/// Same as [fetch] with the given time and size limits.
+async fn fetch_with_limits(
+    url: &str,
+    fetch_timeout: Duration,
+    max_response_size: u64,
+) -> SalObjResult<Vec<u8>> {
+    let to_error = |error: reqwest::Error| {
+        if error.is_timeout() {
+            SalObjError::new(&format!(
+                "Timed out waiting for {url} after {fetch_timeout:?}."
+            ))
+        } else {
+            SalObjError::new(&format!("Failed to fetch {url}: {error}"))
+        }
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(fetch_timeout)
+        .build()
+        .map_err(SalObjError::from_error)?;
+
+    let mut response = client.get(url).send().await.map_err(to_error)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(SalObjError::new(&format!(
+            "Failed to fetch {url}: HTTP status {status}."
+        )));
+    }
+
+    let too_large = || {
+        SalObjError::new(&format!(
+            "Response from {url} larger than {max_response_size} bytes."
+        ))
+    };
+
+    if response.content_length().unwrap_or_default() > max_response_size {
+        return Err(too_large());
+    }
+
+    // The content length may be missing, so also check while reading.
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(to_error)? {
+        data.extend_from_slice(&chunk);
+        if data.len() as u64 > max_response_size {
+            return Err(too_large());
+        }
+    }
+
+    Ok(data)
+}
+
+/// Fetch the object at `url` and verify its contents match the MD5
+/// `check_sum`, as published in the `checkSum` field of
+/// `largeFileObjectAvailable`.
+pub async fn fetch_verified(url: &str, check_sum: &str) -> SalObjResult<Vec<u8>> {
+    let data = fetch(url).await?;
+    verify_check_sum(&data, check_sum)?;
+    Ok(data)
+}
+
+/// Verify the MD5 checksum of `data`.
+///
+/// The checksum is the hexadecimal digest, compared ignoring case.
+pub fn verify_check_sum(data: &[u8], check_sum: &str) -> SalObjResult<()> {
+    let digest = format!("{:x}", md5::compute(data));
+
+    if digest.eq_ignore_ascii_case(check_sum.trim()) {
+        Ok(())
+    } else {
+        Err(SalObjError::new(&format!(
+            "Checksum mismatch: expected {check_sum}, got {digest}."
+        )))
+    }
+}
+
+/// Serve a single HTTP response on a local port and return the URL of
+/// `path` on it.
+#[cfg(test)]
+pub(crate) async fn serve_once(status: &str, body: &'static [u8], path: &str) -> String {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let header = format!(
+        "HTTP/1.0 {status}\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    );
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).await.unwrap();
+        stream.write_all(header.as_bytes()).await.unwrap();
+        stream.write_all(body).await.unwrap();
+    });
+
+    format!("http://{address}{path}")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn verify_check_sum_md5() {
+        let check_sum = format!("{:x}", md5::compute(b"lfa data"));
+
+        assert!(verify_check_sum(b"lfa data", &check_sum).is_ok());
+        assert!(verify_check_sum(b"lfa data", &check_sum.to_uppercase()).is_ok());
+        assert!(verify_check_sum(b"other data", &check_sum).is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_verified_object() {
+        let body = b"name: test\nwait_time: 1.5\n";
+        let url = serve_once("200 OK", body, "/config/_init.yaml").await;
+        let check_sum = format!("{:x}", md5::compute(body));
+
+        let data = fetch_verified(&url, &check_sum).await.unwrap();
+
+        assert_eq!(data, body);
+    }
+
+    #[tokio::test]
+    async fn fetch_verified_bad_check_sum() {
+        let url = serve_once("200 OK", b"corrupted", "/object").await;
+        let check_sum = format!("{:x}", md5::compute(b"original"));
+
+        assert!(fetch_verified(&url, &check_sum).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_stalled_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/object", listener.local_addr().unwrap());
+
+        // Accept the connection but never answer.
+        let server = tokio::spawn(async move { listener.accept().await });
+
+        let error = fetch_with_limits(&url, Duration::from_millis(100), MAX_RESPONSE_SIZE)
+            .await
+            .unwrap_err();
+
+        assert!(error.get_error_message().starts_with("Timed out waiting"));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn fetch_response_too_large() {
+        let url = serve_once("200 OK", b"0123456789", "/object").await;
+
+        assert!(fetch_with_limits(&url, FETCH_TIMEOUT, 4).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_not_found() {
+        let url = serve_once("404 Not Found", b"", "/missing").await;
+
+        assert!(fetch(&url).await.is_err());
+    }
+}
@@ -1,8 +1,16 @@
 //! Sub-module to host all utility tools.
 
+pub mod avro_utils;
 pub mod cli;
 pub mod command_ack;
+#[cfg(feature = "tracing")]
+pub mod command_span;
+pub mod config;
 pub mod csc;
+pub mod heartbeat;
+pub mod lfa;
+pub mod replay;
 pub mod schema_utils;
+pub mod testing;
 pub mod types;
 pub mod xml_utils;
@@ -0,0 +1,210 @@
+//! Replay samples recorded in Avro container files.
+//!
+//! Samples are republished on their topic at a multiple of the cadence they
+//! were originally published at, computed from `private_sndStamp`. This is
+//! useful to reproduce field conditions.
+
+use std::{fs::File, path::Path, time::Duration};
+
+use apache_avro::{types::Value, Reader};
+use tokio::time::sleep;
+
+use crate::{
+    domain::Domain,
+    error::errors::{SalObjError, SalObjResult},
+    sal_info::SalInfo,
+    topics::{base_topic::BaseTopic, read_topic::ReadTopic, write_topic::WriteTopic},
+};
+
+/// Read all the samples in an Avro container file, in file order.
+pub fn read_samples(path: &Path) -> SalObjResult<Vec<Value>> {
+    let file = File::open(path).map_err(|error| {
+        SalObjError::new(&format!("Failed to open {}: {error}", path.display()))
+    })?;
+    let reader = Reader::new(file).map_err(SalObjError::from_error)?;
+
+    reader
+        .map(|sample| sample.map_err(SalObjError::from_error))
+        .collect()
+}
+
+/// Compute how long to wait before publishing each sample.
+///
+/// The first sample is published right away, each of the following ones
+/// after the time elapsed between its `private_sndStamp` and the latest one
+/// seen so far, divided by `speed`. Samples keep their order; if a timestamp
+/// is missing or goes back in time the sample is published without waiting.
+pub fn schedule(samples: Vec<Value>, speed: f64) -> SalObjResult<Vec<(Duration, Value)>> {
+    if speed <= 0.0 || !speed.is_finite() {
+        return Err(SalObjError::new(&format!(
+            "Invalid replay speed {speed}. Must be a positive number."
+        )));
+    }
+
+    let mut previous_stamp: Option<f64> = None;
+
+    Ok(samples
+        .into_iter()
+        .map(|sample| {
+            let stamp = get_snd_stamp(&sample);
+            let delay = match (previous_stamp, stamp) {
+                (Some(previous_stamp), Some(stamp)) if stamp > previous_stamp => {
+                    Duration::from_secs_f64((stamp - previous_stamp) / speed)
+                }
+                _ => Duration::ZERO,
+            };
+            if let Some(stamp) = stamp {
+                previous_stamp = Some(previous_stamp.map_or(stamp, |previous| previous.max(stamp)));
+            }
+            (delay, sample)
+        })
+        .collect())
+}
+
+/// Replay the samples of `topic_name` stored in the Avro container file at
+/// `path`, at `speed` times their original cadence.
+///
+/// The private fields and `salIndex` are filled in by the writer, as for any
+/// other sample. Return the number of samples published.
+pub async fn replay_file(
+    domain: &Domain,
+    sal_info: &SalInfo,
+    topic_name: &str,
+    path: &Path,
+    speed: f64,
+) -> SalObjResult<usize> {
+    let samples = schedule(read_samples(path)?, speed)?;
+    let mut writer = WriteTopic::new(topic_name, sal_info, domain);
+    let schema = writer.get_schema().clone();
+
+    let mut n_samples = 0;
+    for (delay, sample) in samples {
+        sleep(delay).await;
+
+        let mut record = WriteTopic::make_data_type(&schema)
+            .ok_or_else(|| SalObjError::new(&format!("Failed to create {topic_name} record.")))?;
+        if let Value::Record(fields) = sample {
+            for (field, value) in fields {
+                record.put(&field, value);
+            }
+        }
+        writer.write(&mut record).await?;
+        n_samples += 1;
+    }
+    Ok(n_samples)
+}
+
+/// Get the `private_sndStamp` of a sample, if any.
+fn get_snd_stamp(sample: &Value) -> Option<f64> {
+    if let Value::Record(fields) = sample {
+        fields.iter().find_map(|(field, value)| {
+            match (field.as_str(), ReadTopic::unwrap_union(value)) {
+                ("private_sndStamp", Value::Double(stamp)) => Some(*stamp),
+                _ => None,
+            }
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csc::test_csc::topics::scalars::Scalars;
+    use apache_avro::{to_value, Writer};
+    use std::{fs, process};
+
+    fn make_sample(int0: i32, snd_stamp: f64) -> Value {
+        let mut scalars = Scalars::default();
+        scalars.int0 = int0;
+        match to_value(scalars).unwrap() {
+            Value::Record(fields) => Value::Record(
+                fields
+                    .into_iter()
+                    .map(|(field, value)| match field.as_str() {
+                        "private_sndStamp" => (field, Value::Double(snd_stamp)),
+                        _ => (field, value),
+                    })
+                    .collect(),
+            ),
+            _ => panic!("Scalars did not serialize to a record."),
+        }
+    }
+
+    fn get_int0(sample: &Value) -> Option<i32> {
+        if let Value::Record(fields) = sample {
+            fields
+                .iter()
+                .find_map(|(field, value)| match (field.as_str(), value) {
+                    ("int0", Value::Int(int0)) => Some(*int0),
+                    _ => None,
+                })
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn replay_schedule_from_file() {
+        let sal_info = SalInfo::new("Test", 1).unwrap();
+        let schema = sal_info
+            .get_topic_info("scalars")
+            .and_then(|topic_info| topic_info.get_schema_ref())
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("salobj_replay_{}.avro", process::id()));
+        let mut writer = Writer::new(schema, Vec::new());
+        for (int0, snd_stamp) in [(1, 100.0), (2, 101.0), (3, 103.0)] {
+            let sample = make_sample(int0, snd_stamp).resolve(schema).unwrap();
+            writer.append(sample).unwrap();
+        }
+        fs::write(&path, writer.into_inner().unwrap()).unwrap();
+
+        let samples = read_samples(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let samples = schedule(samples, 2.0).unwrap();
+
+        let replayed: Vec<(Duration, Option<i32>)> = samples
+            .iter()
+            .map(|(delay, sample)| (*delay, get_int0(sample)))
+            .collect();
+
+        assert_eq!(
+            replayed,
+            vec![
+                (Duration::ZERO, Some(1)),
+                (Duration::from_millis(500), Some(2)),
+                (Duration::from_secs(1), Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn schedule_out_of_order_stamps() {
+        let samples = vec![
+            make_sample(1, 10.0),
+            make_sample(2, 9.0),
+            make_sample(3, 11.0),
+        ];
+
+        let delays: Vec<Duration> = schedule(samples, 1.0)
+            .unwrap()
+            .into_iter()
+            .map(|(delay, _)| delay)
+            .collect();
+
+        assert_eq!(
+            delays,
+            vec![Duration::ZERO, Duration::ZERO, Duration::from_secs(1)]
+        );
+    }
+
+    #[test]
+    fn schedule_invalid_speed() {
+        assert!(schedule(Vec::new(), 0.0).is_err());
+        assert!(schedule(Vec::new(), -1.0).is_err());
+        assert!(schedule(Vec::new(), f64::NAN).is_err());
+    }
+}
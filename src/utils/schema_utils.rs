@@ -1,6 +1,6 @@
 //! Utilities to read schema files for a specific component.
 
-use crate::error::errors::SalObjError;
+use crate::error::errors::{SalObjError, SalObjResult};
 
 use serde_json;
 use std::collections::HashMap;
@@ -50,6 +50,35 @@ pub fn glob_schema_files(name: &str) -> Result<HashMap<String, String>, Box<dyn
         .collect())
 }
 
+/// Replace the schemas of a component with the ones in `override_dir`.
+///
+/// Only files named `<name>_<topic>.json` are used; each one replaces (or
+/// adds) the schema of that topic. This is meant for debugging schema
+/// mismatches without changing the schema directory.
+pub fn apply_schema_overrides(
+    topic_schemas: &mut HashMap<String, String>,
+    override_dir: &Path,
+    name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let prefix = format!("{name}_");
+
+    for entry in override_dir.read_dir()? {
+        let path = entry?.path();
+        let topic_name = match (
+            path.file_stem().and_then(|stem| stem.to_str()),
+            path.extension().and_then(|extension| extension.to_str()),
+        ) {
+            (Some(topic_name), Some("json")) if topic_name.starts_with(&prefix) => {
+                topic_name.to_owned()
+            }
+            _ => continue,
+        };
+        log::info!("Overriding schema of {topic_name} with {path:?}.");
+        topic_schemas.insert(topic_name, fs::read_to_string(&path)?);
+    }
+    Ok(())
+}
+
 pub fn parse_hash_table(hash_table: &str) -> Result<HashMap<String, String>, Box<dyn Error>> {
     let hash_table_json = serde_json::from_str(hash_table)?;
 
@@ -69,26 +98,157 @@ pub fn parse_hash_table(hash_table: &str) -> Result<HashMap<String, String>, Box
     }
 }
 
+/// Parse a list of enumeration items, e.g. `["Zero=0", "One", "Four=0x04"]`,
+/// into `(name, value)` pairs.
+///
+/// Values may be decimal or hexadecimal (with a `0x` prefix). Items without a
+/// value take the value of the previous item plus one, starting at zero.
+pub fn parse_enum_items(items: &[serde_json::Value]) -> SalObjResult<Vec<(String, i64)>> {
+    let mut next_value = 0;
+
+    items
+        .iter()
+        .map(|item| {
+            let item = item
+                .as_str()
+                .ok_or_else(|| SalObjError::new(&format!("Invalid enumeration item {item}.")))?;
+
+            let (name, value) = match item.split_once('=') {
+                Some((name, value)) => (name.trim(), parse_enum_value(value.trim())?),
+                None => (item.trim(), next_value),
+            };
+            next_value = value + 1;
+
+            Ok((name.to_owned(), value))
+        })
+        .collect()
+}
+
+fn parse_enum_value(value: &str) -> SalObjResult<i64> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex_value) => i64::from_str_radix(hex_value, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| SalObjError::new(&format!("Invalid enumeration value {value}.")))
+}
+
+/// Parse a `<name>_global_enums` schema, which maps enumeration names to
+/// their items.
+pub fn parse_global_enums(global_enums: &str) -> SalObjResult<HashMap<String, Vec<(String, i64)>>> {
+    let global_enums: serde_json::Value =
+        serde_json::from_str(global_enums).map_err(SalObjError::from_error)?;
+
+    global_enums
+        .as_object()
+        .ok_or_else(|| SalObjError::new("Global enumerations must be a json object."))?
+        .iter()
+        .map(|(enum_name, items)| {
+            let items = items.as_array().ok_or_else(|| {
+                SalObjError::new(&format!("Items of enumeration {enum_name} must be a list."))
+            })?;
+            Ok((enum_name.to_owned(), parse_enum_items(items)?))
+        })
+        .collect()
+}
+
+/// Parse a `<name>_field_enums` schema, which maps topic names to field
+/// names to their items.
+///
+/// Return the enumerations keyed by `<topic>.<field>`, using the SAL topic
+/// names as written in the schema.
+pub fn parse_field_enums(field_enums: &str) -> SalObjResult<HashMap<String, Vec<(String, i64)>>> {
+    let field_enums: serde_json::Value =
+        serde_json::from_str(field_enums).map_err(SalObjError::from_error)?;
+
+    let mut enum_definitions = HashMap::new();
+
+    for (topic_name, fields) in field_enums
+        .as_object()
+        .ok_or_else(|| SalObjError::new("Field enumerations must be a json object."))?
+    {
+        for (field_name, items) in fields.as_object().ok_or_else(|| {
+            SalObjError::new(&format!(
+                "Field enumerations of {topic_name} must be a json object."
+            ))
+        })? {
+            let items = items.as_array().ok_or_else(|| {
+                SalObjError::new(&format!(
+                    "Items of enumeration {topic_name}.{field_name} must be a list."
+                ))
+            })?;
+            enum_definitions.insert(
+                format!("{topic_name}.{field_name}"),
+                parse_enum_items(items)?,
+            );
+        }
+    }
+    Ok(enum_definitions)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::utils::testing::with_env_vars;
     use apache_avro::Schema;
     use std::collections::HashSet;
 
-    macro_rules! set_test_lsst_schema_path {
-        () => {
-            env::set_var(
+    /// Read the schemas of the Test component from the test data.
+    fn glob_test_schema_files() -> Result<HashMap<String, String>, Box<dyn Error>> {
+        with_env_vars(
+            &[(
                 "LSST_SCHEMA_PATH",
-                concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/"),
-            );
-        };
+                Some(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/")),
+            )],
+            || glob_schema_files("Test"),
+        )
+    }
+
+    #[test]
+    fn parse_enum_items_values() {
+        let items = serde_json::json!(["A", "B=5", "C", "D=0x10", "E=007"]);
+
+        assert_eq!(
+            parse_enum_items(items.as_array().unwrap()).unwrap(),
+            vec![
+                ("A".to_owned(), 0),
+                ("B".to_owned(), 5),
+                ("C".to_owned(), 6),
+                ("D".to_owned(), 16),
+                ("E".to_owned(), 7),
+            ]
+        );
+        assert!(parse_enum_items(serde_json::json!(["A=one"]).as_array().unwrap()).is_err());
+    }
+
+    #[test]
+    fn apply_schema_overrides_scalars() {
+        let mut topic_schemas = glob_test_schema_files().unwrap();
+        let n_topics = topic_schemas.len();
+
+        assert!(!topic_schemas["Test_scalars"].contains("override0"));
+
+        apply_schema_overrides(
+            &mut topic_schemas,
+            Path::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/data/schema_overrides/"
+            )),
+            "Test",
+        )
+        .unwrap();
+
+        assert_eq!(topic_schemas.len(), n_topics);
+        assert!(topic_schemas["Test_scalars"].contains("override0"));
+        assert!(!topic_schemas["Test_arrays"].contains("override0"));
     }
 
     #[test]
     fn test_parse_hash_map() {
-        set_test_lsst_schema_path!();
-        let topic_schemas = glob_schema_files("Test").unwrap();
+        let topic_schemas = glob_test_schema_files().unwrap();
         let hash_map_table_str = topic_schemas.get("Test_hash_table").unwrap();
 
         let hash_table = parse_hash_table(&hash_map_table_str).unwrap();
@@ -101,8 +261,7 @@ mod tests {
 
     #[test]
     fn test_glob_schema_files() {
-        set_test_lsst_schema_path!();
-        let topic_schemas = glob_schema_files("Test").unwrap();
+        let topic_schemas = glob_test_schema_files().unwrap();
 
         let expected_topics = HashSet::from([
             String::from("Test_ackcmd"),
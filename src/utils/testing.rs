@@ -0,0 +1,157 @@
+//! Utilities to write tests against a running CSC.
+
+use tokio::task::JoinHandle;
+
+use crate::{
+    csc::{
+        base_csc::{BaseCSC, ShutdownHandle},
+        test_csc::csc::TestCSC,
+    },
+    domain::Domain,
+    error::errors::{SalObjError, SalObjResult},
+    remote::Remote,
+};
+
+/// A running CSC together with a [Remote] connected to it.
+///
+/// See [loopback].
+pub struct Loopback {
+    /// Remote connected to the CSC.
+    pub remote: Remote<'static>,
+    /// Domain used to create the remote.
+    pub domain: Domain,
+    shutdown_handle: ShutdownHandle,
+    csc_task: JoinHandle<SalObjResult<()>>,
+}
+
+impl Loopback {
+    /// Request the CSC to shut down and wait for it to finish.
+    pub async fn shutdown(self) -> SalObjResult<()> {
+        self.shutdown_handle.shutdown();
+        self.join().await
+    }
+
+    /// Wait for the CSC to finish on its own, e.g. after `exitControl`.
+    pub async fn join(self) -> SalObjResult<()> {
+        self.csc_task.await.map_err(SalObjError::from_error)?
+    }
+}
+
+/// Start a CSC for `component` with the given `index` and connect a
+/// [Remote] to it.
+///
+/// The CSC runs in a background task, in its initial state, ready for
+/// command and event assertions. Only the `Test` component, served by
+/// [TestCSC], is supported. Use [Loopback::shutdown] to stop the CSC.
+pub async fn loopback(component: &str, index: isize) -> SalObjResult<Loopback> {
+    if component != "Test" {
+        return Err(SalObjError::new(&format!(
+            "No loopback CSC for component {component}."
+        )));
+    }
+
+    loopback_with_csc(TestCSC::new(index)?).await
+}
+
+/// Start `csc` and connect a [Remote] to it.
+///
+/// Like [loopback], for a [TestCSC] that needs to be set up before it
+/// starts, e.g. with a simulation mode or a command authorizer.
+pub async fn loopback_with_csc(mut csc: TestCSC<'static>) -> SalObjResult<Loopback> {
+    let index = csc.get_index();
+    csc.start().await;
+
+    let shutdown_handle = csc
+        .shutdown_signal()
+        .ok_or_else(|| SalObjError::new("TestCSC cannot be shut down."))?;
+    let csc_task = tokio::spawn(async move { csc.run().await });
+
+    let mut domain = Domain::new();
+    let remote = Remote::from_name_index(&mut domain, "Test", index)?;
+
+    Ok(Loopback {
+        remote,
+        domain,
+        shutdown_handle,
+        csc_task,
+    })
+}
+
+/// Serialize the tests that change environment variables.
+#[cfg(test)]
+static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Run `f` with the environment variables in `vars` set, or removed if
+/// `None`, restoring their previous values afterwards.
+///
+/// Every test that changes environment variables must do so through this
+/// function, since tests run in parallel. Assert on the value returned by
+/// `f`, so the environment is restored even if the assertion fails.
+#[cfg(test)]
+pub(crate) fn with_env_vars<T>(vars: &[(&str, Option<&str>)], f: impl FnOnce() -> T) -> T {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    let saved: Vec<(&str, Option<String>)> = vars
+        .iter()
+        .map(|(name, _)| (*name, std::env::var(name).ok()))
+        .collect();
+
+    let set_var = |name: &str, value: Option<&str>| match value {
+        Some(value) => std::env::set_var(name, value),
+        None => std::env::remove_var(name),
+    };
+    for (name, value) in vars {
+        set_var(name, *value);
+    }
+    let result = f();
+    for (name, value) in saved {
+        set_var(name, value.as_deref());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        generics::start::Start,
+        sal_enums::{SalRetCode, State},
+    };
+
+    #[tokio::test]
+    async fn loopback_unknown_component() {
+        assert!(loopback("Sensor", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn loopback_command_round_trip() {
+        let mut loopback = loopback("Test", 143).await.unwrap();
+        let timeout = Duration::from_secs(10);
+
+        loopback
+            .remote
+            .wait_for_summary_state(State::Standby, timeout)
+            .await
+            .unwrap();
+
+        let start: Start = loopback.remote.get_command_data("command_start").unwrap();
+        let ack = loopback
+            .remote
+            .run_command_typed("command_start", &start, timeout, true)
+            .await
+            .unwrap();
+
+        assert_eq!(*ack.get_ack_enum(), SalRetCode::CmdComplete);
+        assert_eq!(
+            loopback
+                .remote
+                .wait_for_summary_state(State::Disabled, timeout)
+                .await
+                .unwrap(),
+            State::Disabled
+        );
+
+        loopback.shutdown().await.unwrap();
+    }
+}
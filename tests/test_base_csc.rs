@@ -1,18 +1,28 @@
 use apache_avro::{from_value, types::Value};
 use salobj::{
-    csc::test_csc::{
-        csc::TestCSC,
-        topics::{arrays::Arrays, scalars::Scalars, wait::Wait},
+    csc::{
+        base_csc::BaseCSC,
+        test_csc::{
+            csc::TestCSC,
+            topics::{arrays::Arrays, scalars::Scalars, wait::Wait},
+        },
     },
     domain::Domain,
-    generics::summary_state::SummaryState,
+    generics::{
+        heartbeat::Heartbeat, log_message::LogMessage, start::Start, summary_state::SummaryState,
+    },
     remote::Remote,
     sal_enums::{SalRetCode, State},
-    topics::{base_sal_topic::BaseSALTopic, base_topic::BaseTopic, write_topic::WriteTopic},
+    sal_info::SalInfo,
+    single_topic_reader::SingleTopicReader,
+    topics::{
+        base_sal_topic::BaseSALTopic, base_topic::BaseTopic, read_topic::ReadTopic,
+        write_topic::WriteTopic,
+    },
+    utils::testing::{loopback, loopback_with_csc},
 };
 use simple_logger::SimpleLogger;
 use std::time::Duration;
-use tokio::task;
 
 macro_rules! assert_command_fails {
     ($cmd:expr, $remote:ident, $current_state:ident) => {
@@ -54,23 +64,33 @@ macro_rules! process_command {
     };
 }
 
+/// Create and start the CSC with `index`, without running it.
+async fn start_csc(index: isize) -> TestCSC<'static> {
+    let mut test_csc = TestCSC::new(index).unwrap();
+    test_csc.start().await;
+    test_csc
+}
+
+/// Bring the CSC behind `remote` from standby to enabled.
+async fn enable(remote: &mut Remote<'_>, timeout: Duration) {
+    remote
+        .run_state_command("command_start", timeout)
+        .await
+        .unwrap();
+    remote
+        .run_state_command("command_enable", timeout)
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 async fn test_state_transition() {
     SimpleLogger::new().init().unwrap();
 
     log::set_max_level(log::LevelFilter::Debug);
 
-    let mut test_csc = TestCSC::new(123).unwrap();
-
-    test_csc.start().await;
-
-    let _ = task::spawn(async move {
-        println!("Running CSC.");
-        let _ = test_csc.run().await;
-    });
-
-    let mut domain = Domain::new();
-    let mut remote = Remote::from_name_index(&mut domain, "Test", 123).unwrap();
+    let mut loopback = loopback("Test", 123).await.unwrap();
+    let remote = &mut loopback.remote;
 
     let timeout = Duration::from_secs(10);
 
@@ -123,4 +143,630 @@ async fn test_state_transition() {
 
         assert_command_fails!(cmd, remote, current_state);
     }
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_wait_for_telemetry() {
+    let mut loopback = loopback("Test", 124).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    // Telemetry is only published after the CSC is started.
+    remote
+        .run_state_command("command_start", timeout)
+        .await
+        .unwrap();
+
+    let scalars = remote.wait_for_telemetry("scalars", timeout).await.unwrap();
+    let scalars = from_value::<Scalars>(&scalars).unwrap();
+
+    assert_eq!(scalars.get_sal_index(), 124);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_query_state() {
+    let mut loopback = loopback("Test", 125).await.unwrap();
+
+    let state = Remote::query_state(&mut loopback.domain, "Test", 125, Duration::from_secs(10))
+        .await
+        .unwrap();
+
+    assert_eq!(state, State::Standby);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_single_topic_reader_heartbeat() {
+    let mut loopback = loopback("Test", 126).await.unwrap();
+
+    let mut heartbeat_reader =
+        SingleTopicReader::new(&mut loopback.domain, "Test", 126, "logevent_heartbeat", 0).unwrap();
+
+    let heartbeat = heartbeat_reader
+        .pop_front(false, Duration::from_secs(10))
+        .await;
+
+    assert!(heartbeat.is_some());
+    assert_eq!(heartbeat_reader.get(), heartbeat);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_wait_for_any_summary_state() {
+    let mut loopback = loopback("Test", 127).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    assert_eq!(
+        remote
+            .wait_for_summary_state(State::Standby, timeout)
+            .await
+            .unwrap(),
+        State::Standby
+    );
+
+    remote
+        .run_state_command("command_start", timeout)
+        .await
+        .unwrap();
+
+    // The initial Standby sample is stale and must not be returned.
+    assert!(remote
+        .wait_for_summary_state(State::Standby, Duration::from_secs(1))
+        .await
+        .is_err());
+
+    let summary_state = remote
+        .wait_for_any_summary_state(&[State::Disabled, State::Enabled], timeout)
+        .await
+        .unwrap();
+
+    assert_eq!(summary_state, State::Disabled);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_heartbeat_toggles() {
+    let mut loopback = loopback("Test", 128).await.unwrap();
+
+    let mut heartbeat_reader =
+        SingleTopicReader::new(&mut loopback.domain, "Test", 128, "logevent_heartbeat", 0).unwrap();
+
+    let timeout = Duration::from_secs(10);
+
+    let first = heartbeat_reader.pop_front(false, timeout).await.unwrap();
+    let second = heartbeat_reader.pop_front(false, timeout).await.unwrap();
+
+    let first = from_value::<Heartbeat>(&first).unwrap();
+    let second = from_value::<Heartbeat>(&second).unwrap();
+
+    assert_eq!(
+        second.get_private_seq_num(),
+        first.get_private_seq_num() + 1
+    );
+    assert_ne!(first.get_heartbeat(), second.get_heartbeat());
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_wait_simulation_mode() {
+    let mut loopback = loopback_with_csc(TestCSC::new(130).unwrap().with_simulation_mode(1))
+        .await
+        .unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    enable(remote, timeout).await;
+
+    // In simulation mode the wait command completes immediately, so it
+    // finishes well before the requested duration.
+    let mut wait: Wait = remote.get_command_data("command_wait").unwrap();
+    wait.duration = 60.0;
+
+    let ack_cmd = remote
+        .run_command_typed("command_wait", &wait, timeout, true)
+        .await
+        .unwrap();
+
+    assert_eq!(*ack_cmd.get_ack_enum(), SalRetCode::CmdComplete);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_active_indices() {
+    let mut loopbacks = Vec::new();
+    for index in [132, 133] {
+        loopbacks.push(loopback("Test", index).await.unwrap());
+    }
+
+    let mut domain = Domain::new();
+    let mut remote = Remote::from_name_index(&mut domain, "Test", 0).unwrap();
+
+    let active_indices = remote.active_indices(Duration::from_secs(5)).await.unwrap();
+
+    assert!(active_indices.contains(&132));
+    assert!(active_indices.contains(&133));
+
+    for loopback in loopbacks {
+        loopback.shutdown().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_get_enum_event() {
+    let mut loopback = loopback("Test", 134).await.unwrap();
+
+    let summary_state: State = loopback
+        .remote
+        .get_enum_event("logevent_summaryState", Duration::from_secs(10))
+        .await
+        .unwrap();
+
+    assert_eq!(summary_state, State::Standby);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_collect_heartbeats() {
+    let mut loopback = loopback("Test", 135).await.unwrap();
+
+    let sal_info = SalInfo::new("Test", 135).unwrap();
+    loopback
+        .domain
+        .register_topics(&[sal_info.make_schema_registry_topic_name("logevent_heartbeat")])
+        .unwrap();
+
+    let mut heartbeat_reader = ReadTopic::new("logevent_heartbeat", &sal_info, &loopback.domain, 0);
+
+    let heartbeats = heartbeat_reader.collect(3, Duration::from_secs(10)).await;
+
+    assert_eq!(heartbeats.len(), 3);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_command_duration() {
+    let mut loopback = loopback("Test", 136).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    enable(remote, timeout).await;
+
+    let mut wait: Wait = remote.get_command_data("command_wait").unwrap();
+    wait.duration = 2.0;
+
+    let ack_cmd = remote
+        .run_command_typed("command_wait", &wait, timeout, true)
+        .await
+        .unwrap();
+
+    let duration = ack_cmd.get_duration().unwrap();
+
+    assert!(duration >= Duration::from_secs(2));
+    assert!(duration < Duration::from_secs(4));
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown_signal() {
+    let mut loopback = loopback("Test", 138).await.unwrap();
+
+    let mut heartbeat_reader =
+        SingleTopicReader::new(&mut loopback.domain, "Test", 138, "logevent_heartbeat", 0).unwrap();
+
+    assert!(heartbeat_reader
+        .pop_front(false, Duration::from_secs(10))
+        .await
+        .is_some());
+
+    tokio::time::timeout(Duration::from_secs(5), loopback.shutdown())
+        .await
+        .expect("CSC did not stop after shutdown was requested.")
+        .unwrap();
+
+    // Heartbeats written before the shutdown may still be in flight, after
+    // those no new heartbeat should arrive.
+    let mut n_late_heartbeats = 0;
+    while heartbeat_reader
+        .pop_front(false, Duration::from_secs(2))
+        .await
+        .is_some()
+    {
+        n_late_heartbeats += 1;
+        assert!(n_late_heartbeats < 3, "Heartbeats did not stop.");
+    }
+}
+
+#[tokio::test]
+async fn test_unimplemented_command() {
+    let mut loopback = loopback("Test", 139).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    // The TestCSC does not implement setLogLevel.
+    let cmd = "command_setLogLevel";
+    let schema = remote.get_command_schema(cmd).unwrap();
+    let mut record = WriteTopic::make_data_type(&schema).unwrap();
+    record.put("level", Value::Int(10));
+    record.put("subsystem", Value::String("".to_owned()));
+
+    let ack_cmd = remote
+        .run_command(cmd.to_string(), &mut record, Duration::from_secs(10), true)
+        .await
+        .unwrap_err();
+
+    assert_eq!(*ack_cmd.get_ack_enum(), SalRetCode::CmdFailed);
+    assert_eq!(
+        ack_cmd.get_result(),
+        "Command command_setLogLevel not implemented."
+    );
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_command_authorizer() {
+    let mut loopback = loopback_with_csc(
+        TestCSC::new(159)
+            .unwrap()
+            .with_command_authorizer(|command_name, _| command_name != "command_start"),
+    )
+    .await
+    .unwrap();
+
+    let ack_cmd = loopback
+        .remote
+        .start_with_config("", Duration::from_secs(10))
+        .await
+        .unwrap_err();
+
+    assert_eq!(*ack_cmd.get_ack_enum(), SalRetCode::CmdNoperm);
+    assert_eq!(
+        ack_cmd.get_result(),
+        "Not authorized to execute command_start."
+    );
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_exit_control_no_grace_period() {
+    let mut loopback = loopback_with_csc(
+        TestCSC::new(140)
+            .unwrap()
+            .with_exit_control_grace_period(Duration::ZERO),
+    )
+    .await
+    .unwrap();
+    let remote = &mut loopback.remote;
+
+    let cmd = "command_exitControl";
+    let schema = remote.get_command_schema(cmd).unwrap();
+    let mut record = WriteTopic::make_data_type(&schema).unwrap();
+
+    remote
+        .run_command(cmd.to_string(), &mut record, Duration::from_secs(10), true)
+        .await
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_millis(500), loopback.join())
+        .await
+        .expect("CSC did not exit promptly after exitControl.")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_mandatory_events() {
+    let mut loopback = loopback("Test", 141).await.unwrap();
+
+    loopback
+        .remote
+        .verify_mandatory_events(Duration::from_secs(10))
+        .await
+        .unwrap();
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stop_heartbeat() {
+    let mut test_csc = start_csc(144).await;
+
+    let mut domain = Domain::new();
+
+    let mut heartbeat_reader =
+        SingleTopicReader::new(&mut domain, "Test", 144, "logevent_heartbeat", 0).unwrap();
+
+    assert!(heartbeat_reader
+        .pop_front(false, Duration::from_secs(10))
+        .await
+        .is_some());
+
+    tokio::time::timeout(Duration::from_secs(5), test_csc.stop_heartbeat())
+        .await
+        .expect("Heartbeat task did not join after stop was requested.")
+        .unwrap();
+
+    // Every heartbeat written before the stop must be complete, and none
+    // should arrive after those.
+    let mut n_late_heartbeats = 0;
+    while let Some(heartbeat) = heartbeat_reader
+        .pop_front(false, Duration::from_secs(2))
+        .await
+    {
+        assert!(from_value::<Heartbeat>(&heartbeat).is_ok());
+        n_late_heartbeats += 1;
+        assert!(n_late_heartbeats < 3, "Heartbeats did not stop.");
+    }
+}
+
+#[tokio::test]
+async fn test_set_arrays_typed() {
+    let mut loopback = loopback("Test", 145).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    enable(remote, timeout).await;
+
+    let arrays: Arrays = remote.get_command_data("command_setArrays").unwrap();
+    let arrays = arrays
+        .with_boolean0([true, false, true, false, true])
+        .with_byte0([1, 2, 3, 4, 5])
+        .with_short0([-1, -2, -3, -4, -5])
+        .with_int0([10, 20, 30, 40, 50])
+        .with_unsigned_int0([1, 2, 3, 4, u32::MAX as u64])
+        .with_double0([0.5, 1.5, 2.5, 3.5, 4.5]);
+
+    let ack = remote
+        .run_command_typed("command_setArrays", &arrays, timeout, true)
+        .await
+        .unwrap();
+
+    assert_eq!(*ack.get_ack_enum(), SalRetCode::CmdComplete);
+
+    let event: Arrays = remote
+        .pop_event_front_typed("logevent_arrays", false, timeout)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(event.boolean0, arrays.boolean0);
+    assert_eq!(event.byte0, arrays.byte0);
+    assert_eq!(event.short0, arrays.short0);
+    assert_eq!(event.int0, arrays.int0);
+    assert_eq!(event.unsigned_int0, arrays.unsigned_int0);
+    assert_eq!(event.double0, arrays.double0);
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_start_with_config() {
+    let mut loopback = loopback("Test", 152).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    let ack = remote
+        .start_with_config("default.yaml", timeout)
+        .await
+        .unwrap();
+
+    assert_eq!(*ack.get_ack_enum(), SalRetCode::CmdComplete);
+
+    let log_message: LogMessage = remote
+        .pop_event_front_typed("logevent_logMessage", false, timeout)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        log_message.get_message(),
+        "Received default.yaml configuration override."
+    );
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown_aborts_queued_commands() {
+    let mut test_csc = start_csc(153).await;
+
+    let shutdown_handle = test_csc.shutdown_signal().unwrap();
+
+    let mut domain = Domain::new();
+    let mut remote = Remote::from_name_index(&mut domain, "Test", 153).unwrap();
+
+    let timeout = Duration::from_secs(30);
+    let start: Start = remote.get_command_data("command_start").unwrap();
+
+    // The CSC is not running yet, so the command stays queued until the CSC
+    // is shut down.
+    let (ack, run_result) = tokio::join!(
+        tokio::time::timeout(
+            Duration::from_secs(10),
+            remote.run_command_typed("command_start", &start, timeout, true),
+        ),
+        async {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            shutdown_handle.shutdown();
+            test_csc.run().await
+        }
+    );
+
+    run_result.unwrap();
+
+    let ack = ack
+        .expect("Queued command was not acknowledged on shutdown.")
+        .unwrap_err();
+
+    assert_eq!(*ack.get_ack_enum(), SalRetCode::CmdAborted);
+    assert_eq!(
+        ack.get_result(),
+        "CSC shutting down; command_start not executed."
+    );
+}
+
+#[tokio::test]
+async fn test_exit_control_stops_heartbeat() {
+    let mut loopback = loopback_with_csc(
+        TestCSC::new(154)
+            .unwrap()
+            .with_exit_control_grace_period(Duration::from_secs(3)),
+    )
+    .await
+    .unwrap();
+    let remote = &mut loopback.remote;
+
+    let cmd = "command_exitControl";
+    let schema = remote.get_command_schema(cmd).unwrap();
+    let mut record = WriteTopic::make_data_type(&schema).unwrap();
+
+    remote
+        .run_command(cmd.to_string(), &mut record, Duration::from_secs(10), true)
+        .await
+        .unwrap();
+
+    // Only heartbeats written after the command was acknowledged are read;
+    // the CSC is still running, in its grace period, but must be silent.
+    let mut heartbeat_reader =
+        SingleTopicReader::new(&mut loopback.domain, "Test", 154, "logevent_heartbeat", 0).unwrap();
+    assert!(heartbeat_reader
+        .pop_front(false, Duration::from_secs(2))
+        .await
+        .is_none());
+
+    let summary_state = remote
+        .pop_event_back("logevent_summaryState", false, Duration::from_secs(2))
+        .await
+        .unwrap()
+        .unwrap();
+    let summary_state = from_value::<SummaryState>(&summary_state).unwrap();
+    assert_eq!(summary_state.get_summary_state(), State::Offline);
+
+    loopback.join().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_read_recent_acks() {
+    let mut loopback = loopback("Test", 155).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    let start_ack = remote.start_with_config("", timeout).await.unwrap();
+
+    let schema = remote.get_command_schema("command_standby").unwrap();
+    let mut record = WriteTopic::make_data_type(&schema).unwrap();
+    let standby_ack = remote
+        .run_command("command_standby".to_owned(), &mut record, timeout, true)
+        .await
+        .unwrap();
+
+    let recent_acks = remote
+        .read_recent_acks(&loopback.domain, 10, Duration::from_secs(2))
+        .await
+        .unwrap();
+
+    for command_ack in [start_ack, standby_ack] {
+        assert!(recent_acks.iter().any(|recent_ack| {
+            recent_ack.get_seq_num() == command_ack.get_seq_num()
+                && recent_ack.get_identity() == command_ack.get_identity()
+                && *recent_ack.get_ack_enum() == SalRetCode::CmdComplete
+        }));
+    }
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_wait_duration() {
+    let mut loopback = loopback("Test", 156).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    enable(remote, timeout).await;
+
+    for duration in [0.0, -1.0] {
+        let mut wait: Wait = remote.get_command_data("command_wait").unwrap();
+        wait.duration = duration;
+
+        let ack_cmd = remote
+            .run_command_typed("command_wait", &wait, timeout, true)
+            .await
+            .unwrap_err();
+
+        assert_eq!(*ack_cmd.get_ack_enum(), SalRetCode::CmdFailed);
+        assert_eq!(
+            ack_cmd.get_result(),
+            format!("Invalid wait duration {duration}; must be positive and finite.")
+        );
+    }
+
+    for (duration, min_duration, max_duration) in [
+        (1.5, Duration::from_millis(1500), Duration::from_secs(3)),
+        (0.25, Duration::from_millis(250), Duration::from_millis(750)),
+    ] {
+        let mut wait: Wait = remote.get_command_data("command_wait").unwrap();
+        wait.duration = duration;
+
+        let ack_cmd = remote
+            .run_command_typed("command_wait", &wait, timeout, true)
+            .await
+            .unwrap();
+
+        let elapsed = ack_cmd.get_duration().unwrap();
+
+        assert_eq!(*ack_cmd.get_ack_enum(), SalRetCode::CmdComplete);
+        assert!(elapsed >= min_duration);
+        assert!(elapsed < max_duration);
+    }
+
+    loopback.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_run_state_command() {
+    let mut loopback = loopback("Test", 158).await.unwrap();
+    let remote = &mut loopback.remote;
+
+    let timeout = Duration::from_secs(10);
+
+    assert_eq!(
+        remote
+            .run_state_command("command_start", timeout)
+            .await
+            .unwrap(),
+        State::Disabled
+    );
+    assert_eq!(
+        remote
+            .run_state_command("command_enable", timeout)
+            .await
+            .unwrap(),
+        State::Enabled
+    );
+    assert!(remote
+        .run_state_command("command_enable", timeout)
+        .await
+        .is_err());
+
+    loopback.shutdown().await.unwrap();
 }
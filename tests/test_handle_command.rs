@@ -0,0 +1,108 @@
+//! Exercise the `handle_command!` macro from outside the salobj crate, with
+//! a minimal control loop that does not need a broker.
+
+use apache_avro::{from_value, to_value, types::Value};
+use handle_command::handle_command;
+use salobj::{
+    generics::empty_topic::EmptyTopic, sal_enums::SalRetCode, topics::base_sal_topic::BaseSALTopic,
+    utils::command_ack::CommandAck,
+};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+struct CmdData {
+    name: String,
+    data: Value,
+    cmdtype: i32,
+}
+
+type AckChannel = mpsc::Sender<CommandAck>;
+type CommandAckResult = Result<(CommandAck, AckChannel), String>;
+
+/// Control loop built on `handle_command!`, handling only `start`.
+#[derive(Default)]
+struct MacroCSC {
+    n_started: usize,
+}
+
+impl MacroCSC {
+    fn get_exit_control_grace_period(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    async fn do_exit_control(
+        &mut self,
+        data: &CmdData,
+        ack_channel: AckChannel,
+    ) -> CommandAckResult {
+        let command = from_value::<EmptyTopic>(&data.data).map_err(|error| error.to_string())?;
+        Ok((CommandAck::make_complete(command), ack_channel))
+    }
+
+    async fn do_start(&mut self, data: &CmdData, ack_channel: AckChannel) -> CommandAckResult {
+        let command = from_value::<EmptyTopic>(&data.data).map_err(|error| error.to_string())?;
+        self.n_started += 1;
+        Ok((CommandAck::make_complete(command), ack_channel))
+    }
+
+    async fn run(
+        &mut self,
+        mut command_receiver: mpsc::Receiver<(CmdData, AckChannel)>,
+    ) -> Result<(), String> {
+        while let Some((data, ack_channel)) = command_receiver.recv().await {
+            handle_command!("start");
+        }
+        Ok(())
+    }
+}
+
+fn make_command(name: &str, seq_num: i32, cmdtype: i32) -> CmdData {
+    CmdData {
+        name: name.to_owned(),
+        data: to_value(EmptyTopic::default().with_private_seq_num(seq_num)).unwrap(),
+        cmdtype,
+    }
+}
+
+#[tokio::test]
+async fn test_handle_command() {
+    let (command_sender, command_receiver) = mpsc::channel(10);
+    let (ack_sender, mut ack_receiver) = mpsc::channel(10);
+
+    for command in [
+        make_command("command_start", 1, 2),
+        make_command("command_setScalars", 2, 3),
+        make_command("command_exitControl", 3, 4),
+        make_command("command_start", 4, 2),
+    ] {
+        command_sender
+            .send((command, ack_sender.clone()))
+            .await
+            .unwrap();
+    }
+
+    let mut csc = MacroCSC::default();
+    csc.run(command_receiver).await.unwrap();
+
+    let start = ack_receiver.recv().await.unwrap();
+    assert_eq!(*start.get_ack_enum(), SalRetCode::CmdComplete);
+    assert_eq!(start.get_seq_num(), 1);
+    assert_eq!(start.get_cmdtype(), 2);
+
+    let not_implemented = ack_receiver.recv().await.unwrap();
+    assert_eq!(*not_implemented.get_ack_enum(), SalRetCode::CmdFailed);
+    assert_eq!(not_implemented.get_seq_num(), 2);
+    assert_eq!(not_implemented.get_cmdtype(), 3);
+    assert_eq!(
+        not_implemented.get_result(),
+        "Command command_setScalars not implemented."
+    );
+
+    let exit_control = ack_receiver.recv().await.unwrap();
+    assert_eq!(*exit_control.get_ack_enum(), SalRetCode::CmdComplete);
+    assert_eq!(exit_control.get_cmdtype(), 4);
+
+    // The loop ends after exitControl, so the last start is not handled.
+    assert_eq!(csc.n_started, 1);
+    assert!(ack_receiver.try_recv().is_err());
+}